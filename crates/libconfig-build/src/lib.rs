@@ -0,0 +1,427 @@
+//! Generates typed settings structs from a config file in `build.rs`.
+//!
+//! ```ignore
+//! // build.rs
+//! libconfig_build::generate_to_out_dir("app.cfg", &Default::default()).unwrap();
+//!
+//! // src/main.rs, with libconfig's `derive` feature enabled
+//! include!(concat!(env!("OUT_DIR"), "/app.rs"));
+//! ```
+
+use libconfig::{Config, LibconfigError, Value};
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+
+#[derive(Debug, Clone)]
+pub struct CodegenOptions {
+    /// Name of the struct generated for the root group.
+    pub struct_name: String,
+    pub include_dir: Option<String>,
+    /// Read the input as a schema: string values name the type of the
+    /// setting (`bool`, `int`, `int64`, `float` or `string`, with a trailing
+    /// `?` for optional settings) instead of being sample values.
+    pub schema: bool,
+}
+
+impl Default for CodegenOptions {
+    fn default() -> Self {
+        CodegenOptions {
+            struct_name: String::from("Settings"),
+            include_dir: None,
+            schema: false,
+        }
+    }
+}
+
+/// Generates the structs for the config file at `input`, and a
+/// `load(&Config)` function returning the root struct.
+pub fn generate(input: &str, options: &CodegenOptions) -> Result<String, LibconfigError> {
+    read(input, options).and_then(|cfg| generate_from_value(&cfg.to_value()?, options))
+}
+
+/// Writes the code for `input` to `$OUT_DIR/<file stem>.rs` and tells cargo
+/// to rerun the build script when the config or its includes change.
+pub fn generate_to_out_dir(input: &str, options: &CodegenOptions) -> Result<(), LibconfigError> {
+    let out_dir =
+        std::env::var("OUT_DIR").map_err(|_| LibconfigError::Missing(String::from("OUT_DIR")))?;
+    let cfg = read(input, options)?;
+    let code = generate_from_value(&cfg.to_value()?, options)?;
+    let stem = Path::new(input).file_stem().unwrap_or_default();
+    let out = Path::new(&out_dir).join(stem).with_extension("rs");
+    fs::write(&out, code)
+        .map_err(|err| LibconfigError::Io(format!("{}: {}", out.display(), err)))?;
+    cfg.rerun_if_changed();
+    Ok(())
+}
+
+fn read(input: &str, options: &CodegenOptions) -> Result<Config, LibconfigError> {
+    let mut cfg = Config::new();
+    if let Some(dir) = &options.include_dir {
+        cfg.set_include_path(dir);
+    }
+    cfg.read_file(input)?;
+    Ok(cfg)
+}
+
+pub fn generate_from_value(
+    value: &Value,
+    options: &CodegenOptions,
+) -> Result<String, LibconfigError> {
+    let Shape::Group(fields) = shape_of(value, "", options.schema)? else {
+        return Err(LibconfigError::TypeMismatch(String::new()));
+    };
+    let mut generator = Generator {
+        structs: Vec::new(),
+        names: RESERVED_TYPES.iter().map(|name| name.to_string()).collect(),
+    };
+    let root = generator.add_struct(&options.struct_name, "", &fields);
+    let mut code = String::from("// Generated by libconfig-build. Do not edit.\n");
+    for def in generator.structs {
+        code.push('\n');
+        code.push_str(&def);
+    }
+    code.push_str(&format!(
+        "\npub fn load(\n    cfg: &::libconfig::Config,\n) -> ::std::result::Result<{name}, ::libconfig::LibconfigError> {{\n    \
+         <{name} as ::libconfig::FromConfig>::from_setting(&mut cfg.get_root())\n}}\n",
+        name = root
+    ));
+    Ok(code)
+}
+
+// The type of a setting, merged over every element of a list.
+#[derive(Debug, Clone, PartialEq)]
+enum Shape {
+    Bool,
+    Int,
+    Int64,
+    Float,
+    String,
+    // Elements that disagree on their type.
+    Any,
+    Vec(Box<Shape>),
+    // Fields with whether they may be missing.
+    Group(Vec<(String, Shape, bool)>),
+}
+
+fn shape_of(value: &Value, path: &str, schema: bool) -> Result<Shape, LibconfigError> {
+    Ok(match value {
+        Value::Bool(_) => Shape::Bool,
+        Value::Int(_) => Shape::Int,
+        Value::Int64(_) => Shape::Int64,
+        Value::Float(_) => Shape::Float,
+        Value::String(name) if schema => schema_type(name.trim_end_matches('?'))
+            .ok_or_else(|| LibconfigError::TypeMismatch(path.to_string()))?,
+        Value::String(_) => Shape::String,
+        Value::Group(children) => Shape::Group(
+            children
+                .iter()
+                .map(|(name, child)| {
+                    let optional =
+                        schema && matches!(child, Value::String(ty) if ty.ends_with('?'));
+                    Ok((
+                        name.clone(),
+                        shape_of(child, &child_path(path, name), schema)?,
+                        optional,
+                    ))
+                })
+                .collect::<Result<_, LibconfigError>>()?,
+        ),
+        Value::Array(elems) | Value::List(elems) => {
+            let mut shape: Option<Shape> = None;
+            for (idx, elem) in elems.iter().enumerate() {
+                let elem = shape_of(elem, &child_path(path, &format!("[{}]", idx)), schema)?;
+                shape = Some(match shape {
+                    Some(shape) => unify(shape, elem),
+                    None => elem,
+                });
+            }
+            Shape::Vec(Box::new(shape.unwrap_or(Shape::Any)))
+        }
+    })
+}
+
+fn schema_type(name: &str) -> Option<Shape> {
+    Some(match name {
+        "bool" => Shape::Bool,
+        "int" => Shape::Int,
+        "int64" => Shape::Int64,
+        "float" => Shape::Float,
+        "string" => Shape::String,
+        _ => return None,
+    })
+}
+
+fn child_path(path: &str, name: &str) -> String {
+    match path.is_empty() {
+        true => name.to_string(),
+        false => format!("{}.{}", path, name),
+    }
+}
+
+fn unify(a: Shape, b: Shape) -> Shape {
+    match (a, b) {
+        (a, b) if a == b => a,
+        (Shape::Int | Shape::Int64, Shape::Int | Shape::Int64) => Shape::Int64,
+        (Shape::Vec(a), Shape::Vec(b)) => Shape::Vec(Box::new(unify(*a, *b))),
+        (Shape::Group(mut fields), Shape::Group(others)) => {
+            for (name, _, optional) in fields.iter_mut() {
+                if !others.iter().any(|(other, _, _)| other == name) {
+                    *optional = true;
+                }
+            }
+            for (name, shape, optional) in others {
+                match fields.iter_mut().find(|(field, _, _)| *field == name) {
+                    Some(field) => {
+                        field.1 = unify(field.1.clone(), shape);
+                        field.2 |= optional;
+                    }
+                    None => fields.push((name, shape, true)),
+                }
+            }
+            Shape::Group(fields)
+        }
+        _ => Shape::Any,
+    }
+}
+
+struct Generator {
+    structs: Vec<String>,
+    names: HashSet<String>,
+}
+
+impl Generator {
+    // Returns the name the struct was given; nested structs are named after
+    // their setting, prefixed with the parent's name on collisions, and the
+    // root struct gets a `Root` suffix.
+    fn add_struct(&mut self, name: &str, parent: &str, fields: &[(String, Shape, bool)]) -> String {
+        let mut name = name.to_string();
+        while !self.names.insert(name.clone()) {
+            name = match parent {
+                "" => format!("{}Root", name),
+                parent => format!("{}{}", parent, name),
+            };
+        }
+        let idx = self.structs.len();
+        self.structs.push(String::new());
+        let mut def = format!(
+            "#[derive(Debug, Clone, PartialEq, ::libconfig::FromConfig)]\npub struct {} {{\n",
+            name
+        );
+        for (key, shape, optional) in fields {
+            let ty = self.type_of(shape, key, &name);
+            let field = field_name(key);
+            if field.trim_start_matches("r#") != key {
+                def.push_str(&format!("    #[config(rename = \"{}\")]\n", key));
+            }
+            match optional {
+                true => def.push_str(&format!(
+                    "    #[config(default)]\n    pub {}: ::std::option::Option<{}>,\n",
+                    field, ty
+                )),
+                false => def.push_str(&format!("    pub {}: {},\n", field, ty)),
+            }
+        }
+        def.push_str("}\n");
+        self.structs[idx] = def;
+        name
+    }
+
+    fn type_of(&mut self, shape: &Shape, key: &str, parent: &str) -> String {
+        match shape {
+            Shape::Bool => String::from("bool"),
+            Shape::Int => String::from("i32"),
+            Shape::Int64 => String::from("i64"),
+            Shape::Float => String::from("f64"),
+            Shape::String => String::from("::std::string::String"),
+            Shape::Any => String::from("::libconfig::Value"),
+            Shape::Vec(elem) => {
+                let singular = key
+                    .strip_suffix('s')
+                    .filter(|s| !s.is_empty())
+                    .unwrap_or(key);
+                format!("::std::vec::Vec<{}>", self.type_of(elem, singular, parent))
+            }
+            Shape::Group(fields) => self.add_struct(&type_name(key), parent, fields),
+        }
+    }
+}
+
+// Names no generated struct may take: the keyword `Self`, and the prelude
+// types that the code around an `include!` may still mean to use.
+const RESERVED_TYPES: &[&str] = &["Self", "Box", "Option", "Result", "String", "Vec"];
+
+const KEYWORDS: &[&str] = &[
+    "as", "async", "await", "break", "const", "continue", "dyn", "else", "enum", "extern", "false",
+    "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub", "ref",
+    "return", "static", "struct", "trait", "true", "type", "unsafe", "use", "where", "while",
+];
+
+// Setting names may contain `-` and `*` and be in camel case.
+fn field_name(key: &str) -> String {
+    let mut field = String::new();
+    let mut prev_lower = false;
+    for c in key.chars() {
+        if c.is_ascii_uppercase() && prev_lower {
+            field.push('_');
+        }
+        prev_lower = c.is_ascii_lowercase() || c.is_ascii_digit();
+        field.push(match c {
+            '-' | '*' => '_',
+            c => c.to_ascii_lowercase(),
+        });
+    }
+    match field.as_str() {
+        "self" | "super" | "crate" => field + "_",
+        _ if KEYWORDS.contains(&field.as_str()) => format!("r#{}", field),
+        _ => field,
+    }
+}
+
+fn type_name(key: &str) -> String {
+    let mut name = String::new();
+    let mut upper = true;
+    for c in key.chars() {
+        match c {
+            '-' | '_' | '*' => upper = true,
+            c if upper => {
+                name.push(c.to_ascii_uppercase());
+                upper = false;
+            }
+            c => name.push(c),
+        }
+    }
+    name
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn group(children: &[(&str, Value)]) -> Value {
+        Value::Group(
+            children
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.clone()))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn ok_on_generate_from_sample() {
+        let server = |port: Value, extra: Option<bool>| {
+            let mut fields = vec![("port", port)];
+            if let Some(extra) = extra {
+                fields.push(("tls", Value::Bool(extra)));
+            }
+            group(&fields)
+        };
+        let value = group(&[
+            ("name", Value::String(String::from("app"))),
+            ("max-conn", Value::Int(10)),
+            ("type", Value::Float(0.5)),
+            (
+                "log",
+                group(&[("level", Value::String(String::from("info")))]),
+            ),
+            (
+                "servers",
+                Value::List(vec![
+                    server(Value::Int(80), None),
+                    server(Value::Int64(443), Some(true)),
+                ]),
+            ),
+            ("ids", Value::Array(vec![Value::Int(1), Value::Int(2)])),
+        ]);
+        let code = generate_from_value(&value, &CodegenOptions::default()).unwrap();
+        assert_eq!(
+            code,
+            "// Generated by libconfig-build. Do not edit.
+
+#[derive(Debug, Clone, PartialEq, ::libconfig::FromConfig)]
+pub struct Settings {
+    pub name: ::std::string::String,
+    #[config(rename = \"max-conn\")]
+    pub max_conn: i32,
+    pub r#type: f64,
+    pub log: Log,
+    pub servers: ::std::vec::Vec<Server>,
+    pub ids: ::std::vec::Vec<i32>,
+}
+
+#[derive(Debug, Clone, PartialEq, ::libconfig::FromConfig)]
+pub struct Log {
+    pub level: ::std::string::String,
+}
+
+#[derive(Debug, Clone, PartialEq, ::libconfig::FromConfig)]
+pub struct Server {
+    pub port: i64,
+    #[config(default)]
+    pub tls: ::std::option::Option<bool>,
+}
+
+pub fn load(
+    cfg: &::libconfig::Config,
+) -> ::std::result::Result<Settings, ::libconfig::LibconfigError> {
+    <Settings as ::libconfig::FromConfig>::from_setting(&mut cfg.get_root())
+}
+"
+        );
+    }
+
+    #[test]
+    fn ok_on_generate_from_schema() {
+        let value = group(&[
+            ("port", Value::String(String::from("int"))),
+            ("debug", Value::String(String::from("bool?"))),
+            (
+                "db",
+                group(&[("db", Value::String(String::from("string")))]),
+            ),
+        ]);
+        let options = CodegenOptions {
+            schema: true,
+            ..CodegenOptions::default()
+        };
+        let code = generate_from_value(&value, &options).unwrap();
+        assert!(code.contains("    pub port: i32,\n"));
+        assert!(
+            code.contains("    #[config(default)]\n    pub debug: ::std::option::Option<bool>,\n")
+        );
+        assert!(code.contains("pub struct Db {\n    pub db: ::std::string::String,\n}"));
+        let value = group(&[("port", Value::String(String::from("integer")))]);
+        assert_eq!(
+            generate_from_value(&value, &options),
+            Err(LibconfigError::TypeMismatch(String::from("port")))
+        );
+    }
+
+    #[test]
+    fn ok_on_groups_named_like_prelude_types() {
+        let value = group(&[
+            ("option", group(&[("string", group(&[]))])),
+            ("self", group(&[("vec", group(&[]))])),
+        ]);
+        let code = generate_from_value(&value, &CodegenOptions::default()).unwrap();
+        assert!(code.contains("    pub option: SettingsOption,\n"));
+        assert!(code.contains("    pub string: SettingsOptionString,\n"));
+        assert!(code.contains("    pub self_: SettingsSelf,\n"));
+        assert!(code.contains("    pub vec: SettingsSelfVec,\n"));
+        let options = CodegenOptions {
+            struct_name: String::from("Result"),
+            ..CodegenOptions::default()
+        };
+        let code = generate_from_value(&value, &options).unwrap();
+        assert!(code.contains("pub struct ResultRoot {\n"));
+        assert!(code.contains("::std::result::Result<ResultRoot, "));
+    }
+
+    #[test]
+    fn ok_on_names() {
+        assert_eq!(field_name("maxConn"), "max_conn");
+        assert_eq!(field_name("a-b*c"), "a_b_c");
+        assert_eq!(field_name("self"), "self_");
+        assert_eq!(type_name("http-server_opts"), "HttpServerOpts");
+    }
+}
@@ -0,0 +1,34 @@
+use libconfig::Config;
+use libconfig_build::{generate, CodegenOptions};
+
+// The code generated for `servers.cfg`, checked in so that it is compiled.
+mod servers {
+    include!("generated/servers.rs");
+}
+
+#[test]
+fn ok_on_load_generated() {
+    let input = "../input/servers.cfg";
+    let code = generate(input, &CodegenOptions::default()).unwrap();
+    assert_eq!(code, include_str!("generated/servers.rs"));
+
+    let mut cfg = Config::new();
+    assert_eq!(cfg.read_file(input), Ok(()));
+    let settings = servers::load(&cfg).unwrap();
+    assert_eq!(settings.max_conn, 10);
+    assert_eq!(settings.log.level, "info");
+    assert_eq!(
+        settings.servers,
+        vec![
+            servers::Server {
+                port: 80,
+                tls: None
+            },
+            servers::Server {
+                port: 443,
+                tls: Some(true)
+            },
+        ]
+    );
+    assert_eq!(settings.ids, vec![1, 2]);
+}
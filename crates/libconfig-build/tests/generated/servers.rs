@@ -0,0 +1,30 @@
+// Generated by libconfig-build. Do not edit.
+
+#[derive(Debug, Clone, PartialEq, ::libconfig::FromConfig)]
+pub struct Settings {
+    pub name: ::std::string::String,
+    #[config(rename = "max-conn")]
+    pub max_conn: i32,
+    pub r#type: f64,
+    pub log: Log,
+    pub servers: ::std::vec::Vec<Server>,
+    pub ids: ::std::vec::Vec<i32>,
+}
+
+#[derive(Debug, Clone, PartialEq, ::libconfig::FromConfig)]
+pub struct Log {
+    pub level: ::std::string::String,
+}
+
+#[derive(Debug, Clone, PartialEq, ::libconfig::FromConfig)]
+pub struct Server {
+    pub port: i64,
+    #[config(default)]
+    pub tls: ::std::option::Option<bool>,
+}
+
+pub fn load(
+    cfg: &::libconfig::Config,
+) -> ::std::result::Result<Settings, ::libconfig::LibconfigError> {
+    <Settings as ::libconfig::FromConfig>::from_setting(&mut cfg.get_root())
+}
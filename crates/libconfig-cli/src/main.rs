@@ -0,0 +1,444 @@
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use libconfig::{
+    Config, ConfigOption, DocumentEditor, Format, FromConfig, LibconfigError, Type, Value,
+    WriteOptions,
+};
+use std::fs;
+use std::io::{self, Read, Write};
+use std::process::ExitCode;
+
+/// Query, edit, check and convert libconfig files.
+///
+/// Every FILE argument may be `-` to read the config from stdin; edited
+/// configs read from stdin are written to stdout.
+#[derive(Parser)]
+#[command(name = "libconfig", version)]
+struct Cli {
+    /// Directory used to resolve `@include` directives.
+    #[arg(short = 'I', long, global = true)]
+    include_dir: Option<String>,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Print the setting at PATH.
+    Get {
+        file: String,
+        path: String,
+        /// Print the setting's type instead of its value.
+        #[arg(long = "type")]
+        show_type: bool,
+        /// Print the value as JSON.
+        #[arg(long, conflicts_with = "show_type")]
+        json: bool,
+    },
+    /// Set the setting at PATH, keeping all other settings and comments.
+    ///
+    /// VALUE is parsed with libconfig syntax (`5`, `5L`, `0x10`, `"text"`,
+    /// `[1, 2]`, `{ a = 1; }`); anything else is stored as a string.
+    Set {
+        file: String,
+        path: String,
+        value: String,
+    },
+    /// Remove the setting at PATH.
+    Rm { file: String, path: String },
+    /// Check that files parse; prints `file:line: message` for each failure.
+    Validate {
+        #[arg(required = true)]
+        files: Vec<String>,
+    },
+    /// Rewrite a file in libconfig's canonical layout.
+    Fmt {
+        file: String,
+        /// Rewrite the file in place instead of printing it.
+        #[arg(short, long)]
+        write: bool,
+        #[command(flatten)]
+        options: FmtOptions,
+    },
+    /// Convert a file to another format.
+    Convert {
+        file: String,
+        #[arg(long, value_enum)]
+        to: Target,
+    },
+}
+
+#[derive(Args)]
+struct FmtOptions {
+    #[arg(long)]
+    tab_width: Option<u16>,
+    #[arg(long)]
+    float_precision: Option<u16>,
+    /// Write integers in hex unless they have their own format.
+    #[arg(long)]
+    hex: bool,
+    /// Terminate settings with semicolons.
+    #[arg(long, value_name = "BOOL")]
+    semicolons: Option<bool>,
+    /// Assign groups with `:` instead of `=`.
+    #[arg(long, value_name = "BOOL")]
+    colon_for_groups: Option<bool>,
+    /// Assign all other settings with `:` instead of `=`.
+    #[arg(long, value_name = "BOOL")]
+    colon_for_settings: Option<bool>,
+    /// Put the opening brace of groups on a separate line.
+    #[arg(long, value_name = "BOOL")]
+    brace_on_new_line: Option<bool>,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Target {
+    Json,
+    Toml,
+    Yaml,
+}
+
+struct Failure {
+    code: u8,
+    message: String,
+}
+
+impl Failure {
+    // Exit code for configs that fail to parse or lack a setting.
+    const INVALID: u8 = 1;
+    // Exit code for I/O errors and unusable arguments.
+    const ERROR: u8 = 2;
+
+    fn invalid(message: String) -> Self {
+        Failure {
+            code: Self::INVALID,
+            message,
+        }
+    }
+
+    fn error(message: String) -> Self {
+        Failure {
+            code: Self::ERROR,
+            message,
+        }
+    }
+}
+
+fn display_name(file: &str) -> &str {
+    match file {
+        "-" => "<stdin>",
+        file => file,
+    }
+}
+
+fn diagnostic(file: &str, err: &LibconfigError) -> String {
+    match err {
+        LibconfigError::Syntax {
+            file: source,
+            line,
+            message,
+        } => {
+            let source = match source.is_empty() {
+                true => display_name(file),
+                false => source,
+            };
+            format!("{}:{}: {}", source, line, message)
+        }
+        err => format!("{}: {}", display_name(file), err),
+    }
+}
+
+fn read_stdin() -> Result<String, Failure> {
+    let mut input = String::new();
+    io::stdin()
+        .read_to_string(&mut input)
+        .map_err(|err| Failure::error(format!("<stdin>: {}", err)))?;
+    Ok(input)
+}
+
+fn load(file: &str, include_dir: Option<&str>) -> Result<Config, Failure> {
+    let mut cfg = Config::new();
+    if let Some(dir) = include_dir {
+        cfg.set_include_path(dir);
+    }
+    let result = match file {
+        "-" => cfg.read_str(&read_stdin()?),
+        path => {
+            fs::metadata(path).map_err(|err| Failure::error(format!("{}: {}", path, err)))?;
+            cfg.read_file(path)
+        }
+    };
+    result.map_err(|err| Failure::invalid(diagnostic(file, &err)))?;
+    Ok(cfg)
+}
+
+fn open(file: &str, include_dir: Option<&str>) -> Result<DocumentEditor, Failure> {
+    let editor = match file {
+        "-" => DocumentEditor::parse(&read_stdin()?),
+        path => {
+            fs::metadata(path).map_err(|err| Failure::error(format!("{}: {}", path, err)))?;
+            DocumentEditor::open(path)
+        }
+    };
+    let mut editor = editor.map_err(|err| Failure::invalid(diagnostic(file, &err)))?;
+    if let Some(dir) = include_dir {
+        editor.set_include_path(dir);
+    }
+    Ok(editor)
+}
+
+fn save(cfg: &mut Config, file: &str) -> Result<(), Failure> {
+    match file {
+        "-" => {
+            let output = cfg
+                .write_str()
+                .map_err(|err| Failure::error(format!("<stdout>: {}", err)))?;
+            print(&output)
+        }
+        path => cfg
+            .write_file_atomic(path, &WriteOptions::default())
+            .map_err(|err| Failure::error(format!("{}: cannot write: {}", path, err))),
+    }
+}
+
+fn print(output: &str) -> Result<(), Failure> {
+    io::stdout()
+        .write_all(output.as_bytes())
+        .map_err(|err| Failure::error(format!("<stdout>: {}", err)))
+}
+
+fn parse_value(input: &str) -> Value {
+    let mut cfg = Config::new();
+    match cfg.read_str(&format!("value = {};", input)) {
+        Ok(()) => cfg
+            .to_value()
+            .ok()
+            .and_then(|value| value.get("value").cloned())
+            .unwrap_or_else(|| Value::String(input.to_string())),
+        Err(_) => Value::String(input.to_string()),
+    }
+}
+
+fn type_name(setting_type: Type) -> &'static str {
+    match setting_type {
+        Type::TypeInt => "int",
+        Type::TypeInt64 => "int64",
+        Type::TypeFloat => "float",
+        Type::TypeString => "string",
+        Type::TypeBoolean => "bool",
+        Type::TypeGroup => "group",
+        Type::TypeArray => "array",
+        Type::TypeList => "list",
+        _ => "none",
+    }
+}
+
+// Edits the text of the file, so that comments and `@include` directives
+// survive, and replaces the file atomically.
+fn edit(
+    file: &str,
+    include_dir: Option<&str>,
+    apply: impl FnOnce(&mut DocumentEditor) -> Result<(), LibconfigError>,
+) -> Result<(), Failure> {
+    let mut editor = open(file, include_dir)?;
+    apply(&mut editor).map_err(|err| Failure::invalid(diagnostic(file, &err)))?;
+    match file {
+        "-" => print(editor.as_str()),
+        path => editor
+            .write_file(path)
+            .map_err(|err| Failure::error(format!("{}: cannot write: {}", path, err))),
+    }
+}
+
+fn run(cli: Cli) -> Result<(), Failure> {
+    let include_dir = cli.include_dir.as_deref();
+    match cli.command {
+        Command::Get {
+            file,
+            path,
+            show_type,
+            json,
+        } => {
+            let mut cfg = load(&file, include_dir)?;
+            let mut setting = cfg.lookup(&path).map_err(|_| {
+                Failure::invalid(format!(
+                    "{}: setting `{}` not found",
+                    display_name(&file),
+                    path
+                ))
+            })?;
+            let output = if show_type {
+                type_name(setting.get_type()).to_string()
+            } else {
+                let value = Value::from_setting(&mut setting)
+                    .map_err(|err| Failure::invalid(diagnostic(&file, &err)))?;
+                match (json, value) {
+                    (true, value) => value
+                        .to_json(&Default::default())
+                        .map_err(|err| Failure::invalid(diagnostic(&file, &err)))?
+                        .to_string(),
+                    (false, Value::String(val)) => val,
+                    (false, value) => value.to_string(),
+                }
+            };
+            print(&format!("{}\n", output))
+        }
+        Command::Set { file, path, value } => {
+            let value = parse_value(&value);
+            edit(&file, include_dir, |editor| {
+                match editor.set(&path, &value) {
+                    Err(LibconfigError::Missing(_)) => editor.add(&path, &value),
+                    result => result,
+                }
+            })
+        }
+        Command::Rm { file, path } => edit(&file, include_dir, |editor| editor.remove(&path)),
+        Command::Validate { files } => {
+            let mut failure = None;
+            for file in &files {
+                if let Err(err) = load(file, include_dir) {
+                    eprintln!("{}", err.message);
+                    failure = Some(failure.unwrap_or(0).max(err.code));
+                }
+            }
+            match failure {
+                Some(code) => Err(Failure {
+                    code,
+                    message: String::new(),
+                }),
+                None => Ok(()),
+            }
+        }
+        Command::Fmt {
+            file,
+            write,
+            options,
+        } => {
+            let mut cfg = load(&file, include_dir)?;
+            if let Some(width) = options.tab_width {
+                cfg.set_tab_width(width);
+            }
+            if let Some(digits) = options.float_precision {
+                cfg.set_float_precision(digits);
+            }
+            if options.hex {
+                cfg.set_default_format(Format::FormatHex);
+            }
+            let flags = [
+                (ConfigOption::OptionSemicolonSeparators, options.semicolons),
+                (
+                    ConfigOption::OptionColonAssignmentForGroups,
+                    options.colon_for_groups,
+                ),
+                (
+                    ConfigOption::OptionColonAssignmentForNonGroups,
+                    options.colon_for_settings,
+                ),
+                (
+                    ConfigOption::OptionOpenBraceOnSeparateLine,
+                    options.brace_on_new_line,
+                ),
+            ];
+            for (option, flag) in flags {
+                if let Some(flag) = flag {
+                    cfg.set_option(option, flag);
+                }
+            }
+            match write && file != "-" {
+                true => save(&mut cfg, &file),
+                false => save(&mut cfg, "-"),
+            }
+        }
+        Command::Convert { file, to } => {
+            let cfg = load(&file, include_dir)?;
+            let output = match to {
+                Target::Json => {
+                    let json = cfg
+                        .to_json()
+                        .map_err(|err| Failure::invalid(diagnostic(&file, &err)))?;
+                    serde_json::to_string_pretty(&json)
+                        .map(|json| json + "\n")
+                        .map_err(|err| Failure::error(err.to_string()))?
+                }
+                Target::Toml => libconfig::convert::to_toml(&cfg)
+                    .map(|(toml, _)| toml)
+                    .map_err(|err| Failure::invalid(diagnostic(&file, &err)))?,
+                Target::Yaml => libconfig::convert::to_yaml(&cfg)
+                    .map(|(yaml, _)| yaml)
+                    .map_err(|err| Failure::invalid(diagnostic(&file, &err)))?,
+            };
+            print(&output)
+        }
+    }
+}
+
+fn main() -> ExitCode {
+    match run(Cli::parse()) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(failure) => {
+            if !failure.message.is_empty() {
+                eprintln!("libconfig: {}", failure.message);
+            }
+            ExitCode::from(failure.code)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_value_uses_libconfig_syntax() {
+        assert_eq!(parse_value("5"), Value::Int(5));
+        assert_eq!(parse_value("5L"), Value::Int64(5));
+        assert_eq!(
+            parse_value("\"quoted\""),
+            Value::String(String::from("quoted"))
+        );
+        assert_eq!(
+            parse_value("[1, 2]"),
+            Value::Array(vec![Value::Int(1), Value::Int(2)])
+        );
+    }
+
+    #[test]
+    fn parse_value_falls_back_to_string() {
+        assert_eq!(parse_value("hello"), Value::String(String::from("hello")));
+        assert_eq!(parse_value("1.2.3"), Value::String(String::from("1.2.3")));
+    }
+
+    #[test]
+    fn diagnostic_names_file_and_line() {
+        let err = LibconfigError::Syntax {
+            file: String::new(),
+            line: 3,
+            message: String::from("syntax error"),
+        };
+        assert_eq!(diagnostic("-", &err), "<stdin>:3: syntax error");
+        assert_eq!(
+            diagnostic("app.cfg", &LibconfigError::Invalid),
+            "app.cfg: invalid operation"
+        );
+    }
+
+    #[test]
+    fn validate_reports_parse_errors() {
+        let cli = Cli::parse_from(["libconfig", "validate", "../input/invalid.cfg"]);
+        assert!(matches!(
+            run(cli),
+            Err(Failure {
+                code: Failure::INVALID,
+                ..
+            })
+        ));
+        let cli = Cli::parse_from(["libconfig", "validate", "missing.cfg"]);
+        assert!(matches!(
+            run(cli),
+            Err(Failure {
+                code: Failure::ERROR,
+                ..
+            })
+        ));
+    }
+}
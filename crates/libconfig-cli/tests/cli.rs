@@ -0,0 +1,124 @@
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{self, Command, Output, Stdio};
+
+const CONFIG: &str =
+    "// The server settings.\nname = \"app\";\nport = 80;\nlog = { level = \"info\"; };\n";
+
+// Writes CONFIG to a file of its own, so that tests editing it don't race.
+fn scratch_file(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("libconfig-cli-{}-{}.cfg", name, process::id()));
+    fs::write(&path, CONFIG).unwrap();
+    path
+}
+
+fn libconfig(args: &[&str], stdin: Option<&str>) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_libconfig"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let mut input = child.stdin.take().unwrap();
+    input
+        .write_all(stdin.unwrap_or_default().as_bytes())
+        .unwrap();
+    drop(input);
+    child.wait_with_output().unwrap()
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8(output.stdout.clone()).unwrap()
+}
+
+#[test]
+fn set_keeps_comments_and_adds_missing_settings() {
+    let path = scratch_file("set");
+    let file = path.to_str().unwrap();
+    assert!(libconfig(&["set", file, "port", "8080"], None)
+        .status
+        .success());
+    assert!(libconfig(&["set", file, "log.file", "\"app.log\""], None)
+        .status
+        .success());
+
+    let text = fs::read_to_string(&path).unwrap();
+    assert!(text.starts_with("// The server settings.\n"));
+    assert!(text.contains("port = 8080;"));
+    let output = libconfig(&["get", file, "log.file"], None);
+    assert_eq!(stdout(&output), "app.log\n");
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn rm_removes_setting() {
+    let path = scratch_file("rm");
+    let file = path.to_str().unwrap();
+    assert!(libconfig(&["rm", file, "port"], None).status.success());
+    assert!(!fs::read_to_string(&path).unwrap().contains("port"));
+
+    let output = libconfig(&["rm", file, "port"], None);
+    assert_eq!(output.status.code(), Some(1));
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn fmt_prints_canonical_layout() {
+    let path = scratch_file("fmt");
+    let file = path.to_str().unwrap();
+    let output = libconfig(&["fmt", file, "--semicolons", "false"], None);
+    assert!(output.status.success());
+    assert_eq!(
+        stdout(&output),
+        "name = \"app\"\nport = 80\nlog : \n{\n  level = \"info\"\n}\n"
+    );
+    assert_eq!(fs::read_to_string(&path).unwrap(), CONFIG);
+
+    assert!(libconfig(&["fmt", "--write", file], None).status.success());
+    assert_eq!(
+        fs::read_to_string(&path).unwrap(),
+        "name = \"app\";\nport = 80;\nlog : \n{\n  level = \"info\";\n};\n"
+    );
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn convert_writes_target_format() {
+    let path = scratch_file("convert");
+    let file = path.to_str().unwrap();
+    let output = libconfig(&["convert", file, "--to", "json"], None);
+    assert!(output.status.success());
+    let json: serde_json::Value = serde_json::from_str(&stdout(&output)).unwrap();
+    assert_eq!(
+        json,
+        serde_json::json!({ "name": "app", "port": 80, "log": { "level": "info" } })
+    );
+
+    let output = libconfig(&["convert", file, "--to", "toml"], None);
+    assert!(stdout(&output).contains("port = 80\n"));
+    let output = libconfig(&["convert", file, "--to", "yaml"], None);
+    assert!(stdout(&output).contains("port: 80\n"));
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn dash_reads_stdin_and_writes_stdout() {
+    let output = libconfig(&["get", "-", "log.level"], Some(CONFIG));
+    assert_eq!(stdout(&output), "info\n");
+
+    let output = libconfig(&["set", "-", "port", "8080"], Some(CONFIG));
+    assert!(output.status.success());
+    assert_eq!(
+        stdout(&output),
+        CONFIG.replace("port = 80;", "port = 8080;")
+    );
+
+    let output = libconfig(&["rm", "-", "name"], Some(CONFIG));
+    assert!(!stdout(&output).contains("name"));
+
+    let output = libconfig(&["validate", "-"], Some("port = ;"));
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stderr).starts_with("<stdin>:1: "));
+}
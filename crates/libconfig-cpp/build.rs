@@ -1,11 +1,9 @@
+use std::env;
+
 fn main() {
-    let lib = cmake::Config::new("libconfig")
-        .define("BUILD_EXAMPLES", "false")
-        .define("BUILD_TESTS", "false")
-        .define("BUILD_SHARED_LIBS", "false")
-        .build();
-    println!(
-        "cargo:rustc-env=LIBCONFIG_PATH={}",
-        lib.to_str().expect("path valid")
-    );
+    println!("cargo:rerun-if-env-changed=LIBCONFIG_NO_VENDOR");
+    // libconfig-sys finds a system libconfig++ through pkg-config instead.
+    let vendored = env::var_os("CARGO_FEATURE_SYSTEM").is_none()
+        && env::var_os("LIBCONFIG_NO_VENDOR").is_none();
+    println!("cargo:rustc-env=LIBCONFIG_VENDORED={}", vendored as u8);
 }
@@ -1,3 +1,7 @@
 fn main() {
     println!("libconfig_path: {}", libconfig_cpp::libconfig_path().to_str().unwrap());
+    println!(
+        "source_dir: {}",
+        libconfig_cpp::source_dir().to_str().unwrap()
+    );
 }
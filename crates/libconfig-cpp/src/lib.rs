@@ -1,6 +1,141 @@
-use std::path::Path;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
 
+// The sources of the C library, which libconfig++ is linked against.
+const C_SOURCES: &[&str] = &[
+    "grammar.c",
+    "libconfig.c",
+    "scanctx.c",
+    "scanner.c",
+    "strbuf.c",
+    "strvec.c",
+    "util.c",
+    "wincompat.c",
+];
+const CXX_SOURCES: &[&str] = &["libconfigcpp.cc"];
+const HEADERS: &[&str] = &["libconfig.h", "libconfig.h++", "libconfig.hh"];
+
+/// The defines for code compiled against the bundled headers, which `build`
+/// uses as well: without them the headers declare the libraries' functions
+/// `dllimport` on Windows, while the bundled libraries are static.
+pub const STATIC_DEFINES: &[&str] = &["LIBCONFIG_STATIC", "LIBCONFIGXX_STATIC"];
+
+/// The bundled libconfig sources.
+pub fn source_dir() -> &'static Path
+{
+    Path::new(concat!(env!("CARGO_MANIFEST_DIR"), "/libconfig/lib"))
+}
+
+/// The prefix `build` installs into when called from the same build script:
+/// `lib` holds the libraries and `include` the headers. Empty when a system
+/// library has to be used.
 pub fn libconfig_path() -> &'static Path
 {
-    Path::new(env!("LIBCONFIG_PATH"))
+    static PATH: OnceLock<PathBuf> = OnceLock::new();
+    PATH.get_or_init(|| match (is_vendored(), env::var_os("OUT_DIR")) {
+        (true, Some(out_dir)) => Path::new(&out_dir).join("libconfig"),
+        _ => PathBuf::new(),
+    })
+}
+
+/// Whether libconfig++ is built from the bundled sources. If not, `build`
+/// must not be called and a system library has to be used.
+pub fn is_vendored() -> bool
+{
+    env!("LIBCONFIG_VENDORED") == "1"
+}
+
+/// Compiles the bundled sources from the build script calling this, into its
+/// `OUT_DIR`, and returns the directory holding the headers.
+///
+/// The build follows that script's target, profile and the compilers and
+/// flags in `CC`, `CXX`, `CFLAGS` and `CXXFLAGS`, as read by `cc`: the C
+/// sources are compiled as C into `config`, and with `cxx` set the C++
+/// bindings into `config++`. `cc` also tells cargo to link them statically,
+/// `config++` first as it depends on `config`.
+pub fn build(cxx: bool) -> PathBuf
+{
+    let src = source_dir();
+    assert!(
+        src.join("libconfig.h").is_file(),
+        "bundled libconfig sources not found in {}; run `git submodule update --init`",
+        src.display()
+    );
+    let out = PathBuf::from(env::var_os("OUT_DIR").expect("called from a build script"))
+        .join("libconfig");
+    let include = out.join("include");
+    fs::create_dir_all(&include).expect("include directory created");
+    fs::create_dir_all(out.join("lib")).expect("lib directory created");
+    for header in HEADERS {
+        fs::copy(src.join(header), include.join(header)).expect("header copied");
+    }
+    write_config_header(&out);
+
+    let mut build = cc::Build::new();
+    build
+        .include(&out)
+        .include(src)
+        .out_dir(out.join("lib"))
+        .define("HAVE_CONFIG_H", None)
+        // The scanner and parser are generated by flex and bison.
+        .warnings(false);
+    for define in STATIC_DEFINES {
+        build.define(define, None);
+    }
+    if target_os() == "windows" {
+        build
+            .define("YY_NO_UNISTD_H", None)
+            .define("_CRT_SECURE_NO_DEPRECATE", None);
+    }
+    if cxx {
+        build
+            .clone()
+            .cpp(true)
+            .files(CXX_SOURCES.iter().map(|file| src.join(file)))
+            .compile("config++");
+    }
+    build
+        .files(C_SOURCES.iter().map(|file| src.join(file)))
+        .compile("config");
+
+    for file in C_SOURCES.iter().chain(CXX_SOURCES).chain(HEADERS) {
+        println!("cargo:rerun-if-changed={}", src.join(file).display());
+    }
+    include
+}
+
+fn target_os() -> String
+{
+    env::var("CARGO_CFG_TARGET_OS").unwrap_or_default()
+}
+
+// Writes the ac_config.h that libconfig's configure step would generate,
+// for the target rather than the host.
+fn write_config_header(dir: &Path)
+{
+    let os = target_os();
+    let mut defines = vec![
+        "STDC_HEADERS",
+        "HAVE_STDINT_H",
+        "HAVE_STDLIB_H",
+        "HAVE_STRING_H",
+    ];
+    if os != "windows" {
+        defines.extend([
+            "HAVE_UNISTD_H",
+            "HAVE_NEWLOCALE",
+            "HAVE_USELOCALE",
+            "HAVE_FREELOCALE",
+        ]);
+    }
+    if os == "macos" || os == "ios" {
+        defines.push("HAVE_XLOCALE_H");
+    }
+    let mut header = String::from("/* Generated by libconfig-cpp. */\n");
+    for define in defines {
+        header.push_str(&format!("#define {} 1\n", define));
+    }
+    fs::write(dir.join("ac_config.h"), header).expect("ac_config.h written");
 }
@@ -0,0 +1,193 @@
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{parse_macro_input, Data, DeriveInput, Expr, Field, Fields, Ident, LitStr, Token};
+
+#[derive(Default)]
+struct FieldAttrs {
+    rename: Option<String>,
+    default: Option<Option<Expr>>,
+    flatten: bool,
+    hex: bool,
+    range: Option<Expr>,
+    skip: bool,
+}
+
+impl FieldAttrs {
+    fn parse(field: &Field) -> syn::Result<Self> {
+        let mut attrs = FieldAttrs::default();
+        for attr in field.attrs.iter().filter(|a| a.path().is_ident("config")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("rename") {
+                    let name: LitStr = meta.value()?.parse()?;
+                    attrs.rename = Some(name.value());
+                } else if meta.path.is_ident("default") {
+                    if meta.input.peek(Token![=]) {
+                        let expr: LitStr = meta.value()?.parse()?;
+                        attrs.default = Some(Some(expr.parse()?));
+                    } else {
+                        attrs.default = Some(None);
+                    }
+                } else if meta.path.is_ident("flatten") {
+                    attrs.flatten = true;
+                } else if meta.path.is_ident("hex") {
+                    attrs.hex = true;
+                } else if meta.path.is_ident("range") {
+                    attrs.range = Some(meta.value()?.parse()?);
+                } else if meta.path.is_ident("skip") {
+                    attrs.skip = true;
+                } else {
+                    return Err(meta.error("unsupported config attribute"));
+                }
+                Ok(())
+            })?;
+        }
+        Ok(attrs)
+    }
+}
+
+fn named_fields(input: &DeriveInput) -> syn::Result<Vec<(&Field, &Ident, FieldAttrs)>> {
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    input,
+                    "config derives require named fields",
+                ))
+            }
+        },
+        _ => {
+            return Err(syn::Error::new_spanned(
+                input,
+                "config derives are only supported on structs",
+            ))
+        }
+    };
+    fields
+        .iter()
+        .map(|field| {
+            let ident = field.ident.as_ref().expect("named field");
+            Ok((field, ident, FieldAttrs::parse(field)?))
+        })
+        .collect()
+}
+
+fn setting_name(ident: &Ident, attrs: &FieldAttrs) -> String {
+    attrs
+        .rename
+        .clone()
+        .unwrap_or_else(|| ident.to_string().trim_start_matches("r#").to_string())
+}
+
+#[proc_macro_derive(FromConfig, attributes(config))]
+pub fn derive_from_config(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand_from_config(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+#[proc_macro_derive(ToConfig, attributes(config))]
+pub fn derive_to_config(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand_to_config(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+fn expand_from_config(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let fields = named_fields(input)?;
+
+    let reads = fields.iter().map(|(field, ident, attrs)| {
+        let ty = &field.ty;
+        let key = setting_name(ident, attrs);
+        let read = if attrs.skip {
+            quote!(::std::default::Default::default())
+        } else if attrs.flatten {
+            quote!(<#ty as ::libconfig::FromConfig>::from_setting(setting)?)
+        } else {
+            match &attrs.default {
+                Some(Some(expr)) => {
+                    quote!(::libconfig::__private::read_field_or(setting, #key, || #expr)?)
+                }
+                Some(None) => quote!(::libconfig::__private::read_field_or(
+                    setting,
+                    #key,
+                    ::std::default::Default::default
+                )?),
+                None => quote!(::libconfig::__private::read_field(setting, #key)?),
+            }
+        };
+        let check = attrs.range.as_ref().map(|range| {
+            quote! {
+                if !(#range).contains(&#ident) {
+                    return ::std::result::Result::Err(::libconfig::LibconfigError::OutOfRange(
+                        ::libconfig::__private::child_path(setting, #key),
+                    ));
+                }
+            }
+        });
+        quote! {
+            let #ident: #ty = #read;
+            #check
+        }
+    });
+    let idents = fields.iter().map(|(_, ident, _)| ident);
+
+    Ok(quote! {
+        impl #impl_generics ::libconfig::FromConfig for #name #ty_generics #where_clause {
+            fn from_setting(
+                setting: &mut ::libconfig::Setting<'_>,
+            ) -> ::std::result::Result<Self, ::libconfig::LibconfigError> {
+                if !setting.is_group() {
+                    return ::std::result::Result::Err(
+                        ::libconfig::LibconfigError::TypeMismatch(setting.get_path()),
+                    );
+                }
+                #(#reads)*
+                ::std::result::Result::Ok(Self { #(#idents),* })
+            }
+        }
+    })
+}
+
+fn expand_to_config(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let fields = named_fields(input)?;
+
+    let writes = fields
+        .iter()
+        .filter(|(_, _, attrs)| !attrs.skip)
+        .map(|(field, ident, attrs)| {
+            let ty = &field.ty;
+            let key = setting_name(ident, attrs);
+            if attrs.flatten {
+                return quote!(<#ty as ::libconfig::ToConfig>::to_setting(&self.#ident, setting)?;);
+            }
+            let format = match attrs.hex {
+                true => quote!(::libconfig::Format::FormatHex),
+                false => quote!(::libconfig::Format::FormatDefault),
+            };
+            quote!(::libconfig::__private::write_field(setting, #key, &self.#ident, #format)?;)
+        });
+
+    Ok(quote! {
+        impl #impl_generics ::libconfig::ToConfig for #name #ty_generics #where_clause {
+            fn setting_type() -> ::libconfig::Type {
+                ::libconfig::Type::TypeGroup
+            }
+
+            fn to_setting(
+                &self,
+                setting: &mut ::libconfig::Setting<'_>,
+            ) -> ::std::result::Result<(), ::libconfig::LibconfigError> {
+                #(#writes)*
+                ::std::result::Result::Ok(())
+            }
+        }
+    })
+}
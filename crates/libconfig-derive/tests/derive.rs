@@ -0,0 +1,176 @@
+use libconfig::{Config, Format, FromConfig, LibconfigError, ToConfig, Type};
+
+#[derive(Debug, PartialEq, FromConfig, ToConfig)]
+struct Outer {
+    inner: i32,
+}
+
+#[derive(Debug, PartialEq, FromConfig, ToConfig)]
+struct TestCfg {
+    name: String,
+    #[config(rename = "val_int", range = 0..=100)]
+    value: i32,
+    #[config(hex)]
+    another: i64,
+    val_u64: i64,
+    some_f64: f64,
+    arr: Vec<i32>,
+    outer: Outer,
+    #[config(default)]
+    missing: Option<bool>,
+    #[config(default = "7")]
+    fallback: u16,
+    #[config(skip)]
+    ignored: u8,
+}
+
+#[derive(Debug, PartialEq, FromConfig, ToConfig)]
+struct Unsigned {
+    port: u32,
+    size: u64,
+    count: usize,
+}
+
+#[derive(Debug, PartialEq, FromConfig)]
+struct Flattened {
+    #[config(flatten)]
+    outer: Outer,
+    #[config(rename = "inner")]
+    copy: i32,
+}
+
+#[derive(Debug, FromConfig)]
+struct Required {
+    #[allow(dead_code)]
+    not_there: i32,
+}
+
+#[derive(Debug, FromConfig)]
+struct Nested {
+    #[allow(dead_code)]
+    outer: Required,
+}
+
+#[derive(Debug, FromConfig)]
+struct Limited {
+    #[config(rename = "val_int", range = 0..=10)]
+    #[allow(dead_code)]
+    value: i32,
+}
+
+#[test]
+fn ok_on_derived_from_config() {
+    let mut cfg = Config::new();
+    assert_eq!(cfg.read_file("../input/test.cfg"), Ok(()));
+    let parsed = TestCfg::from_setting(&mut cfg.get_root()).unwrap();
+    assert_eq!(parsed.name, "Some Name");
+    assert_eq!(parsed.value, 42);
+    assert_eq!(parsed.val_u64, 0xFFFFFFFFFF);
+    assert_eq!(parsed.arr, vec![3, 5, 8]);
+    assert_eq!(parsed.outer, Outer { inner: 3 });
+    assert_eq!(parsed.missing, None);
+    assert_eq!(parsed.fallback, 7);
+    assert_eq!(parsed.ignored, 0);
+}
+
+#[test]
+fn ok_on_flattened_from_config() {
+    let mut cfg = Config::new();
+    assert_eq!(cfg.read_file("../input/test.cfg"), Ok(()));
+    let mut outer = cfg.lookup("outer").unwrap();
+    assert_eq!(
+        Flattened::from_setting(&mut outer),
+        Ok(Flattened {
+            outer: Outer { inner: 3 },
+            copy: 3
+        })
+    );
+}
+
+#[test]
+fn err_names_missing_field_path() {
+    let mut cfg = Config::new();
+    assert_eq!(cfg.read_file("../input/test.cfg"), Ok(()));
+    assert_eq!(
+        Nested::from_setting(&mut cfg.get_root()).unwrap_err(),
+        LibconfigError::Missing(String::from("outer.not_there"))
+    );
+}
+
+#[test]
+fn err_on_value_out_of_range() {
+    let mut cfg = Config::new();
+    assert_eq!(cfg.read_file("../input/test.cfg"), Ok(()));
+    assert_eq!(
+        Limited::from_setting(&mut cfg.get_root()).unwrap_err(),
+        LibconfigError::OutOfRange(String::from("val_int"))
+    );
+}
+
+#[test]
+fn err_on_type_mismatch() {
+    let mut cfg = Config::new();
+    assert_eq!(cfg.read_str("outer = 5;"), Ok(()));
+    assert_eq!(
+        Nested::from_setting(&mut cfg.get_root()).unwrap_err(),
+        LibconfigError::TypeMismatch(String::from("outer"))
+    );
+}
+
+#[test]
+fn round_trip_to_config() {
+    let mut cfg = Config::new();
+    assert_eq!(cfg.read_file("../input/test.cfg"), Ok(()));
+    let mut parsed = TestCfg::from_setting(&mut cfg.get_root()).unwrap();
+    parsed.value = 7;
+    parsed.missing = Some(true);
+    parsed.arr.push(13);
+
+    let mut written = Config::new();
+    assert_eq!(parsed.to_setting(&mut written.get_root()), Ok(()));
+    assert_eq!(written.lookup_i32("val_int"), Some(7));
+    assert_eq!(written.lookup_bool("missing"), Some(true));
+    assert_eq!(written.lookup_i32("fallback"), Some(7));
+    assert!(!written.exists("ignored"));
+    assert_eq!(
+        written.lookup("another").unwrap().get_format(),
+        Format::FormatHex
+    );
+    assert_eq!(TestCfg::from_setting(&mut written.get_root()), Ok(parsed));
+}
+
+#[test]
+fn ok_on_unsigned_from_either_int_width() {
+    let mut cfg = Config::new();
+    assert_eq!(
+        cfg.read_str("port = 8080; size = 5000000000L; count = 3;"),
+        Ok(())
+    );
+    let mut parsed = Unsigned::from_setting(&mut cfg.get_root()).unwrap();
+    assert_eq!(
+        parsed,
+        Unsigned {
+            port: 8080,
+            size: 5_000_000_000,
+            count: 3
+        }
+    );
+
+    parsed.port = 443;
+    parsed.count = 3_000_000_000;
+    assert_eq!(parsed.to_setting(&mut cfg.get_root()), Ok(()));
+    assert_eq!(cfg.lookup("port").unwrap().get_type(), Type::TypeInt);
+    assert_eq!(cfg.lookup("count").unwrap().get_type(), Type::TypeInt64);
+    assert_eq!(Unsigned::from_setting(&mut cfg.get_root()), Ok(parsed));
+
+    let written = Config::new();
+    let too_big = Unsigned {
+        port: 1,
+        size: u64::MAX,
+        count: 1,
+    };
+    assert_eq!(
+        too_big.to_setting(&mut written.get_root()),
+        Err(LibconfigError::OutOfRange(String::from("size")))
+    );
+}
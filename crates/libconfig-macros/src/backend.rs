@@ -0,0 +1,16 @@
+//! The libconfig-sys bindings the config is parsed with, picked like
+//! libconfig's: `pure-rust`, then `capi`, then `cxx`.
+
+#[cfg(not(any(feature = "cxx", feature = "capi", feature = "pure-rust")))]
+compile_error!("one of the `cxx`, `capi` or `pure-rust` features must be enabled");
+
+#[cfg(feature = "pure-rust")]
+pub use libconfig_sys::native::*;
+
+#[cfg(all(feature = "capi", not(feature = "pure-rust")))]
+pub use libconfig_sys::capi::*;
+
+#[cfg(not(any(feature = "capi", feature = "pure-rust")))]
+pub use cxx::Exception;
+#[cfg(not(any(feature = "capi", feature = "pure-rust")))]
+pub use libconfig_sys::ffi::*;
@@ -0,0 +1,158 @@
+use backend::{
+    getElemFromSetting, getRootFromConfig, tryBoolFromSetting, tryF64FromSetting,
+    tryI32FromSetting, tryI64FromSetting, tryStringFromSetting, Config_ctor, Exception, Setting,
+    Type,
+};
+use proc_macro::TokenStream;
+use proc_macro2::{Literal, TokenStream as TokenStream2};
+use quote::quote;
+use std::ffi::{CStr, CString};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use syn::{parse_macro_input, LitStr};
+
+mod backend;
+
+/// Embeds a config file as a `libconfig::Config`.
+///
+/// The path is relative to the crate's `Cargo.toml`, and `@include`s are
+/// resolved relative to the directory of the config file. The file is parsed
+/// at compile time, so a syntax error fails the build and the expansion does
+/// not touch the file system at run time.
+#[proc_macro]
+pub fn include_config(input: TokenStream) -> TokenStream {
+    let path = parse_macro_input!(input as LitStr);
+    match expand(&path) {
+        Ok(value) => quote! {
+            ::libconfig::Config::from_value(&#value).expect("embedded config is valid")
+        },
+        Err(err) => err.to_compile_error(),
+    }
+    .into()
+}
+
+/// Like `include_config!`, but yields the `libconfig::Value` of the root
+/// group.
+#[proc_macro]
+pub fn include_config_value(input: TokenStream) -> TokenStream {
+    let path = parse_macro_input!(input as LitStr);
+    match expand(&path) {
+        Ok(value) => value,
+        Err(err) => err.to_compile_error(),
+    }
+    .into()
+}
+
+fn expand(path: &LitStr) -> syn::Result<TokenStream2> {
+    let manifest_dir = std::env::var("CARGO_MANIFEST_DIR").unwrap_or_default();
+    let file = Path::new(&manifest_dir).join(path.value());
+    if !file.is_file() {
+        return Err(syn::Error::new(
+            path.span(),
+            format!("cannot read config `{}`", file.display()),
+        ));
+    }
+    let include_dir = file.parent().unwrap_or(Path::new(".")).to_path_buf();
+    let value = parse(&file, &include_dir).map_err(|err| syn::Error::new(path.span(), err))?;
+    // Makes cargo rebuild the caller when the config or an include changes.
+    let tracked = tracked_files(&file, &include_dir)
+        .into_iter()
+        .map(|file| file.display().to_string());
+    Ok(quote! {
+        {
+            #(const _: &[u8] = ::core::include_bytes!(#tracked);)*
+            #value
+        }
+    })
+}
+
+fn parse(file: &Path, include_dir: &Path) -> Result<TokenStream2, String> {
+    let mut cfg = Config_ctor();
+    let file_name = CString::new(file.display().to_string()).expect("invalid file");
+    let include_dir = CString::new(include_dir.display().to_string()).expect("invalid file");
+    unsafe {
+        cfg.pin_mut().setIncludeDir(include_dir.as_ptr());
+        // Parse errors read "Parse error at <file>:<line> - <message>".
+        cfg.pin_mut()
+            .readFile(file_name.as_ptr())
+            .map_err(|err| err.what().to_string())?;
+        value_tokens(getRootFromConfig(cfg.as_ref().unwrap()))
+    }
+}
+
+unsafe fn value_tokens(mut setting: Pin<&mut Setting>) -> Result<TokenStream2, String> {
+    let error = |err: Exception| err.what().to_string();
+    Ok(match setting.getType() {
+        Type::TypeBoolean => {
+            let val = tryBoolFromSetting(&setting).map_err(error)?;
+            quote!(::libconfig::Value::Bool(#val))
+        }
+        Type::TypeInt => {
+            let val = Literal::i32_suffixed(tryI32FromSetting(&setting).map_err(error)?);
+            quote!(::libconfig::Value::Int(#val))
+        }
+        Type::TypeInt64 => {
+            let val = Literal::i64_suffixed(tryI64FromSetting(&setting).map_err(error)?);
+            quote!(::libconfig::Value::Int64(#val))
+        }
+        Type::TypeFloat => {
+            // Through the bits, so that the value is exact and infinities
+            // survive.
+            let bits = Literal::u64_suffixed(tryF64FromSetting(&setting).map_err(error)?.to_bits());
+            quote!(::libconfig::Value::Float(::core::primitive::f64::from_bits(#bits)))
+        }
+        Type::TypeString => {
+            let val = tryStringFromSetting(&setting).map_err(error)?.to_string();
+            quote!(::libconfig::Value::String(::std::string::String::from(#val)))
+        }
+        Type::TypeGroup | Type::TypeArray | Type::TypeList => {
+            let ty = setting.getType();
+            let mut elems = Vec::new();
+            for idx in 0..setting.getLength().map_err(error)? {
+                let mut child = getElemFromSetting(setting.as_mut(), idx as u32).map_err(error)?;
+                let value = value_tokens(child.as_mut())?;
+                elems.push(match ty {
+                    Type::TypeGroup => {
+                        let name = CStr::from_ptr(child.getName())
+                            .to_string_lossy()
+                            .into_owned();
+                        quote!((::std::string::String::from(#name), #value))
+                    }
+                    _ => value,
+                });
+            }
+            match ty {
+                Type::TypeGroup => quote!(::libconfig::Value::Group(::std::vec![#(#elems),*])),
+                Type::TypeArray => quote!(::libconfig::Value::Array(::std::vec![#(#elems),*])),
+                _ => quote!(::libconfig::Value::List(::std::vec![#(#elems),*])),
+            }
+        }
+        _ => return Err(String::from("unsupported setting type")),
+    })
+}
+
+// The config followed by every file it includes, directly or not. libconfig
+// only accepts `@include` at the start of a line.
+fn tracked_files(file: &Path, include_dir: &Path) -> Vec<PathBuf> {
+    let mut files = vec![file.to_path_buf()];
+    let mut next = 0;
+    while next < files.len() {
+        let text = fs::read_to_string(&files[next]).unwrap_or_default();
+        for line in text.lines() {
+            let Some(rest) = line
+                .trim_start_matches([' ', '\t'])
+                .strip_prefix("@include")
+                .and_then(|rest| rest.trim_start_matches([' ', '\t']).strip_prefix('"'))
+            else {
+                continue;
+            };
+            let include = include_dir.join(&rest[..rest.find('"').unwrap_or(rest.len())]);
+            if include.is_file() && !files.contains(&include) {
+                files.push(include);
+            }
+        }
+        next += 1;
+    }
+    files
+}
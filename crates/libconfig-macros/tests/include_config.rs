@@ -0,0 +1,24 @@
+use libconfig::{include_config, include_config_value, Value};
+
+#[test]
+fn ok_on_include_config_value() {
+    let value = include_config_value!("../input/test.cfg");
+    assert_eq!(value.get("val_int"), Some(&Value::Int(42)));
+    assert_eq!(value.get("val_u64"), Some(&Value::Int64(0xFFFFFFFFFF)));
+    assert_eq!(value.get("some_f64"), Some(&Value::Float(1e10)));
+    assert_eq!(
+        value.get("arr"),
+        Some(&Value::Array(vec![
+            Value::Int(3),
+            Value::Int(5),
+            Value::Int(8)
+        ]))
+    );
+}
+
+#[test]
+fn ok_on_include_config_with_include() {
+    let cfg = include_config!("../input/test_with_include.cfg");
+    assert_eq!(cfg.lookup_string("name"), Some(String::from("Some Name")));
+    assert_eq!(cfg.lookup_i32("outer.inner"), Some(3));
+}
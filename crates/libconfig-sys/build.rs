@@ -1,26 +1,133 @@
+#[cfg(any(feature = "cxx", feature = "capi"))]
+use std::{env, path::PathBuf};
+
+// The oldest supported libconfig++. Bindings added in later releases live in
+// their own bridge, built only when the headers are new enough.
+#[cfg(any(feature = "cxx", feature = "capi"))]
+const MIN_VERSION: &str = "1.5";
+
+// The C library is enough without the C++ bindings.
+#[cfg(any(feature = "cxx", feature = "capi"))]
+const PACKAGE: &str = if cfg!(feature = "cxx") {
+    "libconfig++"
+} else {
+    "libconfig"
+};
+
 fn main() {
-    println!("cargo:rustc-link-lib=static=config++");
-    println!(
-        "cargo:rustc-link-search={}",
-        libconfig_cpp::libconfig_path()
-            .join("lib")
-            .to_str()
-            .unwrap()
-    );
-
-    cxx_build::bridge("src/lib.rs")
+    println!("cargo:rustc-check-cfg=cfg(libconfig_1_6)");
+    // The `pure-rust` implementation needs nothing compiled or linked.
+    #[cfg(any(feature = "cxx", feature = "capi"))]
+    {
+        let include_paths = match libconfig_cpp::is_vendored() {
+            true => vendored(),
+            false => system(),
+        };
+        #[cfg(feature = "cxx")]
+        bridge(&include_paths);
+        #[cfg(feature = "capi")]
+        shim(&include_paths);
+    }
+
+    println!("cargo:rerun-if-changed=src/lib.rs");
+}
+
+#[cfg(feature = "cxx")]
+fn bridge(include_paths: &[PathBuf]) {
+    let mut bridges = vec!["src/lib.rs"];
+    if header_version(include_paths) >= (1, 6) {
+        println!("cargo:rustc-cfg=libconfig_1_6");
+        bridges.push("src/v1_6.rs");
+    }
+
+    let mut build = cxx_build::bridges(&bridges);
+    build
         .std("c++14")
         .include("include")
-        .include(
-            libconfig_cpp::libconfig_path()
-                .join("include")
-                .to_str()
-                .unwrap(),
-        )
-        .compile("wrapper");
+        .includes(include_paths);
+    for define in static_defines() {
+        build.define(define, None);
+    }
+    build.compile("wrapper");
 
     println!("cargo:rustc-link-lib=static=wrapper");
 
-    println!("cargo:rerun-if-changed=src/lib.rs");
+    println!("cargo:rerun-if-changed=src/v1_6.rs");
     println!("cargo:rerun-if-changed=include/wrapper.h");
+    println!("cargo:rerun-if-changed=include/v1_6.h");
+}
+
+#[cfg(feature = "capi")]
+fn shim(include_paths: &[PathBuf]) {
+    let mut build = cc::Build::new();
+    build.file("src/capi.c").includes(include_paths);
+    for define in static_defines() {
+        build.define(define, None);
+    }
+    build.compile("capi");
+
+    println!("cargo:rerun-if-changed=src/capi.c");
+}
+
+// What code including the headers must define for the library it links.
+#[cfg(any(feature = "cxx", feature = "capi"))]
+fn static_defines() -> &'static [&'static str] {
+    match libconfig_cpp::is_vendored() {
+        true => libconfig_cpp::STATIC_DEFINES,
+        false => &[],
+    }
+}
+
+// Builds the bundled sources, which libconfig-cpp links statically.
+#[cfg(any(feature = "cxx", feature = "capi"))]
+fn vendored() -> Vec<PathBuf> {
+    vec![libconfig_cpp::build(cfg!(feature = "cxx"))]
+}
+
+// Links the library found by pkg-config, dynamically unless
+// LIBCONFIG_STATIC is set to something other than 0.
+#[cfg(any(feature = "cxx", feature = "capi"))]
+fn system() -> Vec<PathBuf> {
+    println!("cargo:rerun-if-env-changed=LIBCONFIG_STATIC");
+    let statik = env::var("LIBCONFIG_STATIC").is_ok_and(|val| val != "0");
+    match pkg_config::Config::new()
+        .atleast_version(MIN_VERSION)
+        .statik(statik)
+        .probe(PACKAGE)
+    {
+        Ok(library) => library.include_paths,
+        Err(err) => panic!(
+            "{} >= {} not found through pkg-config: {}",
+            PACKAGE, MIN_VERSION, err
+        ),
+    }
+}
+
+// The (major, minor) version declared by the first libconfig.h++ found in
+// `include_paths`.
+#[cfg(feature = "cxx")]
+fn header_version(include_paths: &[PathBuf]) -> (u32, u32) {
+    let header = include_paths
+        .iter()
+        .map(|dir| dir.join("libconfig.h++"))
+        .find(|header| header.is_file())
+        .expect("libconfig.h++ not found");
+    let text = std::fs::read_to_string(&header).expect("libconfig.h++ readable");
+    let define = |name: &str| {
+        text.lines()
+            .find_map(|line| {
+                let mut words = line.split_whitespace();
+                match (words.next(), words.next(), words.next()) {
+                    (Some("#define"), Some(word), Some(value)) if word == name => {
+                        value.parse::<u32>().ok()
+                    }
+                    _ => None,
+                }
+            })
+            .unwrap_or_else(|| panic!("{} not defined in {}", name, header.display()))
+    };
+    (
+        define("LIBCONFIGXX_VER_MAJOR"),
+        define("LIBCONFIGXX_VER_MINOR"),
+    )
 }
@@ -0,0 +1,19 @@
+#pragma once
+#include <cstdint>
+#include <libconfig.h++>
+
+// Bindings for libconfig 1.6 and later, compiled only when build.rs finds
+// such headers. Kept apart from wrapper.h, which defines functions that
+// may only be compiled once.
+
+namespace libconfig {
+inline auto getFloatPrecisionFromConfig(const Config &config) -> uint16_t {
+  return config.getFloatPrecision();
+}
+
+inline auto setFloatPrecisionFromConfig(Config &config, uint16_t digits) {
+  config.setFloatPrecision(digits);
+}
+}  // namespace libconfig
+
+using namespace libconfig;
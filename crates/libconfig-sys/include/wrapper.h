@@ -1,7 +1,9 @@
 #pragma once
 #include <cstdint>
+#include <cstdio>
 #include <libconfig.h++>
 #include <memory>
+#include <stdexcept>
 #include <string>
 
 #include "rust/cxx.h"
@@ -18,7 +20,8 @@ template <typename Try, typename Fail>
 static void trycatch(Try &&func, Fail &&fail) noexcept try {
   func();
 } catch (const libconfig::ParseException &pex) {
-  fail("Parse error at " + std::string(pex.getFile()) + ":" +
+  fail("Parse error at " +
+       std::string(pex.getFile() != nullptr ? pex.getFile() : "") + ":" +
        std::to_string(pex.getLine()) + std::string(" - ") +
        std::string(pex.getError()));
 } catch (const std::exception &e) {
@@ -30,6 +33,23 @@ static void trycatch(Try &&func, Fail &&fail) noexcept try {
 
 typedef libconfig::Setting::Type Type;
 typedef libconfig::Setting::Format Format;
+// Mirrors libconfig::Config::Option, which lacks the later flags in older
+// releases. Flags are passed to libconfig as plain bits.
+enum class ConfigOption : uint32_t {
+  OptionNone = 0,
+  OptionAutoConvert = 0x01,
+  OptionSemicolonSeparators = 0x02,
+  OptionColonAssignmentForGroups = 0x04,
+  OptionColonAssignmentForNonGroups = 0x08,
+  OptionOpenBraceOnSeparateLine = 0x10,
+  OptionAllowScientificNotation = 0x20,
+  OptionFsync = 0x40,
+  OptionAllowOverrides = 0x80,
+};
+
+auto getVersionMajor() -> uint32_t { return LIBCONFIGXX_VER_MAJOR; }
+auto getVersionMinor() -> uint32_t { return LIBCONFIGXX_VER_MINOR; }
+auto getVersionRevision() -> uint32_t { return LIBCONFIGXX_VER_REVISION; }
 
 namespace libconfig {
 
@@ -69,6 +89,10 @@ auto addSetting(Setting &setting, const std::string &name, Setting::Type type)
   return setting.add(name, type);
 }
 
+auto addElement(Setting &setting, Setting::Type type) -> Setting & {
+  return setting.add(type);
+}
+
 auto removeSetting(Setting &setting, const std::string &name) {
   setting.remove(name);
 }
@@ -91,6 +115,10 @@ auto getPathFromSetting(const Setting &setting, std::string &path) -> void {
   path = setting.getPath();
 }
 
+auto getElemFromSetting(Setting &setting, uint32_t idx) -> Setting & {
+  return setting[static_cast<int>(idx)];
+}
+
 auto getParentFromSetting(Setting &setting) -> Setting & {
   return setting.getParent();
 }
@@ -107,6 +135,19 @@ auto getRootFromConfig(const Config &config) -> Setting & {
   return config.getRoot();
 }
 
+auto writeToString(const Config &config, std::string &out) -> void {
+  std::unique_ptr<FILE, int (*)(FILE *)> stream(std::tmpfile(), &std::fclose);
+  if (!stream) {
+    throw std::runtime_error("cannot create temporary file");
+  }
+  config.write(stream.get());
+  out.resize(std::ftell(stream.get()));
+  std::rewind(stream.get());
+  if (std::fread(&out[0], 1, out.size(), stream.get()) != out.size()) {
+    throw std::runtime_error("cannot read temporary file");
+  }
+}
+
 auto lookupSettingFromConfig(Config &config, const char *path) -> Setting & {
   return config.lookup(path);
 }
@@ -131,6 +172,20 @@ auto lookupValueI64FromConfig(const Config &config, const char *path,
   return false;
 }
 
+auto getOptionFromConfig(const Config &config, ConfigOption option) -> bool {
+  return (config.getOptions() & static_cast<int>(option)) != 0;
+}
+
+auto setOptionFromConfig(Config &config, ConfigOption option, bool flag) {
+  int options = config.getOptions();
+  if (flag) {
+    options |= static_cast<int>(option);
+  } else {
+    options &= ~static_cast<int>(option);
+  }
+  config.setOptions(options);
+}
+
 }  // namespace libconfig
 
 using namespace libconfig;
@@ -0,0 +1,132 @@
+/* Functions for the parts of the libconfig C API that are macros or need a
+ * C stdio stream. Everything else is called directly from capi.rs. */
+
+#include <libconfig.h>
+#include <stdio.h>
+#include <stdlib.h>
+
+#define CAPI_VERSION_AT_LEAST(major, minor) \
+  (LIBCONFIG_VER_MAJOR > (major) ||         \
+   (LIBCONFIG_VER_MAJOR == (major) && LIBCONFIG_VER_MINOR >= (minor)))
+
+config_t *capi_config_new(void) {
+  config_t *config = malloc(sizeof(config_t));
+  if (config != NULL) {
+    config_init(config);
+  }
+  return config;
+}
+
+void capi_config_free(config_t *config) {
+  config_destroy(config);
+  free(config);
+}
+
+config_setting_t *capi_config_root(const config_t *config) {
+  return config_root_setting(config);
+}
+
+const char *capi_config_include_dir(const config_t *config) {
+  return config_get_include_dir(config);
+}
+
+short capi_config_default_format(const config_t *config) {
+  return config_get_default_format(config);
+}
+
+unsigned short capi_config_tab_width(const config_t *config) {
+  return config_get_tab_width(config);
+}
+
+/* Returns 0 when libconfig has no float precision setting (before 1.6). */
+int capi_config_float_precision(const config_t *config,
+                                unsigned short *digits) {
+#if CAPI_VERSION_AT_LEAST(1, 6)
+  *digits = config_get_float_precision(config);
+  return 1;
+#else
+  (void)config;
+  (void)digits;
+  return 0;
+#endif
+}
+
+int capi_config_set_float_precision(config_t *config, unsigned short digits) {
+#if CAPI_VERSION_AT_LEAST(1, 6)
+  config_set_float_precision(config, digits);
+  return 1;
+#else
+  (void)config;
+  (void)digits;
+  return 0;
+#endif
+}
+
+int capi_config_error_type(const config_t *config) {
+  return config_error_type(config);
+}
+
+const char *capi_config_error_file(const config_t *config) {
+  return config_error_file(config);
+}
+
+int capi_config_error_line(const config_t *config) {
+  return config_error_line(config);
+}
+
+const char *capi_config_error_text(const config_t *config) {
+  return config_error_text(config);
+}
+
+/* Writes the config to a malloc'ed buffer that the caller frees with
+ * capi_free. Returns 0 on failure. */
+int capi_config_write_string(const config_t *config, char **out,
+                             size_t *len) {
+  FILE *stream = tmpfile();
+  long size;
+  int ok = 0;
+  if (stream == NULL) {
+    return 0;
+  }
+  config_write(config, stream);
+  size = ftell(stream);
+  if (size >= 0) {
+    rewind(stream);
+    *out = malloc((size_t)size + 1);
+    if (*out != NULL) {
+      *len = fread(*out, 1, (size_t)size, stream);
+      ok = *len == (size_t)size;
+      if (!ok) {
+        free(*out);
+      }
+    }
+  }
+  fclose(stream);
+  return ok;
+}
+
+void capi_free(void *ptr) { free(ptr); }
+
+int capi_setting_type(const config_setting_t *setting) {
+  return config_setting_type(setting);
+}
+
+const char *capi_setting_name(const config_setting_t *setting) {
+  return config_setting_name(setting);
+}
+
+config_setting_t *capi_setting_parent(const config_setting_t *setting) {
+  return config_setting_parent(setting);
+}
+
+int capi_setting_is_root(const config_setting_t *setting) {
+  return config_setting_is_root(setting);
+}
+
+int capi_setting_auto_convert(const config_setting_t *setting) {
+  return config_get_auto_convert(setting->config);
+}
+
+unsigned int capi_version_major(void) { return LIBCONFIG_VER_MAJOR; }
+unsigned int capi_version_minor(void) { return LIBCONFIG_VER_MINOR; }
+unsigned int capi_version_revision(void) { return LIBCONFIG_VER_REVISION; }
@@ -0,0 +1,1047 @@
+//! Bindings to the libconfig C API with the same shape as the cxx bridge in
+//! `ffi`, so that code written against one compiles against the other.
+//!
+//! Failures that the C++ library reports with exceptions are checked here
+//! and reported as `Exception`s carrying the same messages for parse errors.
+#![allow(non_snake_case)]
+
+pub use crate::common::{ConfigOption, Exception, Format, Owned, StringBuf, Type, UniquePtr};
+
+use std::ffi::{CStr, CString};
+use std::marker::PhantomPinned;
+use std::os::raw::{c_char, c_int, c_short, c_uint, c_ushort, c_void};
+use std::pin::Pin;
+use std::ptr;
+
+type Result<T> = std::result::Result<T, Exception>;
+
+mod sys {
+    use std::os::raw::{c_char, c_double, c_int, c_longlong, c_short, c_uint, c_ushort, c_void};
+
+    pub type config_t = c_void;
+    pub type config_setting_t = c_void;
+
+    pub const CONFIG_TYPE_NONE: c_int = 0;
+    pub const CONFIG_TYPE_GROUP: c_int = 1;
+    pub const CONFIG_TYPE_INT: c_int = 2;
+    pub const CONFIG_TYPE_INT64: c_int = 3;
+    pub const CONFIG_TYPE_FLOAT: c_int = 4;
+    pub const CONFIG_TYPE_STRING: c_int = 5;
+    pub const CONFIG_TYPE_BOOL: c_int = 6;
+    pub const CONFIG_TYPE_ARRAY: c_int = 7;
+    pub const CONFIG_TYPE_LIST: c_int = 8;
+
+    pub const CONFIG_ERR_PARSE: c_int = 2;
+
+    extern "C" {
+        pub fn capi_config_new() -> *mut config_t;
+        pub fn capi_config_free(config: *mut config_t);
+        pub fn capi_config_root(config: *const config_t) -> *mut config_setting_t;
+        pub fn capi_config_include_dir(config: *const config_t) -> *const c_char;
+        pub fn capi_config_default_format(config: *const config_t) -> c_short;
+        pub fn capi_config_tab_width(config: *const config_t) -> c_ushort;
+        pub fn capi_config_float_precision(config: *const config_t, digits: *mut c_ushort)
+            -> c_int;
+        pub fn capi_config_set_float_precision(config: *mut config_t, digits: c_ushort) -> c_int;
+        pub fn capi_config_error_type(config: *const config_t) -> c_int;
+        pub fn capi_config_error_file(config: *const config_t) -> *const c_char;
+        pub fn capi_config_error_line(config: *const config_t) -> c_int;
+        pub fn capi_config_error_text(config: *const config_t) -> *const c_char;
+        pub fn capi_config_write_string(
+            config: *const config_t,
+            out: *mut *mut c_char,
+            len: *mut usize,
+        ) -> c_int;
+        pub fn capi_free(ptr: *mut c_void);
+        pub fn capi_setting_type(setting: *const config_setting_t) -> c_int;
+        pub fn capi_setting_name(setting: *const config_setting_t) -> *const c_char;
+        pub fn capi_setting_parent(setting: *const config_setting_t) -> *mut config_setting_t;
+        pub fn capi_setting_is_root(setting: *const config_setting_t) -> c_int;
+        pub fn capi_setting_auto_convert(setting: *const config_setting_t) -> c_int;
+        pub fn capi_version_major() -> c_uint;
+        pub fn capi_version_minor() -> c_uint;
+        pub fn capi_version_revision() -> c_uint;
+
+        pub fn config_read_file(config: *mut config_t, filename: *const c_char) -> c_int;
+        pub fn config_read_string(config: *mut config_t, input: *const c_char) -> c_int;
+        pub fn config_write_file(config: *mut config_t, filename: *const c_char) -> c_int;
+        pub fn config_set_include_dir(config: *mut config_t, include_dir: *const c_char);
+        pub fn config_get_options(config: *const config_t) -> c_int;
+        pub fn config_set_options(config: *mut config_t, options: c_int);
+        pub fn config_set_default_format(config: *mut config_t, format: c_short);
+        pub fn config_set_tab_width(config: *mut config_t, width: c_ushort);
+        pub fn config_lookup(config: *const config_t, path: *const c_char)
+            -> *mut config_setting_t;
+
+        pub fn config_setting_lookup(
+            setting: *mut config_setting_t,
+            path: *const c_char,
+        ) -> *mut config_setting_t;
+        pub fn config_setting_get_member(
+            setting: *const config_setting_t,
+            name: *const c_char,
+        ) -> *mut config_setting_t;
+        pub fn config_setting_get_elem(
+            setting: *const config_setting_t,
+            idx: c_uint,
+        ) -> *mut config_setting_t;
+        pub fn config_setting_add(
+            parent: *mut config_setting_t,
+            name: *const c_char,
+            type_: c_int,
+        ) -> *mut config_setting_t;
+        pub fn config_setting_remove(parent: *mut config_setting_t, name: *const c_char) -> c_int;
+        pub fn config_setting_remove_elem(parent: *mut config_setting_t, idx: c_uint) -> c_int;
+        pub fn config_setting_length(setting: *const config_setting_t) -> c_int;
+        pub fn config_setting_index(setting: *const config_setting_t) -> c_int;
+        pub fn config_setting_get_format(setting: *const config_setting_t) -> c_short;
+        pub fn config_setting_set_format(setting: *mut config_setting_t, format: c_short) -> c_int;
+        pub fn config_setting_get_int(setting: *const config_setting_t) -> c_int;
+        pub fn config_setting_get_int64(setting: *const config_setting_t) -> c_longlong;
+        pub fn config_setting_get_float(setting: *const config_setting_t) -> c_double;
+        pub fn config_setting_get_bool(setting: *const config_setting_t) -> c_int;
+        pub fn config_setting_get_string(setting: *const config_setting_t) -> *const c_char;
+        pub fn config_setting_set_int(setting: *mut config_setting_t, value: c_int) -> c_int;
+        pub fn config_setting_set_int64(setting: *mut config_setting_t, value: c_longlong)
+            -> c_int;
+        pub fn config_setting_set_float(setting: *mut config_setting_t, value: c_double) -> c_int;
+        pub fn config_setting_set_bool(setting: *mut config_setting_t, value: c_int) -> c_int;
+        pub fn config_setting_set_string(
+            setting: *mut config_setting_t,
+            value: *const c_char,
+        ) -> c_int;
+    }
+}
+
+impl Type {
+    fn from_c(code: c_int) -> Type {
+        match code {
+            sys::CONFIG_TYPE_GROUP => Type::TypeGroup,
+            sys::CONFIG_TYPE_INT => Type::TypeInt,
+            sys::CONFIG_TYPE_INT64 => Type::TypeInt64,
+            sys::CONFIG_TYPE_FLOAT => Type::TypeFloat,
+            sys::CONFIG_TYPE_STRING => Type::TypeString,
+            sys::CONFIG_TYPE_BOOL => Type::TypeBoolean,
+            sys::CONFIG_TYPE_ARRAY => Type::TypeArray,
+            sys::CONFIG_TYPE_LIST => Type::TypeList,
+            _ => Type::TypeNone,
+        }
+    }
+
+    fn to_c(self) -> c_int {
+        match self {
+            Type::TypeGroup => sys::CONFIG_TYPE_GROUP,
+            Type::TypeInt => sys::CONFIG_TYPE_INT,
+            Type::TypeInt64 => sys::CONFIG_TYPE_INT64,
+            Type::TypeFloat => sys::CONFIG_TYPE_FLOAT,
+            Type::TypeString => sys::CONFIG_TYPE_STRING,
+            Type::TypeBoolean => sys::CONFIG_TYPE_BOOL,
+            Type::TypeArray => sys::CONFIG_TYPE_ARRAY,
+            Type::TypeList => sys::CONFIG_TYPE_LIST,
+            _ => sys::CONFIG_TYPE_NONE,
+        }
+    }
+}
+
+/// A `config_setting_t`, owned by its config.
+#[repr(C)]
+pub struct Setting {
+    _private: [u8; 0],
+    _pinned: PhantomPinned,
+}
+
+/// A `config_t`.
+#[repr(C)]
+pub struct Config {
+    _private: [u8; 0],
+    _pinned: PhantomPinned,
+}
+
+impl Owned for Config {
+    unsafe fn release(ptr: *mut Self) {
+        sys::capi_config_free(ptr.cast());
+    }
+}
+
+/// Walks the children of an aggregate setting.
+pub struct SettingIterator {
+    parent: *const sys::config_setting_t,
+    next: c_uint,
+}
+
+impl Owned for SettingIterator {
+    unsafe fn release(ptr: *mut Self) {
+        drop(Box::from_raw(ptr));
+    }
+}
+
+unsafe fn wrap<'c>(ptr: *mut sys::config_setting_t) -> Result<Pin<&'c mut Setting>> {
+    match ptr.is_null() {
+        true => Err(Exception::new("SettingNotFoundException")),
+        false => Ok(Pin::new_unchecked(&mut *ptr.cast::<Setting>())),
+    }
+}
+
+fn to_c(s: &StringBuf) -> Result<CString> {
+    CString::new(s.to_str()).map_err(|_| Exception::new("string contains NUL"))
+}
+
+fn type_error() -> Exception {
+    Exception::new("SettingTypeException")
+}
+
+fn checked(ok: c_int) -> Result<()> {
+    match ok {
+        0 => Err(type_error()),
+        _ => Ok(()),
+    }
+}
+
+impl Setting {
+    fn raw(&self) -> *mut sys::config_setting_t {
+        self as *const Setting as *mut sys::config_setting_t
+    }
+
+    // Accesses as `type_` are allowed for the setting's own type, between the
+    // two integer types, and between numbers when the config converts them
+    // automatically.
+    unsafe fn assert_type(&self, type_: Type) -> Result<()> {
+        let integer = |t: Type| matches!(t, Type::TypeInt | Type::TypeInt64);
+        let numeric = integer(type_) || type_ == Type::TypeFloat;
+        match self.getType() == type_
+            || (integer(type_) && integer(self.getType()))
+            || (numeric && self.isNumber() && sys::capi_setting_auto_convert(self.raw()) != 0)
+        {
+            true => Ok(()),
+            false => Err(type_error()),
+        }
+    }
+
+    unsafe fn lookup_setting(&self, path: *const c_char) -> Result<&Setting> {
+        Ok(wrap(sys::config_setting_lookup(self.raw(), path))?
+            .into_ref()
+            .get_ref())
+    }
+
+    /// # Safety
+    ///
+    /// `name` must point to a NUL-terminated string. The setting must belong to
+    /// a config that is still alive and must not have been removed.
+    pub unsafe fn exists(&self, name: *const c_char) -> bool {
+        self.isGroup() && !sys::config_setting_get_member(self.raw(), name).is_null()
+    }
+
+    /// # Safety
+    ///
+    /// `path` must point to a NUL-terminated string. The setting must belong to
+    /// a config that is still alive and must not have been removed.
+    pub unsafe fn lookup_bool(&self, path: *const c_char, value: &mut bool) -> bool {
+        store(
+            self.lookup_setting(path)
+                .and_then(|s| tryBoolFromSetting(s)),
+            value,
+        )
+    }
+
+    /// # Safety
+    ///
+    /// `path` must point to a NUL-terminated string. The setting must belong to
+    /// a config that is still alive and must not have been removed.
+    pub unsafe fn lookup_i32(&self, path: *const c_char, value: &mut i32) -> bool {
+        store(
+            self.lookup_setting(path).and_then(|s| tryI32FromSetting(s)),
+            value,
+        )
+    }
+
+    /// # Safety
+    ///
+    /// `path` must point to a NUL-terminated string. The setting must belong to
+    /// a config that is still alive and must not have been removed.
+    pub unsafe fn lookup_u32(&self, path: *const c_char, value: &mut u32) -> bool {
+        let setting = self.lookup_setting(path);
+        store(
+            setting
+                .and_then(|s| tryI32FromSetting(s))
+                .map(|v| v.max(0) as u32),
+            value,
+        )
+    }
+
+    /// # Safety
+    ///
+    /// `path` must point to a NUL-terminated string. The setting must belong to
+    /// a config that is still alive and must not have been removed.
+    pub unsafe fn lookup_f32(&self, path: *const c_char, value: &mut f32) -> bool {
+        store(
+            self.lookup_setting(path).and_then(|s| tryF32FromSetting(s)),
+            value,
+        )
+    }
+
+    /// # Safety
+    ///
+    /// `path` must point to a NUL-terminated string. The setting must belong to
+    /// a config that is still alive and must not have been removed.
+    pub unsafe fn lookup_f64(&self, path: *const c_char, value: &mut f64) -> bool {
+        store(
+            self.lookup_setting(path).and_then(|s| tryF64FromSetting(s)),
+            value,
+        )
+    }
+
+    /// # Safety
+    ///
+    /// `path` must point to a NUL-terminated string. The setting must belong to
+    /// a config that is still alive and must not have been removed.
+    pub unsafe fn lookup_string(&self, path: *const c_char, value: Pin<&mut StringBuf>) -> bool {
+        match self
+            .lookup_setting(path)
+            .and_then(|s| tryStringFromSetting(s))
+        {
+            Ok(val) => {
+                value.get_mut().set(val);
+                true
+            }
+            Err(_) => false,
+        }
+    }
+
+    /// # Safety
+    ///
+    /// The setting must belong to a config that is still alive and must not
+    /// have been removed. The name returned lives as long as the setting.
+    pub unsafe fn getName(&self) -> *const c_char {
+        sys::capi_setting_name(self.raw())
+    }
+
+    /// # Safety
+    ///
+    /// The setting must belong to a config that is still alive and must not
+    /// have been removed.
+    pub unsafe fn isRoot(&self) -> bool {
+        sys::capi_setting_is_root(self.raw()) != 0
+    }
+
+    /// # Safety
+    ///
+    /// The setting must belong to a config that is still alive and must not
+    /// have been removed.
+    pub unsafe fn getIndex(&self) -> i32 {
+        sys::config_setting_index(self.raw())
+    }
+
+    /// # Safety
+    ///
+    /// The setting must belong to a config that is still alive and must not
+    /// have been removed.
+    pub unsafe fn getType(&self) -> Type {
+        Type::from_c(sys::capi_setting_type(self.raw()))
+    }
+
+    /// # Safety
+    ///
+    /// The setting must belong to a config that is still alive and must not
+    /// have been removed.
+    pub unsafe fn getFormat(&self) -> Format {
+        Format {
+            repr: sys::config_setting_get_format(self.raw()) as u32,
+        }
+    }
+
+    /// # Safety
+    ///
+    /// The setting must belong to a config that is still alive and must not
+    /// have been removed.
+    pub unsafe fn setFormat(self: Pin<&mut Self>, format: Format) {
+        let format = match (self.getType(), format) {
+            (Type::TypeInt | Type::TypeInt64, Format::FormatHex) => Format::FormatHex,
+            _ => Format::FormatDefault,
+        };
+        sys::config_setting_set_format(self.raw(), format.repr as c_short);
+    }
+
+    /// # Safety
+    ///
+    /// The setting must belong to a config that is still alive and must not
+    /// have been removed.
+    pub unsafe fn getLength(&self) -> Result<i32> {
+        Ok(sys::config_setting_length(self.raw()))
+    }
+
+    /// # Safety
+    ///
+    /// The setting must belong to a config that is still alive and must not
+    /// have been removed.
+    pub unsafe fn isGroup(&self) -> bool {
+        self.getType() == Type::TypeGroup
+    }
+
+    /// # Safety
+    ///
+    /// The setting must belong to a config that is still alive and must not
+    /// have been removed.
+    pub unsafe fn isArray(&self) -> bool {
+        self.getType() == Type::TypeArray
+    }
+
+    /// # Safety
+    ///
+    /// The setting must belong to a config that is still alive and must not
+    /// have been removed.
+    pub unsafe fn isList(&self) -> bool {
+        self.getType() == Type::TypeList
+    }
+
+    /// # Safety
+    ///
+    /// The setting must belong to a config that is still alive and must not
+    /// have been removed.
+    pub unsafe fn isAggregate(&self) -> bool {
+        matches!(
+            self.getType(),
+            Type::TypeGroup | Type::TypeArray | Type::TypeList
+        )
+    }
+
+    /// # Safety
+    ///
+    /// The setting must belong to a config that is still alive and must not
+    /// have been removed.
+    pub unsafe fn isScalar(&self) -> bool {
+        matches!(
+            self.getType(),
+            Type::TypeInt
+                | Type::TypeInt64
+                | Type::TypeFloat
+                | Type::TypeString
+                | Type::TypeBoolean
+        )
+    }
+
+    /// # Safety
+    ///
+    /// The setting must belong to a config that is still alive and must not
+    /// have been removed.
+    pub unsafe fn isNumber(&self) -> bool {
+        matches!(
+            self.getType(),
+            Type::TypeInt | Type::TypeInt64 | Type::TypeFloat
+        )
+    }
+
+    /// # Safety
+    ///
+    /// The setting must belong to a config that is still alive and must not
+    /// have been removed.
+    pub unsafe fn isString(&self) -> bool {
+        self.getType() == Type::TypeString
+    }
+}
+
+fn store<T>(result: Result<T>, value: &mut T) -> bool {
+    match result {
+        Ok(val) => {
+            *value = val;
+            true
+        }
+        Err(_) => false,
+    }
+}
+
+/// # Safety
+///
+/// The setting must belong to a config that is still alive and must not have
+/// been removed.
+pub unsafe fn setBool(setting: Pin<&mut Setting>, val: bool) -> Result<()> {
+    setting.assert_type(Type::TypeBoolean)?;
+    checked(sys::config_setting_set_bool(setting.raw(), val as c_int))
+}
+
+/// # Safety
+///
+/// The setting must belong to a config that is still alive and must not have
+/// been removed.
+pub unsafe fn setI32(setting: Pin<&mut Setting>, val: i32) -> Result<()> {
+    setting.assert_type(Type::TypeInt)?;
+    checked(sys::config_setting_set_int(setting.raw(), val))
+}
+
+/// # Safety
+///
+/// The setting must belong to a config that is still alive and must not have
+/// been removed.
+pub unsafe fn setI64(setting: Pin<&mut Setting>, val: i64) -> Result<()> {
+    setting.assert_type(Type::TypeInt64)?;
+    if setting.getType() == Type::TypeInt && i32::try_from(val).is_err() {
+        return Err(type_error());
+    }
+    checked(sys::config_setting_set_int64(setting.raw(), val))
+}
+
+/// # Safety
+///
+/// The setting must belong to a config that is still alive and must not have
+/// been removed.
+pub unsafe fn setF32(setting: Pin<&mut Setting>, val: f32) -> Result<()> {
+    setF64(setting, val as f64)
+}
+
+/// # Safety
+///
+/// The setting must belong to a config that is still alive and must not have
+/// been removed.
+pub unsafe fn setF64(setting: Pin<&mut Setting>, val: f64) -> Result<()> {
+    setting.assert_type(Type::TypeFloat)?;
+    checked(sys::config_setting_set_float(setting.raw(), val))
+}
+
+/// # Safety
+///
+/// The setting must belong to a config that is still alive and must not have
+/// been removed.
+pub unsafe fn setString(setting: Pin<&mut Setting>, val: &StringBuf) -> Result<()> {
+    setting.assert_type(Type::TypeString)?;
+    let val = to_c(val)?;
+    checked(sys::config_setting_set_string(setting.raw(), val.as_ptr()))
+}
+
+/// # Safety
+///
+/// `path` must point to a NUL-terminated string. The setting must belong to a
+/// config that is still alive and must not have been removed.
+pub unsafe fn lookupValueI64FromSetting(
+    setting: &Setting,
+    path: *const c_char,
+    value: &mut i64,
+) -> bool {
+    store(
+        setting
+            .lookup_setting(path)
+            .and_then(|s| tryI64FromSetting(s)),
+        value,
+    )
+}
+
+/// # Safety
+///
+/// `path` must point to a NUL-terminated string. The setting must belong to a
+/// config that is still alive and must not have been removed.
+pub unsafe fn lookupValueU64FromSetting(
+    setting: &Setting,
+    path: *const c_char,
+    value: &mut u64,
+) -> bool {
+    let found = setting.lookup_setting(path);
+    store(
+        found
+            .and_then(|s| tryI64FromSetting(s))
+            .map(|v| v.max(0) as u64),
+        value,
+    )
+}
+
+/// # Safety
+///
+/// The setting must belong to a config that is still alive and must not have
+/// been removed.
+pub unsafe fn addSetting<'c>(
+    setting: Pin<&'c mut Setting>,
+    name: &StringBuf,
+    libtype: Type,
+) -> Result<Pin<&'c mut Setting>> {
+    if !setting.isGroup() || libtype == Type::TypeNone {
+        return Err(type_error());
+    }
+    let name = to_c(name)?;
+    wrap(sys::config_setting_add(
+        setting.raw(),
+        name.as_ptr(),
+        libtype.to_c(),
+    ))
+    .map_err(|_| Exception::new("SettingNameException"))
+}
+
+/// # Safety
+///
+/// The setting must belong to a config that is still alive and must not have
+/// been removed.
+pub unsafe fn addElement(setting: Pin<&mut Setting>, libtype: Type) -> Result<Pin<&mut Setting>> {
+    // Array elements share one scalar type.
+    let allowed = match setting.getType() {
+        Type::TypeList => libtype != Type::TypeNone,
+        Type::TypeArray => match sys::config_setting_get_elem(setting.raw(), 0) {
+            first if first.is_null() => setting_is_scalar_type(libtype),
+            first => Type::from_c(sys::capi_setting_type(first)) == libtype,
+        },
+        _ => false,
+    };
+    if !allowed {
+        return Err(type_error());
+    }
+    wrap(sys::config_setting_add(
+        setting.raw(),
+        ptr::null(),
+        libtype.to_c(),
+    ))
+}
+
+fn setting_is_scalar_type(libtype: Type) -> bool {
+    matches!(
+        libtype,
+        Type::TypeInt | Type::TypeInt64 | Type::TypeFloat | Type::TypeString | Type::TypeBoolean
+    )
+}
+
+/// # Safety
+///
+/// The setting must belong to a config that is still alive and must not have
+/// been removed. Handles to the removed member and its descendants must not be
+/// used afterwards.
+pub unsafe fn removeSetting(setting: Pin<&mut Setting>, name: &StringBuf) -> Result<()> {
+    if !setting.isGroup() {
+        return Err(type_error());
+    }
+    let name = to_c(name)?;
+    match sys::config_setting_remove(setting.raw(), name.as_ptr()) {
+        0 => Err(Exception::new("SettingNotFoundException")),
+        _ => Ok(()),
+    }
+}
+
+/// # Safety
+///
+/// The setting must belong to a config that is still alive and must not have
+/// been removed. Handles to the removed element and its descendants must not be
+/// used afterwards.
+pub unsafe fn removeSettingByIndex(setting: Pin<&mut Setting>, idx: u32) -> Result<()> {
+    if !setting.isAggregate() {
+        return Err(type_error());
+    }
+    match sys::config_setting_remove_elem(setting.raw(), idx) {
+        0 => Err(Exception::new("SettingNotFoundException")),
+        _ => Ok(()),
+    }
+}
+
+/// # Safety
+///
+/// The setting must belong to a config that is still alive and must not have
+/// been removed.
+pub unsafe fn tryBoolFromSetting(setting: &Setting) -> Result<bool> {
+    setting.assert_type(Type::TypeBoolean)?;
+    Ok(sys::config_setting_get_bool(setting.raw()) != 0)
+}
+
+/// # Safety
+///
+/// The setting must belong to a config that is still alive and must not have
+/// been removed.
+pub unsafe fn tryI32FromSetting(setting: &Setting) -> Result<i32> {
+    setting.assert_type(Type::TypeInt)?;
+    match setting.getType() {
+        Type::TypeInt64 => {
+            i32::try_from(sys::config_setting_get_int64(setting.raw())).map_err(|_| type_error())
+        }
+        _ => Ok(sys::config_setting_get_int(setting.raw())),
+    }
+}
+
+/// # Safety
+///
+/// The setting must belong to a config that is still alive and must not have
+/// been removed.
+pub unsafe fn tryI64FromSetting(setting: &Setting) -> Result<i64> {
+    setting.assert_type(Type::TypeInt64)?;
+    Ok(sys::config_setting_get_int64(setting.raw()))
+}
+
+/// # Safety
+///
+/// The setting must belong to a config that is still alive and must not have
+/// been removed.
+pub unsafe fn tryF32FromSetting(setting: &Setting) -> Result<f32> {
+    Ok(tryF64FromSetting(setting)? as f32)
+}
+
+/// # Safety
+///
+/// The setting must belong to a config that is still alive and must not have
+/// been removed.
+pub unsafe fn tryF64FromSetting(setting: &Setting) -> Result<f64> {
+    setting.assert_type(Type::TypeFloat)?;
+    Ok(sys::config_setting_get_float(setting.raw()))
+}
+
+/// # Safety
+///
+/// The setting must belong to a config that is still alive and must not have
+/// been removed.
+pub unsafe fn tryStringFromSetting(setting: &Setting) -> Result<String> {
+    setting.assert_type(Type::TypeString)?;
+    let val = sys::config_setting_get_string(setting.raw());
+    Ok(CStr::from_ptr(val).to_string_lossy().into_owned())
+}
+
+/// # Safety
+///
+/// `path` must point to a NUL-terminated string. The setting must belong to a
+/// config that is still alive and must not have been removed.
+pub unsafe fn lookupSettingFromSetting(
+    setting: Pin<&mut Setting>,
+    path: *const c_char,
+) -> Result<Pin<&mut Setting>> {
+    wrap(sys::config_setting_lookup(setting.raw(), path))
+}
+
+// Built like libconfig++ does: names joined with '.', and `[index]` for
+// elements of arrays and lists.
+/// # Safety
+///
+/// The setting must belong to a config that is still alive and must not have
+/// been removed.
+pub unsafe fn getPathFromSetting(setting: &Setting, path: Pin<&mut StringBuf>) {
+    let mut parts = Vec::new();
+    let mut current = setting.raw();
+    while sys::capi_setting_is_root(current) == 0 {
+        let name = sys::capi_setting_name(current);
+        parts.push(match name.is_null() {
+            true => format!("[{}]", sys::config_setting_index(current)),
+            false => CStr::from_ptr(name).to_string_lossy().into_owned(),
+        });
+        current = sys::capi_setting_parent(current);
+    }
+    parts.reverse();
+    path.get_mut().set(parts.join("."));
+}
+
+/// # Safety
+///
+/// The setting must belong to a config that is still alive and must not have
+/// been removed.
+pub unsafe fn getElemFromSetting(
+    setting: Pin<&mut Setting>,
+    idx: u32,
+) -> Result<Pin<&mut Setting>> {
+    if !setting.isAggregate() {
+        return Err(type_error());
+    }
+    wrap(sys::config_setting_get_elem(setting.raw(), idx))
+}
+
+/// # Safety
+///
+/// The setting must belong to a config that is still alive and must not have
+/// been removed. The parent returned must not be used at the same time as
+/// another handle to it.
+pub unsafe fn getParentFromSetting(setting: Pin<&mut Setting>) -> Result<Pin<&mut Setting>> {
+    wrap(sys::capi_setting_parent(setting.raw()))
+}
+
+/// # Safety
+///
+/// The setting must belong to a config that is still alive and must not have
+/// been removed. The iterator must not be used after the setting or its
+/// children are changed or dropped.
+pub unsafe fn getSettingIter(setting: Pin<&mut Setting>) -> Result<UniquePtr<SettingIterator>> {
+    Ok(UniquePtr::from_raw(Box::into_raw(Box::new(
+        SettingIterator {
+            parent: setting.raw(),
+            next: 0,
+        },
+    ))))
+}
+
+/// # Safety
+///
+/// Must not be called more often than the iterated setting has children, and
+/// `'c` must not outlive that setting.
+pub unsafe fn getNextFromIter<'c>(iter: &mut UniquePtr<SettingIterator>) -> Pin<&'c mut Setting> {
+    let iter = iter.pin_mut().get_mut();
+    let elem = sys::config_setting_get_elem(iter.parent, iter.next);
+    iter.next += 1;
+    wrap(elem).expect("iterated past the end of a setting")
+}
+
+pub fn Config_ctor() -> UniquePtr<Config> {
+    let config = unsafe { sys::capi_config_new() };
+    assert!(!config.is_null(), "cannot allocate config_t");
+    UniquePtr::from_raw(config.cast())
+}
+
+impl Config {
+    fn raw(&self) -> *mut sys::config_t {
+        self as *const Config as *mut sys::config_t
+    }
+
+    // The exception libconfig++ throws for the last failed read or write.
+    unsafe fn error(&self) -> Exception {
+        if sys::capi_config_error_type(self.raw()) != sys::CONFIG_ERR_PARSE {
+            return Exception::new("FileIOException");
+        }
+        let text = |s: *const c_char| match s.is_null() {
+            true => String::new(),
+            false => CStr::from_ptr(s).to_string_lossy().into_owned(),
+        };
+        Exception::parse(
+            &text(sys::capi_config_error_file(self.raw())),
+            sys::capi_config_error_line(self.raw()),
+            &text(sys::capi_config_error_text(self.raw())),
+        )
+    }
+
+    unsafe fn root(&self) -> &Setting {
+        wrap(sys::capi_config_root(self.raw()))
+            .expect("config has a root")
+            .into_ref()
+            .get_ref()
+    }
+
+    /// # Safety
+    ///
+    /// `filename` must point to a NUL-terminated string.
+    pub unsafe fn readFile(self: Pin<&mut Self>, filename: *const c_char) -> Result<()> {
+        match sys::config_read_file(self.raw(), filename) {
+            0 => Err(self.error()),
+            _ => Ok(()),
+        }
+    }
+
+    /// # Safety
+    ///
+    /// None beyond a valid config; unsafe only to match `ffi`.
+    pub unsafe fn writeFile(self: Pin<&mut Self>, filename: &StringBuf) -> Result<()> {
+        let filename = to_c(filename)?;
+        match sys::config_write_file(self.raw(), filename.as_ptr()) {
+            0 => Err(Exception::new("FileIOException")),
+            _ => Ok(()),
+        }
+    }
+
+    /// # Safety
+    ///
+    /// None beyond a valid config; unsafe only to match `ffi`.
+    pub unsafe fn readString(self: Pin<&mut Self>, input: &StringBuf) -> Result<()> {
+        let input = to_c(input)?;
+        match sys::config_read_string(self.raw(), input.as_ptr()) {
+            0 => Err(self.error()),
+            _ => Ok(()),
+        }
+    }
+
+    /// # Safety
+    ///
+    /// `path` must be null or point to a NUL-terminated string.
+    pub unsafe fn setIncludeDir(self: Pin<&mut Self>, path: *const c_char) {
+        sys::config_set_include_dir(self.raw(), path)
+    }
+
+    /// # Safety
+    ///
+    /// The directory returned is only valid until the next `setIncludeDir`.
+    pub unsafe fn getIncludeDir(&self) -> *const c_char {
+        sys::capi_config_include_dir(self.raw())
+    }
+
+    /// # Safety
+    ///
+    /// None beyond a valid config; unsafe only to match `ffi`.
+    pub unsafe fn getOptions(&self) -> i32 {
+        sys::config_get_options(self.raw())
+    }
+
+    /// # Safety
+    ///
+    /// The setting must belong to a config that is still alive and must not
+    /// have been removed.
+    pub unsafe fn setOptions(self: Pin<&mut Self>, options: i32) {
+        sys::config_set_options(self.raw(), options)
+    }
+
+    /// # Safety
+    ///
+    /// None beyond a valid config; unsafe only to match `ffi`.
+    pub unsafe fn getDefaultFormat(&self) -> Format {
+        Format {
+            repr: sys::capi_config_default_format(self.raw()) as u32,
+        }
+    }
+
+    /// # Safety
+    ///
+    /// The setting must belong to a config that is still alive and must not
+    /// have been removed.
+    pub unsafe fn setDefaultFormat(self: Pin<&mut Self>, format: Format) {
+        sys::config_set_default_format(self.raw(), format.repr as c_short)
+    }
+
+    /// # Safety
+    ///
+    /// None beyond a valid config; unsafe only to match `ffi`.
+    pub unsafe fn getTabWidth(&self) -> u16 {
+        sys::capi_config_tab_width(self.raw())
+    }
+
+    /// # Safety
+    ///
+    /// The setting must belong to a config that is still alive and must not
+    /// have been removed.
+    pub unsafe fn setTabWidth(self: Pin<&mut Self>, width: u16) {
+        sys::config_set_tab_width(self.raw(), width as c_ushort)
+    }
+
+    /// # Safety
+    ///
+    /// `path` must point to a NUL-terminated string.
+    pub unsafe fn exists(&self, path: *const c_char) -> bool {
+        !sys::config_lookup(self.raw(), path).is_null()
+    }
+
+    /// # Safety
+    ///
+    /// `path` must point to a NUL-terminated string.
+    pub unsafe fn lookup_bool(&self, path: *const c_char, value: &mut bool) -> bool {
+        self.root().lookup_bool(path, value)
+    }
+
+    /// # Safety
+    ///
+    /// `path` must point to a NUL-terminated string.
+    pub unsafe fn lookup_i32(&self, path: *const c_char, value: &mut i32) -> bool {
+        self.root().lookup_i32(path, value)
+    }
+
+    /// # Safety
+    ///
+    /// `path` must point to a NUL-terminated string.
+    pub unsafe fn lookup_u32(&self, path: *const c_char, value: &mut u32) -> bool {
+        self.root().lookup_u32(path, value)
+    }
+
+    /// # Safety
+    ///
+    /// `path` must point to a NUL-terminated string.
+    pub unsafe fn lookup_f32(&self, path: *const c_char, value: &mut f32) -> bool {
+        self.root().lookup_f32(path, value)
+    }
+
+    /// # Safety
+    ///
+    /// `path` must point to a NUL-terminated string.
+    pub unsafe fn lookup_f64(&self, path: *const c_char, value: &mut f64) -> bool {
+        self.root().lookup_f64(path, value)
+    }
+
+    /// # Safety
+    ///
+    /// `path` must point to a NUL-terminated string.
+    pub unsafe fn lookup_string(&self, path: *const c_char, value: Pin<&mut StringBuf>) -> bool {
+        self.root().lookup_string(path, value)
+    }
+}
+
+/// # Safety
+///
+/// `path` must point to a NUL-terminated string.
+pub unsafe fn lookupValueI64FromConfig(
+    config: &Config,
+    path: *const c_char,
+    value: &mut i64,
+) -> bool {
+    lookupValueI64FromSetting(config.root(), path, value)
+}
+
+/// # Safety
+///
+/// `path` must point to a NUL-terminated string.
+pub unsafe fn lookupValueU64FromConfig(
+    config: &Config,
+    path: *const c_char,
+    value: &mut u64,
+) -> bool {
+    lookupValueU64FromSetting(config.root(), path, value)
+}
+
+/// # Safety
+///
+/// None beyond a valid config; unsafe only to match `ffi`.
+pub unsafe fn writeToString(config: &Config, out: Pin<&mut StringBuf>) -> Result<()> {
+    let mut buf: *mut c_char = ptr::null_mut();
+    let mut len = 0;
+    if sys::capi_config_write_string(config.raw(), &mut buf, &mut len) == 0 {
+        return Err(Exception::new("cannot write config to a temporary file"));
+    }
+    let bytes = std::slice::from_raw_parts(buf.cast::<u8>(), len);
+    out.get_mut()
+        .set(String::from_utf8_lossy(bytes).into_owned());
+    sys::capi_free(buf.cast::<c_void>());
+    Ok(())
+}
+
+/// # Safety
+///
+/// `config` must be valid for `'c`, and no other handle to its root may be used
+/// while the one returned is.
+pub unsafe fn getRootFromConfig<'c>(config: *const Config) -> Pin<&'c mut Setting> {
+    wrap(sys::capi_config_root((*config).raw())).expect("config has a root")
+}
+
+/// # Safety
+///
+/// `path` must point to a NUL-terminated string.
+pub unsafe fn lookupSettingFromConfig(
+    cfg: Pin<&mut Config>,
+    path: *const c_char,
+) -> Result<Pin<&mut Setting>> {
+    wrap(sys::config_lookup(cfg.raw(), path))
+}
+
+/// # Safety
+///
+/// None beyond a valid config; unsafe only to match `ffi`.
+pub unsafe fn getOptionFromConfig(config: &Config, option: ConfigOption) -> bool {
+    config.getOptions() & option.repr as i32 != 0
+}
+
+/// # Safety
+///
+/// None beyond a valid config; unsafe only to match `ffi`.
+pub unsafe fn setOptionFromConfig(config: Pin<&mut Config>, option: ConfigOption, flag: bool) {
+    let options = match flag {
+        true => config.getOptions() | option.repr as i32,
+        false => config.getOptions() & !(option.repr as i32),
+    };
+    config.setOptions(options)
+}
+
+pub fn getVersionMajor() -> u32 {
+    unsafe { sys::capi_version_major() }
+}
+
+pub fn getVersionMinor() -> u32 {
+    unsafe { sys::capi_version_minor() }
+}
+
+pub fn getVersionRevision() -> u32 {
+    unsafe { sys::capi_version_revision() }
+}
+
+/// Bindings that depend on the version of libconfig found at build time.
+pub mod compat {
+    use super::{sys, Config};
+    use std::pin::Pin;
+
+    /// `None` before libconfig 1.6.
+    pub fn float_precision(config: &Config) -> Option<u16> {
+        let mut digits = 0;
+        match unsafe { sys::capi_config_float_precision(config.raw(), &mut digits) } {
+            0 => None,
+            _ => Some(digits),
+        }
+    }
+
+    /// Returns false, leaving `config` unchanged, before libconfig 1.6.
+    pub fn set_float_precision(config: Pin<&mut Config>, digits: u16) -> bool {
+        unsafe { sys::capi_config_set_float_precision(config.raw(), digits) != 0 }
+    }
+}
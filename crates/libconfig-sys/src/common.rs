@@ -0,0 +1,193 @@
+//! Stand-ins for the types cxx generates, shared by the bindings that do not
+//! go through cxx.
+
+use std::fmt;
+use std::ops::Deref;
+use std::pin::Pin;
+use std::ptr;
+
+macro_rules! shared_enum {
+    ($name:ident { $($variant:ident = $value:expr,)* }) => {
+        #[derive(Copy, Clone, PartialEq, Eq, Hash)]
+        #[repr(transparent)]
+        pub struct $name {
+            pub repr: u32,
+        }
+
+        #[allow(non_upper_case_globals)]
+        impl $name {
+            $(pub const $variant: Self = $name { repr: $value };)*
+        }
+
+        impl fmt::Debug for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                match *self {
+                    $($name::$variant => f.write_str(stringify!($variant)),)*
+                    _ => write!(f, "{}({})", stringify!($name), self.repr),
+                }
+            }
+        }
+    };
+}
+
+// The same values as the shared enums of the cxx bridge.
+shared_enum!(Type {
+    TypeNone = 0,
+    TypeInt = 1,
+    TypeInt64 = 2,
+    TypeFloat = 3,
+    TypeString = 4,
+    TypeBoolean = 5,
+    TypeGroup = 6,
+    TypeArray = 7,
+    TypeList = 8,
+});
+
+shared_enum!(Format {
+    FormatDefault = 0,
+    FormatHex = 1,
+});
+
+shared_enum!(ConfigOption {
+    OptionNone = 0,
+    OptionAutoConvert = 0x01,
+    OptionSemicolonSeparators = 0x02,
+    OptionColonAssignmentForGroups = 0x04,
+    OptionColonAssignmentForNonGroups = 0x08,
+    OptionOpenBraceOnSeparateLine = 0x10,
+    OptionAllowScientificNotation = 0x20,
+    OptionFsync = 0x40,
+    OptionAllowOverrides = 0x80,
+});
+
+/// An error in place of the exception libconfig++ would have thrown.
+#[derive(Debug)]
+pub struct Exception {
+    what: String,
+}
+
+impl Exception {
+    pub(crate) fn new(what: &str) -> Self {
+        Exception {
+            what: what.to_string(),
+        }
+    }
+
+    // Worded like the wrapper around libconfig++ reports parse errors.
+    pub(crate) fn parse(file: &str, line: i32, text: &str) -> Self {
+        Exception {
+            what: format!("Parse error at {}:{} - {}", file, line, text),
+        }
+    }
+
+    pub fn what(&self) -> &str {
+        &self.what
+    }
+}
+
+impl fmt::Display for Exception {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.what)
+    }
+}
+
+impl std::error::Error for Exception {}
+
+/// A string passed to or filled in by the bindings, standing in for
+/// `CxxString`. Create one with `let_string!`.
+#[derive(Debug, Default)]
+pub struct StringBuf {
+    inner: String,
+}
+
+impl StringBuf {
+    pub fn to_str(&self) -> &str {
+        &self.inner
+    }
+
+    pub(crate) fn set(&mut self, s: String) {
+        self.inner = s;
+    }
+}
+
+impl From<&str> for StringBuf {
+    fn from(s: &str) -> Self {
+        StringBuf {
+            inner: s.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for StringBuf {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.inner)
+    }
+}
+
+/// Declares a pinned `StringBuf` like `cxx::let_cxx_string!` declares a
+/// `CxxString`.
+#[macro_export]
+macro_rules! let_string {
+    ($var:ident = $value:expr $(,)?) => {
+        let mut $var = $crate::common::StringBuf::from($value);
+        #[allow(unused_mut)]
+        let mut $var = ::std::pin::Pin::new(&mut $var);
+    };
+}
+
+/// Types a `UniquePtr` can own.
+pub trait Owned {
+    /// # Safety
+    ///
+    /// `ptr` is non-null and was produced for this type by the bindings.
+    unsafe fn release(ptr: *mut Self);
+}
+
+/// An owning pointer with the interface of `cxx::UniquePtr`.
+pub struct UniquePtr<T: Owned> {
+    ptr: *mut T,
+}
+
+impl<T: Owned> UniquePtr<T> {
+    pub(crate) fn from_raw(ptr: *mut T) -> Self {
+        UniquePtr { ptr }
+    }
+
+    pub fn null() -> Self {
+        UniquePtr {
+            ptr: ptr::null_mut(),
+        }
+    }
+
+    pub fn is_null(&self) -> bool {
+        self.ptr.is_null()
+    }
+
+    pub fn as_ref(&self) -> Option<&T> {
+        unsafe { self.ptr.as_ref() }
+    }
+
+    pub fn as_mut(&mut self) -> Option<Pin<&mut T>> {
+        unsafe { self.ptr.as_mut().map(|inner| Pin::new_unchecked(inner)) }
+    }
+
+    pub fn pin_mut(&mut self) -> Pin<&mut T> {
+        self.as_mut().expect("called pin_mut on a null UniquePtr")
+    }
+}
+
+impl<T: Owned> Deref for UniquePtr<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.as_ref().expect("called deref on a null UniquePtr")
+    }
+}
+
+impl<T: Owned> Drop for UniquePtr<T> {
+    fn drop(&mut self) {
+        if !self.ptr.is_null() {
+            unsafe { T::release(self.ptr) }
+        }
+    }
+}
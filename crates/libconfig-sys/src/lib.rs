@@ -1,3 +1,4 @@
+#[cfg(feature = "cxx")]
 #[cxx::bridge]
 pub mod ffi {
 
@@ -22,11 +23,26 @@ pub mod ffi {
         FormatHex,
     }
 
+    #[derive(Debug)]
+    #[repr(u32)]
+    enum ConfigOption {
+        OptionNone = 0,
+        OptionAutoConvert = 0x01,
+        OptionSemicolonSeparators = 0x02,
+        OptionColonAssignmentForGroups = 0x04,
+        OptionColonAssignmentForNonGroups = 0x08,
+        OptionOpenBraceOnSeparateLine = 0x10,
+        OptionAllowScientificNotation = 0x20,
+        OptionFsync = 0x40,
+        OptionAllowOverrides = 0x80,
+    }
+
     unsafe extern "C++" {
         include!("libconfig-sys/include/wrapper.h");
 
         type Type;
         type Format;
+        type ConfigOption;
 
         type Setting;
         type SettingIterator;
@@ -85,6 +101,10 @@ pub mod ffi {
             name: &CxxString,
             libtype: Type,
         ) -> Result<Pin<&'c mut Setting>>;
+        unsafe fn addElement<'c>(
+            setting: Pin<&'c mut Setting>,
+            libtype: Type,
+        ) -> Result<Pin<&'c mut Setting>>;
         unsafe fn removeSetting<'c>(setting: Pin<&'c mut Setting>, name: &CxxString) -> Result<()>;
         unsafe fn removeSettingByIndex<'c>(setting: Pin<&'c mut Setting>, idx: u32) -> Result<()>;
         unsafe fn tryBoolFromSetting(setting: &Setting) -> Result<bool>;
@@ -99,6 +119,10 @@ pub mod ffi {
             path: *const c_char,
         ) -> Result<Pin<&'c mut Setting>>;
         unsafe fn getPathFromSetting(setting: &Setting, path: Pin<&mut CxxString>);
+        unsafe fn getElemFromSetting<'c>(
+            setting: Pin<&'c mut Setting>,
+            idx: u32,
+        ) -> Result<Pin<&'c mut Setting>>;
         unsafe fn getParentFromSetting<'c>(
             setting: Pin<&'c mut Setting>,
         ) -> Result<Pin<&'c mut Setting>>;
@@ -118,6 +142,13 @@ pub mod ffi {
         unsafe fn writeFile(self: Pin<&mut Config>, filename: &CxxString) -> Result<()>;
         unsafe fn readString(self: Pin<&mut Config>, input: &CxxString) -> Result<()>;
         unsafe fn setIncludeDir(self: Pin<&mut Config>, path: *const c_char);
+        unsafe fn getIncludeDir(self: &Config) -> *const c_char;
+        unsafe fn getOptions(self: &Config) -> i32;
+        unsafe fn setOptions(self: Pin<&mut Config>, options: i32);
+        unsafe fn getDefaultFormat(self: &Config) -> Format;
+        unsafe fn setDefaultFormat(self: Pin<&mut Config>, format: Format);
+        unsafe fn getTabWidth(self: &Config) -> u16;
+        unsafe fn setTabWidth(self: Pin<&mut Config>, width: u16);
         unsafe fn exists(self: &Config, path: *const c_char) -> bool;
         #[rust_name = "lookup_bool"]
         unsafe fn lookupValue(self: &Config, path: *const c_char, value: &mut bool) -> bool;
@@ -147,11 +178,61 @@ pub mod ffi {
             path: *const c_char,
             value: &mut u64,
         ) -> bool;
+        unsafe fn writeToString(config: &Config, out: Pin<&mut CxxString>) -> Result<()>;
         // Cannot use as member function due to lifetime
         unsafe fn getRootFromConfig<'c>(config: &'c Config) -> Pin<&'c mut Setting>;
         unsafe fn lookupSettingFromConfig<'c>(
             cfg: Pin<&'c mut Config>,
             path: *const c_char,
         ) -> Result<Pin<&'c mut Setting>>;
+        unsafe fn getOptionFromConfig(config: &Config, option: ConfigOption) -> bool;
+        unsafe fn setOptionFromConfig(config: Pin<&mut Config>, option: ConfigOption, flag: bool);
+
+        fn getVersionMajor() -> u32;
+        fn getVersionMinor() -> u32;
+        fn getVersionRevision() -> u32;
+    }
+}
+
+// Built by build.rs only when the headers are from libconfig 1.6 or later.
+#[cfg(all(feature = "cxx", libconfig_1_6))]
+mod v1_6;
+
+/// Bindings that depend on the version of libconfig++ found at build time.
+#[cfg(feature = "cxx")]
+pub mod compat {
+    use crate::ffi::Config;
+    use std::pin::Pin;
+
+    /// `None` before libconfig 1.6.
+    #[cfg(libconfig_1_6)]
+    pub fn float_precision(config: &Config) -> Option<u16> {
+        Some(unsafe { crate::v1_6::ffi::getFloatPrecisionFromConfig(config) })
+    }
+
+    #[cfg(not(libconfig_1_6))]
+    pub fn float_precision(_config: &Config) -> Option<u16> {
+        None
+    }
+
+    /// Returns false, leaving `config` unchanged, before libconfig 1.6.
+    #[cfg(libconfig_1_6)]
+    pub fn set_float_precision(config: Pin<&mut Config>, digits: u16) -> bool {
+        unsafe { crate::v1_6::ffi::setFloatPrecisionFromConfig(config, digits) };
+        true
+    }
+
+    #[cfg(not(libconfig_1_6))]
+    pub fn set_float_precision(_config: Pin<&mut Config>, _digits: u16) -> bool {
+        false
     }
 }
+
+#[cfg(any(feature = "capi", feature = "pure-rust"))]
+pub mod common;
+
+#[cfg(feature = "capi")]
+pub mod capi;
+
+#[cfg(feature = "pure-rust")]
+pub mod native;
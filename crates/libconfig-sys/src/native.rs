@@ -0,0 +1,1230 @@
+//! A libconfig implementation in Rust with the same shape as the cxx bridge in
+//! `ffi`, for builds without a C or C++ toolchain. It follows the grammar,
+//! lookup rules and output of libconfig 1.7.
+#![allow(non_snake_case)]
+
+mod parse;
+mod write;
+
+pub use crate::common::{ConfigOption, Exception, Format, Owned, StringBuf, Type, UniquePtr};
+
+use std::ffi::{CStr, CString};
+use std::marker::PhantomPinned;
+use std::os::raw::c_char;
+use std::pin::Pin;
+use std::ptr;
+
+type Result<T> = std::result::Result<T, Exception>;
+
+// The libconfig release this implementation behaves like.
+const VERSION: (u32, u32, u32) = (1, 7, 3);
+
+// Separators between the components of a lookup path.
+const PATH_TOKENS: &[u8] = b":./";
+
+enum Value {
+    None,
+    Int(i32),
+    Int64(i64),
+    Float(f64),
+    Bool(bool),
+    String(CString),
+    Group(Vec<*mut Setting>),
+    Array(Vec<*mut Setting>),
+    List(Vec<*mut Setting>),
+}
+
+impl Value {
+    fn of(type_: Type) -> Value {
+        match type_ {
+            Type::TypeInt => Value::Int(0),
+            Type::TypeInt64 => Value::Int64(0),
+            Type::TypeFloat => Value::Float(0.0),
+            Type::TypeBoolean => Value::Bool(false),
+            Type::TypeString => Value::String(CString::default()),
+            Type::TypeGroup => Value::Group(Vec::new()),
+            Type::TypeArray => Value::Array(Vec::new()),
+            Type::TypeList => Value::List(Vec::new()),
+            _ => Value::None,
+        }
+    }
+
+    fn type_(&self) -> Type {
+        match self {
+            Value::None => Type::TypeNone,
+            Value::Int(_) => Type::TypeInt,
+            Value::Int64(_) => Type::TypeInt64,
+            Value::Float(_) => Type::TypeFloat,
+            Value::Bool(_) => Type::TypeBoolean,
+            Value::String(_) => Type::TypeString,
+            Value::Group(_) => Type::TypeGroup,
+            Value::Array(_) => Type::TypeArray,
+            Value::List(_) => Type::TypeList,
+        }
+    }
+}
+
+/// A setting, owned by its parent or, for the root, by its config.
+pub struct Setting {
+    name: Option<CString>,
+    value: Value,
+    format: Format,
+    parent: *mut Setting,
+    config: *const Config,
+    _pinned: PhantomPinned,
+}
+
+impl Drop for Setting {
+    fn drop(&mut self) {
+        for &child in self.children() {
+            drop(unsafe { Box::from_raw(child) });
+        }
+    }
+}
+
+/// A configuration, always behind the `UniquePtr` from `Config_ctor`.
+pub struct Config {
+    root: *mut Setting,
+    include_dir: Option<CString>,
+    options: i32,
+    default_format: Format,
+    tab_width: u16,
+    float_precision: u16,
+    _pinned: PhantomPinned,
+}
+
+impl Drop for Config {
+    fn drop(&mut self) {
+        drop(unsafe { Box::from_raw(self.root) });
+    }
+}
+
+impl Owned for Config {
+    unsafe fn release(ptr: *mut Self) {
+        drop(Box::from_raw(ptr));
+    }
+}
+
+/// Walks the children of an aggregate setting.
+pub struct SettingIterator {
+    parent: *const Setting,
+    next: usize,
+}
+
+impl Owned for SettingIterator {
+    unsafe fn release(ptr: *mut Self) {
+        drop(Box::from_raw(ptr));
+    }
+}
+
+unsafe fn wrap<'c>(ptr: *mut Setting) -> Result<Pin<&'c mut Setting>> {
+    match ptr.is_null() {
+        true => Err(Exception::new("SettingNotFoundException")),
+        false => Ok(Pin::new_unchecked(&mut *ptr)),
+    }
+}
+
+fn to_c(s: &StringBuf) -> Result<CString> {
+    CString::new(s.to_str()).map_err(|_| Exception::new("string contains NUL"))
+}
+
+fn type_error() -> Exception {
+    Exception::new("SettingTypeException")
+}
+
+// Compares a setting name with a path component, which ends at the first
+// path token.
+fn name_matches(name: &CStr, path: &[u8]) -> bool {
+    let end = path
+        .iter()
+        .position(|c| PATH_TOKENS.contains(c))
+        .unwrap_or(path.len());
+    name.to_bytes() == &path[..end]
+}
+
+// Setting names start with a letter or '*', followed by letters, digits and
+// any of "*_-".
+fn is_valid_name(name: &[u8]) -> bool {
+    match name.split_first() {
+        Some((first, rest)) => {
+            (first.is_ascii_alphabetic() || *first == b'*')
+                && rest
+                    .iter()
+                    .all(|c| c.is_ascii_alphanumeric() || b"*_-".contains(c))
+        }
+        None => false,
+    }
+}
+
+// Like atoi: leading whitespace, an optional sign and the digits up to the
+// first other character.
+fn atoi(s: &[u8]) -> i64 {
+    let s = &s[s.iter().take_while(|c| c.is_ascii_whitespace()).count()..];
+    let (negative, digits) = match s.first() {
+        Some(b'-') => (true, &s[1..]),
+        Some(b'+') => (false, &s[1..]),
+        _ => (false, s),
+    };
+    let value = digits
+        .iter()
+        .take_while(|c| c.is_ascii_digit())
+        .fold(0i64, |acc, c| {
+            acc.saturating_mul(10).saturating_add((c - b'0') as i64)
+        });
+    match negative {
+        true => -value,
+        false => value,
+    }
+}
+
+impl Setting {
+    fn new(
+        name: Option<CString>,
+        type_: Type,
+        parent: *mut Setting,
+        config: *const Config,
+    ) -> *mut Setting {
+        Box::into_raw(Box::new(Setting {
+            name,
+            value: Value::of(type_),
+            format: Format::FormatDefault,
+            parent,
+            config,
+            _pinned: PhantomPinned,
+        }))
+    }
+
+    fn raw(&self) -> *mut Setting {
+        self as *const Setting as *mut Setting
+    }
+
+    fn config(&self) -> &Config {
+        unsafe { &*self.config }
+    }
+
+    fn children(&self) -> &[*mut Setting] {
+        match &self.value {
+            Value::Group(children) | Value::Array(children) | Value::List(children) => children,
+            _ => &[],
+        }
+    }
+
+    fn children_mut(&mut self) -> Option<&mut Vec<*mut Setting>> {
+        match &mut self.value {
+            Value::Group(children) | Value::Array(children) | Value::List(children) => {
+                Some(children)
+            }
+            _ => None,
+        }
+    }
+
+    fn member(&self, name: &[u8]) -> *mut Setting {
+        match &self.value {
+            Value::Group(children) => children
+                .iter()
+                .copied()
+                .find(|&child| {
+                    let child = unsafe { &*child };
+                    child
+                        .name
+                        .as_deref()
+                        .is_some_and(|own| name_matches(own, name))
+                })
+                .unwrap_or(ptr::null_mut()),
+            _ => ptr::null_mut(),
+        }
+    }
+
+    fn elem(&self, idx: i64) -> *mut Setting {
+        usize::try_from(idx)
+            .ok()
+            .and_then(|idx| self.children().get(idx).copied())
+            .unwrap_or(ptr::null_mut())
+    }
+
+    // Appends a new child, or returns null when the setting is not an
+    // aggregate. Elements of arrays and lists are never named.
+    fn push(&mut self, name: Option<CString>, type_: Type) -> *mut Setting {
+        let (parent, config) = (self.raw(), self.config);
+        let name = match self.value {
+            Value::Group(_) => name,
+            _ => None,
+        };
+        match self.children_mut() {
+            Some(children) => {
+                let child = Setting::new(name, type_, parent, config);
+                children.push(child);
+                child
+            }
+            None => ptr::null_mut(),
+        }
+    }
+
+    // Adds a member to a group like config_setting_add: an existing member of
+    // the same name is returned when the config allows overrides, and is an
+    // error otherwise.
+    fn add_member(&mut self, name: &[u8], type_: Type) -> Option<*mut Setting> {
+        if !is_valid_name(name) {
+            return None;
+        }
+        let existing = self.member(name);
+        if !existing.is_null() {
+            let overrides = ConfigOption::OptionAllowOverrides.repr as i32;
+            return match self.config().options & overrides != 0 {
+                true => Some(existing),
+                false => None,
+            };
+        }
+        let name = CString::new(name).ok()?;
+        Some(self.push(Some(name), type_)).filter(|child| !child.is_null())
+    }
+
+    // Resolves `path` like config_setting_lookup: components are separated by
+    // any of `PATH_TOKENS`, and `[n]` selects the n-th element. An empty path
+    // finds nothing.
+    fn lookup_raw(&self, path: &[u8]) -> *mut Setting {
+        let mut p = 0;
+        let mut found = self.raw();
+        loop {
+            while p < path.len() && PATH_TOKENS.contains(&path[p]) {
+                p += 1;
+            }
+            if p == path.len() {
+                break;
+            }
+            let current = unsafe { &*found };
+            found = match path[p] {
+                b'[' => {
+                    p += 1;
+                    current.elem(atoi(&path[p..]))
+                }
+                _ => current.member(&path[p..]),
+            };
+            if found.is_null() {
+                break;
+            }
+            while p < path.len() && !PATH_TOKENS.contains(&path[p]) {
+                p += 1;
+            }
+        }
+        match p < path.len() || found == self.raw() {
+            true => ptr::null_mut(),
+            false => found,
+        }
+    }
+
+    unsafe fn lookup_setting(&self, path: *const c_char) -> Result<&Setting> {
+        Ok(wrap(self.lookup_raw(CStr::from_ptr(path).to_bytes()))?
+            .into_ref()
+            .get_ref())
+    }
+
+    fn auto_convert(&self) -> bool {
+        self.config().options & ConfigOption::OptionAutoConvert.repr as i32 != 0
+    }
+
+    // Accesses as `type_` are allowed for the setting's own type, between the
+    // two integer types, and between numbers when the config converts them
+    // automatically.
+    fn assert_type(&self, type_: Type) -> Result<()> {
+        let own = self.value.type_();
+        let integer = |t: Type| matches!(t, Type::TypeInt | Type::TypeInt64);
+        let number = |t: Type| integer(t) || t == Type::TypeFloat;
+        match own == type_
+            || (integer(type_) && integer(own))
+            || (number(type_) && number(own) && self.auto_convert())
+        {
+            true => Ok(()),
+            false => Err(type_error()),
+        }
+    }
+
+    // Stores an integer in a number setting, keeping its type.
+    fn store_integer(&mut self, val: i64) -> Result<()> {
+        self.value = match self.value {
+            Value::Int(_) => Value::Int(i32::try_from(val).map_err(|_| type_error())?),
+            Value::Int64(_) => Value::Int64(val),
+            Value::Float(_) => Value::Float(val as f64),
+            _ => return Err(type_error()),
+        };
+        Ok(())
+    }
+
+    fn integer(&self) -> Result<i64> {
+        match self.value {
+            Value::Int(val) => Ok(val as i64),
+            Value::Int64(val) => Ok(val),
+            Value::Float(val) => Ok(val as i64),
+            _ => Err(type_error()),
+        }
+    }
+
+    /// # Safety
+    ///
+    /// `name` must point to a NUL-terminated string. The setting must belong to
+    /// a config that is still alive and must not have been removed.
+    pub unsafe fn exists(&self, name: *const c_char) -> bool {
+        !self.member(CStr::from_ptr(name).to_bytes()).is_null()
+    }
+
+    /// # Safety
+    ///
+    /// `path` must point to a NUL-terminated string. The setting must belong to
+    /// a config that is still alive and must not have been removed.
+    pub unsafe fn lookup_bool(&self, path: *const c_char, value: &mut bool) -> bool {
+        store(
+            self.lookup_setting(path)
+                .and_then(|s| tryBoolFromSetting(s)),
+            value,
+        )
+    }
+
+    /// # Safety
+    ///
+    /// `path` must point to a NUL-terminated string. The setting must belong to
+    /// a config that is still alive and must not have been removed.
+    pub unsafe fn lookup_i32(&self, path: *const c_char, value: &mut i32) -> bool {
+        store(
+            self.lookup_setting(path).and_then(|s| tryI32FromSetting(s)),
+            value,
+        )
+    }
+
+    /// # Safety
+    ///
+    /// `path` must point to a NUL-terminated string. The setting must belong to
+    /// a config that is still alive and must not have been removed.
+    pub unsafe fn lookup_u32(&self, path: *const c_char, value: &mut u32) -> bool {
+        let setting = self.lookup_setting(path);
+        store(
+            setting
+                .and_then(|s| tryI32FromSetting(s))
+                .map(|v| v.max(0) as u32),
+            value,
+        )
+    }
+
+    /// # Safety
+    ///
+    /// `path` must point to a NUL-terminated string. The setting must belong to
+    /// a config that is still alive and must not have been removed.
+    pub unsafe fn lookup_f32(&self, path: *const c_char, value: &mut f32) -> bool {
+        store(
+            self.lookup_setting(path).and_then(|s| tryF32FromSetting(s)),
+            value,
+        )
+    }
+
+    /// # Safety
+    ///
+    /// `path` must point to a NUL-terminated string. The setting must belong to
+    /// a config that is still alive and must not have been removed.
+    pub unsafe fn lookup_f64(&self, path: *const c_char, value: &mut f64) -> bool {
+        store(
+            self.lookup_setting(path).and_then(|s| tryF64FromSetting(s)),
+            value,
+        )
+    }
+
+    /// # Safety
+    ///
+    /// `path` must point to a NUL-terminated string. The setting must belong to
+    /// a config that is still alive and must not have been removed.
+    pub unsafe fn lookup_string(&self, path: *const c_char, value: Pin<&mut StringBuf>) -> bool {
+        match self
+            .lookup_setting(path)
+            .and_then(|s| tryStringFromSetting(s))
+        {
+            Ok(val) => {
+                value.get_mut().set(val);
+                true
+            }
+            Err(_) => false,
+        }
+    }
+
+    /// # Safety
+    ///
+    /// The setting must belong to a config that is still alive and must not
+    /// have been removed. The name returned lives as long as the setting.
+    pub unsafe fn getName(&self) -> *const c_char {
+        match &self.name {
+            Some(name) => name.as_ptr(),
+            None => ptr::null(),
+        }
+    }
+
+    /// # Safety
+    ///
+    /// The setting must belong to a config that is still alive and must not
+    /// have been removed.
+    pub unsafe fn isRoot(&self) -> bool {
+        self.parent.is_null()
+    }
+
+    /// # Safety
+    ///
+    /// The setting must belong to a config that is still alive and must not
+    /// have been removed.
+    pub unsafe fn getIndex(&self) -> i32 {
+        match self.parent.as_ref() {
+            Some(parent) => parent
+                .children()
+                .iter()
+                .position(|&child| child == self.raw())
+                .map_or(-1, |idx| idx as i32),
+            None => -1,
+        }
+    }
+
+    /// # Safety
+    ///
+    /// The setting must belong to a config that is still alive and must not
+    /// have been removed.
+    pub unsafe fn getType(&self) -> Type {
+        self.value.type_()
+    }
+
+    /// # Safety
+    ///
+    /// The setting must belong to a config that is still alive and must not
+    /// have been removed.
+    pub unsafe fn getFormat(&self) -> Format {
+        match self.format {
+            Format::FormatDefault => self.config().default_format,
+            format => format,
+        }
+    }
+
+    /// # Safety
+    ///
+    /// The setting must belong to a config that is still alive and must not
+    /// have been removed.
+    pub unsafe fn setFormat(self: Pin<&mut Self>, format: Format) {
+        let this = self.get_unchecked_mut();
+        this.format = match (&this.value, format) {
+            (Value::Int(_) | Value::Int64(_), Format::FormatHex) => Format::FormatHex,
+            _ => Format::FormatDefault,
+        };
+    }
+
+    /// # Safety
+    ///
+    /// The setting must belong to a config that is still alive and must not
+    /// have been removed.
+    pub unsafe fn getLength(&self) -> Result<i32> {
+        Ok(self.children().len() as i32)
+    }
+
+    /// # Safety
+    ///
+    /// The setting must belong to a config that is still alive and must not
+    /// have been removed.
+    pub unsafe fn isGroup(&self) -> bool {
+        self.getType() == Type::TypeGroup
+    }
+
+    /// # Safety
+    ///
+    /// The setting must belong to a config that is still alive and must not
+    /// have been removed.
+    pub unsafe fn isArray(&self) -> bool {
+        self.getType() == Type::TypeArray
+    }
+
+    /// # Safety
+    ///
+    /// The setting must belong to a config that is still alive and must not
+    /// have been removed.
+    pub unsafe fn isList(&self) -> bool {
+        self.getType() == Type::TypeList
+    }
+
+    /// # Safety
+    ///
+    /// The setting must belong to a config that is still alive and must not
+    /// have been removed.
+    pub unsafe fn isAggregate(&self) -> bool {
+        matches!(
+            self.getType(),
+            Type::TypeGroup | Type::TypeArray | Type::TypeList
+        )
+    }
+
+    /// # Safety
+    ///
+    /// The setting must belong to a config that is still alive and must not
+    /// have been removed.
+    pub unsafe fn isScalar(&self) -> bool {
+        setting_is_scalar_type(self.getType())
+    }
+
+    /// # Safety
+    ///
+    /// The setting must belong to a config that is still alive and must not
+    /// have been removed.
+    pub unsafe fn isNumber(&self) -> bool {
+        matches!(
+            self.getType(),
+            Type::TypeInt | Type::TypeInt64 | Type::TypeFloat
+        )
+    }
+
+    /// # Safety
+    ///
+    /// The setting must belong to a config that is still alive and must not
+    /// have been removed.
+    pub unsafe fn isString(&self) -> bool {
+        self.getType() == Type::TypeString
+    }
+}
+
+fn store<T>(result: Result<T>, value: &mut T) -> bool {
+    match result {
+        Ok(val) => {
+            *value = val;
+            true
+        }
+        Err(_) => false,
+    }
+}
+
+fn setting_is_scalar_type(libtype: Type) -> bool {
+    matches!(
+        libtype,
+        Type::TypeInt | Type::TypeInt64 | Type::TypeFloat | Type::TypeString | Type::TypeBoolean
+    )
+}
+
+/// # Safety
+///
+/// The setting must belong to a config that is still alive and must not have
+/// been removed.
+pub unsafe fn setBool(setting: Pin<&mut Setting>, val: bool) -> Result<()> {
+    setting.assert_type(Type::TypeBoolean)?;
+    setting.get_unchecked_mut().value = Value::Bool(val);
+    Ok(())
+}
+
+/// # Safety
+///
+/// The setting must belong to a config that is still alive and must not have
+/// been removed.
+pub unsafe fn setI32(setting: Pin<&mut Setting>, val: i32) -> Result<()> {
+    setting.assert_type(Type::TypeInt)?;
+    setting.get_unchecked_mut().store_integer(val as i64)
+}
+
+/// # Safety
+///
+/// The setting must belong to a config that is still alive and must not have
+/// been removed.
+pub unsafe fn setI64(setting: Pin<&mut Setting>, val: i64) -> Result<()> {
+    setting.assert_type(Type::TypeInt64)?;
+    setting.get_unchecked_mut().store_integer(val)
+}
+
+/// # Safety
+///
+/// The setting must belong to a config that is still alive and must not have
+/// been removed.
+pub unsafe fn setF32(setting: Pin<&mut Setting>, val: f32) -> Result<()> {
+    setF64(setting, val as f64)
+}
+
+/// # Safety
+///
+/// The setting must belong to a config that is still alive and must not have
+/// been removed.
+pub unsafe fn setF64(setting: Pin<&mut Setting>, val: f64) -> Result<()> {
+    setting.assert_type(Type::TypeFloat)?;
+    let this = setting.get_unchecked_mut();
+    this.value = match this.value {
+        Value::Int(_) => Value::Int(val as i32),
+        Value::Int64(_) => Value::Int64(val as i64),
+        _ => Value::Float(val),
+    };
+    Ok(())
+}
+
+/// # Safety
+///
+/// The setting must belong to a config that is still alive and must not have
+/// been removed.
+pub unsafe fn setString(setting: Pin<&mut Setting>, val: &StringBuf) -> Result<()> {
+    setting.assert_type(Type::TypeString)?;
+    let val = to_c(val)?;
+    setting.get_unchecked_mut().value = Value::String(val);
+    Ok(())
+}
+
+/// # Safety
+///
+/// `path` must point to a NUL-terminated string. The setting must belong to a
+/// config that is still alive and must not have been removed.
+pub unsafe fn lookupValueI64FromSetting(
+    setting: &Setting,
+    path: *const c_char,
+    value: &mut i64,
+) -> bool {
+    store(
+        setting
+            .lookup_setting(path)
+            .and_then(|s| tryI64FromSetting(s)),
+        value,
+    )
+}
+
+/// # Safety
+///
+/// `path` must point to a NUL-terminated string. The setting must belong to a
+/// config that is still alive and must not have been removed.
+pub unsafe fn lookupValueU64FromSetting(
+    setting: &Setting,
+    path: *const c_char,
+    value: &mut u64,
+) -> bool {
+    let found = setting.lookup_setting(path);
+    store(
+        found
+            .and_then(|s| tryI64FromSetting(s))
+            .map(|v| v.max(0) as u64),
+        value,
+    )
+}
+
+/// # Safety
+///
+/// The setting must belong to a config that is still alive and must not have
+/// been removed.
+pub unsafe fn addSetting<'c>(
+    setting: Pin<&'c mut Setting>,
+    name: &StringBuf,
+    libtype: Type,
+) -> Result<Pin<&'c mut Setting>> {
+    if !setting.isGroup() || libtype == Type::TypeNone {
+        return Err(type_error());
+    }
+    match setting
+        .get_unchecked_mut()
+        .add_member(name.to_str().as_bytes(), libtype)
+    {
+        Some(child) => wrap(child),
+        None => Err(Exception::new("SettingNameException")),
+    }
+}
+
+/// # Safety
+///
+/// The setting must belong to a config that is still alive and must not have
+/// been removed.
+pub unsafe fn addElement(setting: Pin<&mut Setting>, libtype: Type) -> Result<Pin<&mut Setting>> {
+    // Array elements share one scalar type.
+    let allowed = match &setting.value {
+        Value::List(_) => libtype != Type::TypeNone,
+        Value::Array(elements) => match elements.first() {
+            Some(&first) => (*first).getType() == libtype,
+            None => setting_is_scalar_type(libtype),
+        },
+        _ => false,
+    };
+    if !allowed {
+        return Err(type_error());
+    }
+    wrap(setting.get_unchecked_mut().push(None, libtype))
+}
+
+/// # Safety
+///
+/// The setting must belong to a config that is still alive and must not have
+/// been removed. Handles to the removed member and its descendants must not be
+/// used afterwards.
+pub unsafe fn removeSetting(setting: Pin<&mut Setting>, name: &StringBuf) -> Result<()> {
+    if !setting.isGroup() {
+        return Err(type_error());
+    }
+    // Like config_setting_remove, the last component of the path names the
+    // member removed from the parent of the setting found.
+    let path = name.to_str().as_bytes();
+    let found = setting.lookup_raw(path);
+    if found.is_null() {
+        return Err(Exception::new("SettingNotFoundException"));
+    }
+    let last = path
+        .iter()
+        .rposition(|c| PATH_TOKENS.contains(c))
+        .map_or(path, |idx| &path[idx + 1..]);
+    let parent = &mut *(*found).parent;
+    let removed = match &mut parent.value {
+        Value::Group(children) => children
+            .iter()
+            .position(|&child| {
+                (*child)
+                    .name
+                    .as_deref()
+                    .is_some_and(|own| name_matches(own, last))
+            })
+            .map(|idx| children.remove(idx)),
+        _ => None,
+    };
+    match removed {
+        Some(child) => {
+            drop(Box::from_raw(child));
+            Ok(())
+        }
+        None => Err(Exception::new("SettingNotFoundException")),
+    }
+}
+
+/// # Safety
+///
+/// The setting must belong to a config that is still alive and must not have
+/// been removed. Handles to the removed element and its descendants must not be
+/// used afterwards.
+pub unsafe fn removeSettingByIndex(setting: Pin<&mut Setting>, idx: u32) -> Result<()> {
+    if !setting.isAggregate() {
+        return Err(type_error());
+    }
+    let children = setting
+        .get_unchecked_mut()
+        .children_mut()
+        .expect("aggregate has children");
+    match (idx as usize) < children.len() {
+        true => {
+            drop(Box::from_raw(children.remove(idx as usize)));
+            Ok(())
+        }
+        false => Err(Exception::new("SettingNotFoundException")),
+    }
+}
+
+/// # Safety
+///
+/// The setting must belong to a config that is still alive and must not have
+/// been removed.
+pub unsafe fn tryBoolFromSetting(setting: &Setting) -> Result<bool> {
+    match setting.value {
+        Value::Bool(val) => Ok(val),
+        _ => Err(type_error()),
+    }
+}
+
+/// # Safety
+///
+/// The setting must belong to a config that is still alive and must not have
+/// been removed.
+pub unsafe fn tryI32FromSetting(setting: &Setting) -> Result<i32> {
+    setting.assert_type(Type::TypeInt)?;
+    match setting.value {
+        Value::Float(val) => Ok(val as i32),
+        _ => i32::try_from(setting.integer()?).map_err(|_| type_error()),
+    }
+}
+
+/// # Safety
+///
+/// The setting must belong to a config that is still alive and must not have
+/// been removed.
+pub unsafe fn tryI64FromSetting(setting: &Setting) -> Result<i64> {
+    setting.assert_type(Type::TypeInt64)?;
+    setting.integer()
+}
+
+/// # Safety
+///
+/// The setting must belong to a config that is still alive and must not have
+/// been removed.
+pub unsafe fn tryF32FromSetting(setting: &Setting) -> Result<f32> {
+    Ok(tryF64FromSetting(setting)? as f32)
+}
+
+/// # Safety
+///
+/// The setting must belong to a config that is still alive and must not have
+/// been removed.
+pub unsafe fn tryF64FromSetting(setting: &Setting) -> Result<f64> {
+    setting.assert_type(Type::TypeFloat)?;
+    match setting.value {
+        Value::Float(val) => Ok(val),
+        _ => Ok(setting.integer()? as f64),
+    }
+}
+
+/// # Safety
+///
+/// The setting must belong to a config that is still alive and must not have
+/// been removed.
+pub unsafe fn tryStringFromSetting(setting: &Setting) -> Result<String> {
+    match &setting.value {
+        Value::String(val) => Ok(val.to_string_lossy().into_owned()),
+        _ => Err(type_error()),
+    }
+}
+
+/// # Safety
+///
+/// `path` must point to a NUL-terminated string. The setting must belong to a
+/// config that is still alive and must not have been removed.
+pub unsafe fn lookupSettingFromSetting(
+    setting: Pin<&mut Setting>,
+    path: *const c_char,
+) -> Result<Pin<&mut Setting>> {
+    wrap(setting.lookup_raw(CStr::from_ptr(path).to_bytes()))
+}
+
+// Built like libconfig++ does: names joined with '.', and `[index]` for
+// elements of arrays and lists.
+/// # Safety
+///
+/// The setting must belong to a config that is still alive and must not have
+/// been removed.
+pub unsafe fn getPathFromSetting(setting: &Setting, path: Pin<&mut StringBuf>) {
+    let mut parts = Vec::new();
+    let mut current = setting;
+    while let Some(parent) = current.parent.as_ref() {
+        parts.push(match &current.name {
+            Some(name) => name.to_string_lossy().into_owned(),
+            None => format!("[{}]", current.getIndex()),
+        });
+        current = parent;
+    }
+    parts.reverse();
+    path.get_mut().set(parts.join("."));
+}
+
+/// # Safety
+///
+/// The setting must belong to a config that is still alive and must not have
+/// been removed.
+pub unsafe fn getElemFromSetting(
+    setting: Pin<&mut Setting>,
+    idx: u32,
+) -> Result<Pin<&mut Setting>> {
+    if !setting.isAggregate() {
+        return Err(type_error());
+    }
+    wrap(setting.elem(idx as i64))
+}
+
+/// # Safety
+///
+/// The setting must belong to a config that is still alive and must not have
+/// been removed. The parent returned must not be used at the same time as
+/// another handle to it.
+pub unsafe fn getParentFromSetting(setting: Pin<&mut Setting>) -> Result<Pin<&mut Setting>> {
+    wrap(setting.parent)
+}
+
+/// # Safety
+///
+/// The setting must belong to a config that is still alive and must not have
+/// been removed. The iterator must not be used after the setting or its
+/// children are changed or dropped.
+pub unsafe fn getSettingIter(setting: Pin<&mut Setting>) -> Result<UniquePtr<SettingIterator>> {
+    Ok(UniquePtr::from_raw(Box::into_raw(Box::new(
+        SettingIterator {
+            parent: setting.raw(),
+            next: 0,
+        },
+    ))))
+}
+
+/// # Safety
+///
+/// Must not be called more often than the iterated setting has children, and
+/// `'c` must not outlive that setting.
+pub unsafe fn getNextFromIter<'c>(iter: &mut UniquePtr<SettingIterator>) -> Pin<&'c mut Setting> {
+    let iter = iter.pin_mut().get_mut();
+    let elem = (*iter.parent).elem(iter.next as i64);
+    iter.next += 1;
+    wrap(elem).expect("iterated past the end of a setting")
+}
+
+pub fn Config_ctor() -> UniquePtr<Config> {
+    let config = Box::into_raw(Box::new(Config {
+        root: ptr::null_mut(),
+        include_dir: None,
+        options: (ConfigOption::OptionSemicolonSeparators.repr
+            | ConfigOption::OptionColonAssignmentForGroups.repr
+            | ConfigOption::OptionOpenBraceOnSeparateLine.repr) as i32,
+        default_format: Format::FormatDefault,
+        tab_width: 2,
+        float_precision: 6,
+        _pinned: PhantomPinned,
+    }));
+    unsafe { (*config).root = Setting::new(None, Type::TypeGroup, ptr::null_mut(), config) };
+    UniquePtr::from_raw(config)
+}
+
+impl Config {
+    fn raw(&self) -> *const Config {
+        self
+    }
+
+    // Replaces the root with an empty group and parses `input` into it.
+    fn read(self: Pin<&mut Self>, input: &[u8], file: Option<&str>) -> Result<()> {
+        let this = unsafe { self.get_unchecked_mut() };
+        let root = Setting::new(None, Type::TypeGroup, ptr::null_mut(), this.raw());
+        drop(unsafe { Box::from_raw(std::mem::replace(&mut this.root, root)) });
+        parse::parse(this, input, file)
+            .map_err(|err| Exception::parse(err.file.as_deref().unwrap_or(""), err.line, err.text))
+    }
+
+    unsafe fn root(&self) -> &Setting {
+        &*self.root
+    }
+
+    fn option(&self, option: ConfigOption) -> bool {
+        self.options & option.repr as i32 != 0
+    }
+
+    /// # Safety
+    ///
+    /// `filename` must point to a NUL-terminated string.
+    pub unsafe fn readFile(self: Pin<&mut Self>, filename: *const c_char) -> Result<()> {
+        let filename = CStr::from_ptr(filename).to_string_lossy();
+        match std::fs::read(&*filename) {
+            Ok(input) => self.read(&input, Some(&filename)),
+            Err(_) => Err(Exception::new("FileIOException")),
+        }
+    }
+
+    /// # Safety
+    ///
+    /// None beyond a valid config; unsafe only to match `ffi`.
+    pub unsafe fn writeFile(self: Pin<&mut Self>, filename: &StringBuf) -> Result<()> {
+        let written = std::fs::File::create(filename.to_str()).and_then(|mut file| {
+            std::io::Write::write_all(&mut file, &write::write(&self))?;
+            match self.option(ConfigOption::OptionFsync) {
+                true => file.sync_all(),
+                false => Ok(()),
+            }
+        });
+        written.map_err(|_| Exception::new("FileIOException"))
+    }
+
+    /// # Safety
+    ///
+    /// None beyond a valid config; unsafe only to match `ffi`.
+    pub unsafe fn readString(self: Pin<&mut Self>, input: &StringBuf) -> Result<()> {
+        to_c(input)?;
+        self.read(input.to_str().as_bytes(), None)
+    }
+
+    /// # Safety
+    ///
+    /// `path` must be null or point to a NUL-terminated string.
+    pub unsafe fn setIncludeDir(self: Pin<&mut Self>, path: *const c_char) {
+        self.get_unchecked_mut().include_dir = match path.is_null() {
+            true => None,
+            false => Some(CStr::from_ptr(path).to_owned()),
+        };
+    }
+
+    /// # Safety
+    ///
+    /// The directory returned is only valid until the next `setIncludeDir`.
+    pub unsafe fn getIncludeDir(&self) -> *const c_char {
+        match &self.include_dir {
+            Some(dir) => dir.as_ptr(),
+            None => ptr::null(),
+        }
+    }
+
+    /// # Safety
+    ///
+    /// None beyond a valid config; unsafe only to match `ffi`.
+    pub unsafe fn getOptions(&self) -> i32 {
+        self.options
+    }
+
+    /// # Safety
+    ///
+    /// The setting must belong to a config that is still alive and must not
+    /// have been removed.
+    pub unsafe fn setOptions(self: Pin<&mut Self>, options: i32) {
+        self.get_unchecked_mut().options = options;
+    }
+
+    /// # Safety
+    ///
+    /// None beyond a valid config; unsafe only to match `ffi`.
+    pub unsafe fn getDefaultFormat(&self) -> Format {
+        self.default_format
+    }
+
+    /// # Safety
+    ///
+    /// The setting must belong to a config that is still alive and must not
+    /// have been removed.
+    pub unsafe fn setDefaultFormat(self: Pin<&mut Self>, format: Format) {
+        self.get_unchecked_mut().default_format = match format {
+            Format::FormatHex => Format::FormatHex,
+            _ => Format::FormatDefault,
+        };
+    }
+
+    /// # Safety
+    ///
+    /// None beyond a valid config; unsafe only to match `ffi`.
+    pub unsafe fn getTabWidth(&self) -> u16 {
+        self.tab_width
+    }
+
+    // libconfig keeps the width within four bits.
+    /// # Safety
+    ///
+    /// The setting must belong to a config that is still alive and must not
+    /// have been removed.
+    pub unsafe fn setTabWidth(self: Pin<&mut Self>, width: u16) {
+        self.get_unchecked_mut().tab_width = width & 0x0F;
+    }
+
+    /// # Safety
+    ///
+    /// `path` must point to a NUL-terminated string.
+    pub unsafe fn exists(&self, path: *const c_char) -> bool {
+        !self
+            .root()
+            .lookup_raw(CStr::from_ptr(path).to_bytes())
+            .is_null()
+    }
+
+    /// # Safety
+    ///
+    /// `path` must point to a NUL-terminated string.
+    pub unsafe fn lookup_bool(&self, path: *const c_char, value: &mut bool) -> bool {
+        self.root().lookup_bool(path, value)
+    }
+
+    /// # Safety
+    ///
+    /// `path` must point to a NUL-terminated string.
+    pub unsafe fn lookup_i32(&self, path: *const c_char, value: &mut i32) -> bool {
+        self.root().lookup_i32(path, value)
+    }
+
+    /// # Safety
+    ///
+    /// `path` must point to a NUL-terminated string.
+    pub unsafe fn lookup_u32(&self, path: *const c_char, value: &mut u32) -> bool {
+        self.root().lookup_u32(path, value)
+    }
+
+    /// # Safety
+    ///
+    /// `path` must point to a NUL-terminated string.
+    pub unsafe fn lookup_f32(&self, path: *const c_char, value: &mut f32) -> bool {
+        self.root().lookup_f32(path, value)
+    }
+
+    /// # Safety
+    ///
+    /// `path` must point to a NUL-terminated string.
+    pub unsafe fn lookup_f64(&self, path: *const c_char, value: &mut f64) -> bool {
+        self.root().lookup_f64(path, value)
+    }
+
+    /// # Safety
+    ///
+    /// `path` must point to a NUL-terminated string.
+    pub unsafe fn lookup_string(&self, path: *const c_char, value: Pin<&mut StringBuf>) -> bool {
+        self.root().lookup_string(path, value)
+    }
+}
+
+/// # Safety
+///
+/// `path` must point to a NUL-terminated string.
+pub unsafe fn lookupValueI64FromConfig(
+    config: &Config,
+    path: *const c_char,
+    value: &mut i64,
+) -> bool {
+    lookupValueI64FromSetting(config.root(), path, value)
+}
+
+/// # Safety
+///
+/// `path` must point to a NUL-terminated string.
+pub unsafe fn lookupValueU64FromConfig(
+    config: &Config,
+    path: *const c_char,
+    value: &mut u64,
+) -> bool {
+    lookupValueU64FromSetting(config.root(), path, value)
+}
+
+/// # Safety
+///
+/// None beyond a valid config; unsafe only to match `ffi`.
+pub unsafe fn writeToString(config: &Config, out: Pin<&mut StringBuf>) -> Result<()> {
+    let bytes = write::write(config);
+    out.get_mut()
+        .set(String::from_utf8_lossy(&bytes).into_owned());
+    Ok(())
+}
+
+/// # Safety
+///
+/// `config` must be valid for `'c`, and no other handle to its root may be used
+/// while the one returned is.
+pub unsafe fn getRootFromConfig<'c>(config: *const Config) -> Pin<&'c mut Setting> {
+    wrap((*config).root).expect("config has a root")
+}
+
+/// # Safety
+///
+/// `path` must point to a NUL-terminated string.
+pub unsafe fn lookupSettingFromConfig(
+    cfg: Pin<&mut Config>,
+    path: *const c_char,
+) -> Result<Pin<&mut Setting>> {
+    wrap(cfg.root().lookup_raw(CStr::from_ptr(path).to_bytes()))
+}
+
+/// # Safety
+///
+/// None beyond a valid config; unsafe only to match `ffi`.
+pub unsafe fn getOptionFromConfig(config: &Config, option: ConfigOption) -> bool {
+    config.option(option)
+}
+
+/// # Safety
+///
+/// None beyond a valid config; unsafe only to match `ffi`.
+pub unsafe fn setOptionFromConfig(config: Pin<&mut Config>, option: ConfigOption, flag: bool) {
+    let options = match flag {
+        true => config.getOptions() | option.repr as i32,
+        false => config.getOptions() & !(option.repr as i32),
+    };
+    config.setOptions(options)
+}
+
+pub fn getVersionMajor() -> u32 {
+    VERSION.0
+}
+
+pub fn getVersionMinor() -> u32 {
+    VERSION.1
+}
+
+pub fn getVersionRevision() -> u32 {
+    VERSION.2
+}
+
+/// Kept for the same interface as the other bindings; everything is
+/// supported.
+pub mod compat {
+    use super::Config;
+    use std::pin::Pin;
+
+    pub fn float_precision(config: &Config) -> Option<u16> {
+        Some(config.float_precision)
+    }
+
+    pub fn set_float_precision(config: Pin<&mut Config>, digits: u16) -> bool {
+        unsafe { config.get_unchecked_mut().float_precision = digits };
+        true
+    }
+}
@@ -0,0 +1,556 @@
+//! The libconfig grammar: a scanner with `@include` support and a recursive
+//! descent parser building settings under the root of a config.
+
+use super::{Config, Setting, Type, Value};
+use crate::common::Format;
+use std::ffi::CString;
+use std::path::Path;
+use std::rc::Rc;
+
+// Deeper includes fail, as in libconfig.
+const MAX_INCLUDE_DEPTH: usize = 10;
+
+/// Where and why parsing stopped.
+pub(super) struct Error {
+    pub file: Option<Rc<str>>,
+    pub line: i32,
+    pub text: &'static str,
+}
+
+enum Tok {
+    Name(Vec<u8>),
+    Equals,
+    Semicolon,
+    Comma,
+    GroupStart,
+    GroupEnd,
+    ArrayStart,
+    ArrayEnd,
+    ListStart,
+    ListEnd,
+    Bool(bool),
+    Int(i32),
+    Int64(i64),
+    Hex(i32),
+    Hex64(i64),
+    Float(f64),
+    Str(Vec<u8>),
+    End,
+    // Anything the scanner cannot make sense of, with its own message when
+    // it is not a plain syntax error.
+    Error(Option<&'static str>),
+}
+
+struct Token {
+    tok: Tok,
+    file: Option<Rc<str>>,
+    line: i32,
+}
+
+struct Source {
+    text: Vec<u8>,
+    pos: usize,
+    line: i32,
+    file: Option<Rc<str>>,
+}
+
+impl Source {
+    fn peek(&self) -> Option<u8> {
+        self.text.get(self.pos).copied()
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<u8> {
+        self.text.get(self.pos + offset).copied()
+    }
+
+    fn bump(&mut self) -> Option<u8> {
+        let c = self.peek()?;
+        self.pos += 1;
+        if c == b'\n' {
+            self.line += 1;
+        }
+        Some(c)
+    }
+
+    fn eat(&mut self, c: u8) -> bool {
+        match self.peek() == Some(c) {
+            true => {
+                self.bump();
+                true
+            }
+            false => false,
+        }
+    }
+
+    fn rest(&self) -> &[u8] {
+        &self.text[self.pos..]
+    }
+}
+
+struct Scanner<'a> {
+    // Innermost include last.
+    sources: Vec<Source>,
+    include_dir: Option<&'a str>,
+}
+
+impl Scanner<'_> {
+    fn source(&mut self) -> &mut Source {
+        self.sources.last_mut().expect("scanner has a source")
+    }
+
+    fn token(&mut self, tok: Tok) -> Token {
+        let source = self.source();
+        Token {
+            tok,
+            file: source.file.clone(),
+            line: source.line,
+        }
+    }
+
+    fn next(&mut self) -> Token {
+        loop {
+            if let Some(file) = self.include_directive() {
+                if let Err(text) = self.include(&file) {
+                    return self.token(Tok::Error(Some(text)));
+                }
+                continue;
+            }
+            if self.skip_blank() {
+                continue;
+            }
+            if self.source().peek().is_none() {
+                match self.sources.len() > 1 {
+                    true => {
+                        self.sources.pop();
+                        continue;
+                    }
+                    false => return self.token(Tok::End),
+                }
+            }
+            let tok = self.scan();
+            return self.token(tok);
+        }
+    }
+
+    // Skips one run of whitespace or one comment, and tells whether anything
+    // was skipped.
+    fn skip_blank(&mut self) -> bool {
+        let blank = |c: Option<u8>| matches!(c, Some(b' ' | b'\t' | b'\r' | b'\n' | b'\x0c'));
+        let source = self.source();
+        match (source.peek(), source.peek_at(1)) {
+            (c, _) if blank(c) => {
+                while blank(source.peek()) {
+                    source.bump();
+                }
+            }
+            (Some(b'#'), _) | (Some(b'/'), Some(b'/')) => {
+                while !matches!(source.peek(), None | Some(b'\n')) {
+                    source.bump();
+                }
+            }
+            (Some(b'/'), Some(b'*')) => {
+                source.pos += 2;
+                while source.peek().is_some() && !source.rest().starts_with(b"*/") {
+                    source.bump();
+                }
+                source.pos = (source.pos + 2).min(source.text.len());
+            }
+            _ => return false,
+        }
+        true
+    }
+
+    // Consumes `@include "file"` and returns the file name. Like libconfig's
+    // scanner, this only sees directives right after a line break, since a
+    // whitespace run swallows the blanks that start the next line; only the
+    // first line may indent one.
+    fn include_directive(&mut self) -> Option<Vec<u8>> {
+        let source = self.source();
+        if source.pos > 0 && source.text[source.pos - 1] != b'\n' {
+            return None;
+        }
+        let blanks = |rest: &[u8]| {
+            rest.iter()
+                .take_while(|c| matches!(c, b' ' | b'\t'))
+                .count()
+        };
+        let mut pos = source.pos + blanks(source.rest());
+        if !source.text[pos..].starts_with(b"@include") {
+            return None;
+        }
+        pos += b"@include".len();
+        let gap = blanks(&source.text[pos..]);
+        if gap == 0 || source.text.get(pos + gap) != Some(&b'"') {
+            return None;
+        }
+        source.pos = pos + gap + 1;
+        let mut file = Vec::new();
+        loop {
+            match source.bump() {
+                Some(b'"') | None => return Some(file),
+                Some(b'\\') if matches!(source.peek(), Some(b'\\' | b'"')) => {
+                    file.push(source.bump().unwrap_or_default())
+                }
+                Some(c) => file.push(c),
+            }
+        }
+    }
+
+    fn include(&mut self, file: &[u8]) -> Result<(), &'static str> {
+        if self.sources.len() > MAX_INCLUDE_DEPTH {
+            return Err("include file nesting too deep");
+        }
+        let file = String::from_utf8_lossy(file).into_owned();
+        let path = match self.include_dir {
+            Some(dir) if !Path::new(&file).is_absolute() => format!("{}/{}", dir, file),
+            _ => file,
+        };
+        let text = std::fs::read(&path).map_err(|_| "cannot open include file")?;
+        self.sources.push(Source {
+            text,
+            pos: 0,
+            line: 1,
+            file: Some(path.into()),
+        });
+        Ok(())
+    }
+
+    fn scan(&mut self) -> Tok {
+        let source = self.source();
+        let c = source.peek().expect("scan past the end");
+        let punct = match c {
+            b'=' | b':' => Some(Tok::Equals),
+            b';' => Some(Tok::Semicolon),
+            b',' => Some(Tok::Comma),
+            b'{' => Some(Tok::GroupStart),
+            b'}' => Some(Tok::GroupEnd),
+            b'[' => Some(Tok::ArrayStart),
+            b']' => Some(Tok::ArrayEnd),
+            b'(' => Some(Tok::ListStart),
+            b')' => Some(Tok::ListEnd),
+            _ => None,
+        };
+        if let Some(tok) = punct {
+            source.bump();
+            return tok;
+        }
+        match c {
+            b'"' => self.string(),
+            b'A'..=b'Z' | b'a'..=b'z' | b'*' => self.name(),
+            b'0'..=b'9' | b'-' | b'+' | b'.' => self.number(),
+            _ => {
+                source.bump();
+                Tok::Error(None)
+            }
+        }
+    }
+
+    fn name(&mut self) -> Tok {
+        let source = self.source();
+        let start = source.pos;
+        source.pos += source
+            .rest()
+            .iter()
+            .take_while(|c| c.is_ascii_alphanumeric() || b"-_*".contains(c))
+            .count();
+        let name = &source.text[start..source.pos];
+        match name {
+            _ if name.eq_ignore_ascii_case(b"true") => Tok::Bool(true),
+            _ if name.eq_ignore_ascii_case(b"false") => Tok::Bool(false),
+            _ => Tok::Name(name.to_vec()),
+        }
+    }
+
+    // One string literal; adjacent literals are joined by the parser.
+    fn string(&mut self) -> Tok {
+        let source = self.source();
+        source.bump();
+        let mut text = Vec::new();
+        loop {
+            match source.bump() {
+                None => return Tok::End,
+                Some(b'"') => return Tok::Str(text),
+                Some(b'\\') => match source.peek() {
+                    Some(b'\\') => text.push(b'\\'),
+                    Some(b'"') => text.push(b'"'),
+                    Some(b'f') => text.push(b'\x0c'),
+                    Some(b'n') => text.push(b'\n'),
+                    Some(b'r') => text.push(b'\r'),
+                    Some(b't') => text.push(b'\t'),
+                    Some(b'x')
+                        if source.peek_at(1).is_some_and(|c| c.is_ascii_hexdigit())
+                            && source.peek_at(2).is_some_and(|c| c.is_ascii_hexdigit()) =>
+                    {
+                        let digits = &source.text[source.pos + 1..source.pos + 3];
+                        let digits = std::str::from_utf8(digits).unwrap_or_default();
+                        text.push(u8::from_str_radix(digits, 16).unwrap_or_default());
+                        source.pos += 2;
+                    }
+                    // Any other backslash stands for itself.
+                    _ => {
+                        text.push(b'\\');
+                        continue;
+                    }
+                },
+                Some(c) => {
+                    text.push(c);
+                    continue;
+                }
+            }
+            source.bump();
+        }
+    }
+
+    fn number(&mut self) -> Tok {
+        let source = self.source();
+        let start = source.pos;
+        let digits = |source: &Source, from: usize| {
+            source.text[from..]
+                .iter()
+                .take_while(|c| c.is_ascii_digit())
+                .count()
+        };
+        let int64 = |source: &mut Source| match source.eat(b'L') {
+            true => {
+                source.eat(b'L');
+                true
+            }
+            false => false,
+        };
+
+        let hex = source.rest().len() > 2
+            && matches!(&source.rest()[..2], b"0x" | b"0X")
+            && source.text[start + 2].is_ascii_hexdigit();
+        if hex {
+            source.pos += 2;
+            let count = source
+                .rest()
+                .iter()
+                .take_while(|c| c.is_ascii_hexdigit())
+                .count();
+            // Saturates like strtoull.
+            let value = std::str::from_utf8(&source.rest()[..count])
+                .ok()
+                .and_then(|s| u64::from_str_radix(s, 16).ok())
+                .unwrap_or(u64::MAX);
+            source.pos += count;
+            return match int64(source) {
+                true => Tok::Hex64(value as i64),
+                false => Tok::Hex(value as u32 as i32),
+            };
+        }
+
+        let mut pos = start + matches!(source.peek(), Some(b'-' | b'+')) as usize;
+        let whole = digits(source, pos);
+        pos += whole;
+        let mut float = false;
+        if source.text.get(pos) == Some(&b'.') {
+            float = true;
+            pos += 1;
+            pos += digits(source, pos);
+        }
+        if (float || whole > 0) && matches!(source.text.get(pos), Some(b'e' | b'E')) {
+            let sign = matches!(source.text.get(pos + 1), Some(b'-' | b'+')) as usize;
+            let exponent = digits(source, pos + 1 + sign);
+            if exponent > 0 {
+                float = true;
+                pos += 1 + sign + exponent;
+            }
+        }
+        if !float && whole == 0 {
+            source.pos = pos.max(start + 1);
+            return Tok::Error(None);
+        }
+        let text = String::from_utf8_lossy(&source.text[start..pos]).into_owned();
+        source.pos = pos;
+        if float {
+            return Tok::Float(text.parse().unwrap_or(0.0));
+        }
+        match (text.parse::<i64>(), int64(source)) {
+            (Ok(value), true) => Tok::Int64(value),
+            (Ok(value), false) => match i32::try_from(value) {
+                Ok(value) => Tok::Int(value),
+                Err(_) => Tok::Int64(value),
+            },
+            (Err(_), _) => Tok::Error(None),
+        }
+    }
+}
+
+struct Parser<'a> {
+    scanner: Scanner<'a>,
+    peeked: Option<Token>,
+}
+
+impl Parser<'_> {
+    fn next(&mut self) -> Token {
+        match self.peeked.take() {
+            Some(token) => token,
+            None => self.scanner.next(),
+        }
+    }
+
+    fn peek(&mut self) -> &Tok {
+        let token = self.next();
+        &self.peeked.insert(token).tok
+    }
+
+    fn error(token: Token, text: &'static str) -> Error {
+        Error {
+            file: token.file,
+            line: token.line,
+            text,
+        }
+    }
+
+    fn unexpected(token: Token) -> Error {
+        let text = match token.tok {
+            Tok::Error(Some(text)) => text,
+            _ => "syntax error",
+        };
+        Parser::error(token, text)
+    }
+
+    fn settings(&mut self, parent: *mut Setting, nested: bool) -> Result<(), Error> {
+        loop {
+            let token = self.next();
+            match token.tok {
+                Tok::Name(name) => self.setting(parent, &name)?,
+                Tok::GroupEnd if nested => return Ok(()),
+                Tok::End if !nested => return Ok(()),
+                _ => return Err(Parser::unexpected(token)),
+            }
+        }
+    }
+
+    fn setting(&mut self, parent: *mut Setting, name: &[u8]) -> Result<(), Error> {
+        let equals = self.next();
+        if !matches!(equals.tok, Tok::Equals) {
+            return Err(Parser::unexpected(equals));
+        }
+        let setting = match unsafe { (*parent).add_member(name, Type::TypeNone) } {
+            Some(setting) => setting,
+            None => return Err(Parser::error(equals, "duplicate setting name")),
+        };
+        let token = self.next();
+        self.value(setting, token)?;
+        if matches!(self.peek(), Tok::Semicolon | Tok::Comma) {
+            self.next();
+        }
+        Ok(())
+    }
+
+    fn value(&mut self, setting: *mut Setting, token: Token) -> Result<(), Error> {
+        let aggregate = match token.tok {
+            Tok::GroupStart => Type::TypeGroup,
+            Tok::ArrayStart => Type::TypeArray,
+            Tok::ListStart => Type::TypeList,
+            _ => {
+                let (value, format) = self.scalar(token)?;
+                assign(setting, value, format);
+                return Ok(());
+            }
+        };
+        // Overridden aggregates of the same type keep their children.
+        let setting_ref = unsafe { &mut *setting };
+        if setting_ref.value.type_() != aggregate {
+            setting_ref.value = Value::of(aggregate);
+        }
+        match aggregate {
+            Type::TypeGroup => self.settings(setting, true),
+            _ => self.elements(setting, aggregate == Type::TypeArray),
+        }
+    }
+
+    fn elements(&mut self, parent: *mut Setting, array: bool) -> Result<(), Error> {
+        let mut token = self.next();
+        if matches!(token.tok, Tok::ArrayEnd if array)
+            || matches!(token.tok, Tok::ListEnd if !array)
+        {
+            return Ok(());
+        }
+        let parent = unsafe { &mut *parent };
+        loop {
+            if array {
+                let line = (token.file.clone(), token.line);
+                let (value, format) = self.scalar(token)?;
+                if let Some(&first) = parent.children().first() {
+                    if unsafe { (*first).value.type_() } != value.type_() {
+                        return Err(Error {
+                            file: line.0,
+                            line: line.1,
+                            text: "mismatched element type in array",
+                        });
+                    }
+                }
+                assign(parent.push(None, Type::TypeNone), value, format);
+            } else {
+                let element = parent.push(None, Type::TypeNone);
+                self.value(element, token)?;
+            }
+            let separator = self.next();
+            match separator.tok {
+                Tok::Comma => token = self.next(),
+                Tok::ArrayEnd if array => return Ok(()),
+                Tok::ListEnd if !array => return Ok(()),
+                _ => return Err(Parser::unexpected(separator)),
+            }
+        }
+    }
+
+    fn scalar(&mut self, token: Token) -> Result<(Value, Format), Error> {
+        let value = match token.tok {
+            Tok::Bool(val) => Value::Bool(val),
+            Tok::Int(val) => Value::Int(val),
+            Tok::Int64(val) => Value::Int64(val),
+            Tok::Hex(val) => return Ok((Value::Int(val), Format::FormatHex)),
+            Tok::Hex64(val) => return Ok((Value::Int64(val), Format::FormatHex)),
+            Tok::Float(val) => Value::Float(val),
+            Tok::Str(mut text) => {
+                while matches!(self.peek(), Tok::Str(_)) {
+                    if let Tok::Str(more) = self.next().tok {
+                        text.extend(more);
+                    }
+                }
+                // An escaped NUL ends the string, as it does in C.
+                text.truncate(text.iter().position(|&c| c == 0).unwrap_or(text.len()));
+                Value::String(CString::new(text).unwrap_or_default())
+            }
+            _ => return Err(Parser::unexpected(token)),
+        };
+        Ok((value, Format::FormatDefault))
+    }
+}
+
+// Sets a parsed scalar. A setting that already had a value, because the
+// config allows overrides, only takes values of its own type.
+fn assign(setting: *mut Setting, value: Value, format: Format) {
+    let setting = unsafe { &mut *setting };
+    let own = setting.value.type_();
+    if own == Type::TypeNone || own == value.type_() {
+        setting.value = value;
+        setting.format = format;
+    }
+}
+
+/// Parses `input`, read from `file` unless it is a string, into the root of
+/// `config`.
+pub(super) fn parse(config: &Config, input: &[u8], file: Option<&str>) -> Result<(), Error> {
+    let include_dir = config
+        .include_dir
+        .as_deref()
+        .and_then(|dir| dir.to_str().ok());
+    let mut parser = Parser {
+        scanner: Scanner {
+            sources: vec![Source {
+                text: input.to_vec(),
+                pos: 0,
+                line: 1,
+                file: file.map(Rc::from),
+            }],
+            include_dir,
+        },
+        peeked: None,
+    };
+    parser.settings(config.root, false)
+}
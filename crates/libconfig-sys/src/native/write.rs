@@ -0,0 +1,207 @@
+//! Config output, byte for byte as libconfig writes it.
+
+use super::{Config, ConfigOption, Format, Setting, Value};
+
+struct Writer<'a> {
+    config: &'a Config,
+    out: Vec<u8>,
+}
+
+impl Writer<'_> {
+    fn indent(&mut self, depth: usize) {
+        let (fill, count) = match self.config.tab_width {
+            0 => (b'\t', depth - 1),
+            width => (b' ', (depth - 1) * width as usize),
+        };
+        self.out.extend(std::iter::repeat_n(fill, count));
+    }
+
+    fn setting(&mut self, setting: &Setting, depth: usize) {
+        if depth > 1 {
+            self.indent(depth);
+        }
+        if let Some(name) = &setting.name {
+            let colon = match setting.value {
+                Value::Group(_) => ConfigOption::OptionColonAssignmentForGroups,
+                _ => ConfigOption::OptionColonAssignmentForNonGroups,
+            };
+            self.out.extend(name.to_bytes());
+            self.out.extend(match self.config.option(colon) {
+                true => b" : ",
+                false => b" = ",
+            });
+        }
+        self.value(setting, depth);
+        if depth > 0 {
+            if self.config.option(ConfigOption::OptionSemicolonSeparators) {
+                self.out.push(b';');
+            }
+            self.out.push(b'\n');
+        }
+    }
+
+    fn value(&mut self, setting: &Setting, depth: usize) {
+        let hex = unsafe { setting.getFormat() } == Format::FormatHex;
+        match &setting.value {
+            Value::None => self.out.extend(b"???"),
+            Value::Bool(val) => self.out.extend(match val {
+                true => &b"true"[..],
+                false => &b"false"[..],
+            }),
+            Value::Int(val) => self.out.extend(
+                match hex {
+                    true => format!("0x{:X}", *val as u32),
+                    false => val.to_string(),
+                }
+                .bytes(),
+            ),
+            Value::Int64(val) => self.out.extend(
+                match hex {
+                    true => format!("0x{:X}L", *val as u64),
+                    false => format!("{}L", val),
+                }
+                .bytes(),
+            ),
+            Value::Float(val) => {
+                let scientific = self
+                    .config
+                    .option(ConfigOption::OptionAllowScientificNotation);
+                let text = format_double(*val, self.config.float_precision as usize, scientific);
+                self.out.extend(text.bytes());
+            }
+            Value::String(val) => {
+                self.out.push(b'"');
+                for &c in val.to_bytes() {
+                    match c {
+                        b'"' | b'\\' => self.out.extend([b'\\', c]),
+                        b'\n' => self.out.extend(b"\\n"),
+                        b'\r' => self.out.extend(b"\\r"),
+                        b'\x0c' => self.out.extend(b"\\f"),
+                        b'\t' => self.out.extend(b"\\t"),
+                        c if c < b' ' => self.out.extend(format!("\\x{:02X}", c).bytes()),
+                        c => self.out.push(c),
+                    }
+                }
+                self.out.push(b'"');
+            }
+            Value::Array(elements) => self.elements(b'[', elements, b']', depth),
+            Value::List(elements) => self.elements(b'(', elements, b')', depth),
+            Value::Group(members) => {
+                if depth > 0 {
+                    if self
+                        .config
+                        .option(ConfigOption::OptionOpenBraceOnSeparateLine)
+                    {
+                        self.out.push(b'\n');
+                        if depth > 1 {
+                            self.indent(depth);
+                        }
+                    }
+                    self.out.extend(b"{\n");
+                }
+                for &member in members {
+                    self.setting(unsafe { &*member }, depth + 1);
+                }
+                if depth > 1 {
+                    self.indent(depth);
+                }
+                if depth > 0 {
+                    self.out.push(b'}');
+                }
+            }
+        }
+    }
+
+    fn elements(&mut self, open: u8, elements: &[*mut Setting], close: u8, depth: usize) {
+        self.out.extend([open, b' ']);
+        for (idx, &element) in elements.iter().enumerate() {
+            self.value(unsafe { &*element }, depth + 1);
+            if idx + 1 < elements.len() {
+                self.out.push(b',');
+            }
+            self.out.push(b' ');
+        }
+        self.out.push(close);
+    }
+}
+
+// printf's "%.*f", or "%.*g" when scientific notation is allowed, with
+// trailing zeros trimmed down to one decimal unless there is an exponent.
+fn format_double(val: f64, precision: usize, scientific: bool) -> String {
+    let mut text = match (val.is_finite(), scientific) {
+        (false, _) => c_special(val),
+        (true, false) => format!("{:.*}", precision, val),
+        (true, true) => format_g(val, precision),
+    };
+    if !text.contains('e') {
+        match text.find('.') {
+            None => text.push_str(".0"),
+            Some(dot) => {
+                let keep = text.trim_end_matches('0').len().max(dot + 2);
+                text.truncate(keep);
+            }
+        }
+    }
+    text
+}
+
+fn c_special(val: f64) -> String {
+    match (val.is_nan(), val.is_sign_negative()) {
+        (true, true) => "-nan".to_string(),
+        (true, false) => "nan".to_string(),
+        (false, true) => "-inf".to_string(),
+        (false, false) => "inf".to_string(),
+    }
+}
+
+// printf's "%.*g": the shorter of fixed and exponent notation for the
+// precision, without trailing zeros.
+fn format_g(val: f64, precision: usize) -> String {
+    let precision = precision.max(1);
+    let exp_form = format!("{:.*e}", precision - 1, val);
+    let (mantissa, exponent) = exp_form.split_once('e').expect("exponent in {:e} output");
+    let exponent: i32 = exponent.parse().expect("integer exponent");
+    let trim = |s: &str| match s.contains('.') {
+        true => s.trim_end_matches('0').trim_end_matches('.').to_string(),
+        false => s.to_string(),
+    };
+    match exponent < -4 || exponent >= precision as i32 {
+        true => format!(
+            "{}e{}{:02}",
+            trim(mantissa),
+            if exponent < 0 { '-' } else { '+' },
+            exponent.abs()
+        ),
+        false => trim(&format!(
+            "{:.*}",
+            (precision as i32 - 1 - exponent) as usize,
+            val
+        )),
+    }
+}
+
+/// The config as libconfig's `config_write` would write it.
+pub(super) fn write(config: &Config) -> Vec<u8> {
+    let mut writer = Writer {
+        config,
+        out: Vec::new(),
+    };
+    writer.setting(unsafe { &*config.root }, 0);
+    writer.out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::format_double;
+
+    #[test]
+    fn ok_on_format_double() {
+        assert_eq!(format_double(1.0, 6, false), "1.0");
+        assert_eq!(format_double(0.48, 6, false), "0.48");
+        assert_eq!(format_double(-12.5, 2, false), "-12.5");
+        assert_eq!(format_double(1e10, 6, true), "1e+10");
+        assert_eq!(format_double(0.0001, 6, true), "0.0001");
+        assert_eq!(format_double(123456789.0, 6, true), "1.23457e+08");
+        assert_eq!(format_double(100.0, 6, true), "100.0");
+    }
+}
@@ -0,0 +1,11 @@
+#[cxx::bridge]
+pub mod ffi {
+    unsafe extern "C++" {
+        include!("libconfig-sys/include/v1_6.h");
+
+        type Config = crate::ffi::Config;
+
+        unsafe fn getFloatPrecisionFromConfig(config: &Config) -> u16;
+        unsafe fn setFloatPrecisionFromConfig(config: Pin<&mut Config>, digits: u16);
+    }
+}
@@ -0,0 +1,132 @@
+//! Runs libconfig++ and the implementation in Rust over the files in
+//! `input/corpus` and checks that they agree on the settings read, on the
+//! output written back, and on where and why invalid files fail.
+#![cfg(all(feature = "cxx", feature = "pure-rust"))]
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const CORPUS: &str = "../input/corpus";
+
+// The options and tab width each file is written back with, the defaults
+// first.
+const WRITE_VARIANTS: [(Option<i32>, u16); 3] = [(None, 2), (Some(0), 0), (Some(0x3F), 4)];
+
+macro_rules! backend {
+    ($name:ident, $bindings:path, $let_string:path) => {
+        mod $name {
+            use super::WRITE_VARIANTS;
+            use std::ffi::CString;
+            use std::fmt::Write;
+            use std::path::Path;
+            use std::pin::Pin;
+            use $bindings::*;
+            use $let_string as let_string;
+
+            // One line per setting: path, type, format and value.
+            unsafe fn dump(mut setting: Pin<&mut Setting>, out: &mut String) {
+                let_string!(path = "");
+                getPathFromSetting(&setting, path.as_mut());
+                let length = setting.getLength().unwrap_or(0);
+                let value = match setting.getType() {
+                    Type::TypeInt => format!("{:?}", tryI32FromSetting(&setting).ok()),
+                    Type::TypeInt64 => format!("{:?}", tryI64FromSetting(&setting).ok()),
+                    Type::TypeFloat => format!("{:?}", tryF64FromSetting(&setting).ok()),
+                    Type::TypeBoolean => format!("{:?}", tryBoolFromSetting(&setting).ok()),
+                    Type::TypeString => format!(
+                        "{:?}",
+                        tryStringFromSetting(&setting)
+                            .ok()
+                            .map(|val| val.to_string())
+                    ),
+                    _ => format!("{} children", length),
+                };
+                writeln!(
+                    out,
+                    "{} {:?} {:?} {}",
+                    path,
+                    setting.getType(),
+                    setting.getFormat(),
+                    value
+                )
+                .unwrap();
+                for idx in 0..length {
+                    dump(
+                        getElemFromSetting(setting.as_mut(), idx as u32).unwrap(),
+                        out,
+                    );
+                }
+            }
+
+            /// The dump of `file`, followed by the file as written with each
+            /// of `WRITE_VARIANTS`, or the message of the failed read.
+            pub fn read(file: &Path, include_dir: &Path) -> Result<Vec<String>, String> {
+                let file = CString::new(file.to_str().unwrap()).unwrap();
+                let include_dir = CString::new(include_dir.to_str().unwrap()).unwrap();
+                let mut config = Config_ctor();
+                unsafe {
+                    config.pin_mut().setIncludeDir(include_dir.as_ptr());
+                    config
+                        .pin_mut()
+                        .readFile(file.as_ptr())
+                        .map_err(|err| err.what().to_string())?;
+                    let mut tree = String::new();
+                    dump(getRootFromConfig(config.as_ref().unwrap()), &mut tree);
+                    let mut results = vec![tree];
+                    for (options, tab_width) in WRITE_VARIANTS {
+                        if let Some(options) = options {
+                            config.pin_mut().setOptions(options);
+                        }
+                        config.pin_mut().setTabWidth(tab_width);
+                        let_string!(written = "");
+                        writeToString(&config, written.as_mut()).unwrap();
+                        results.push(written.to_string());
+                    }
+                    Ok(results)
+                }
+            }
+        }
+    };
+}
+
+backend!(cpp, libconfig_sys::ffi, cxx::let_cxx_string);
+backend!(native, libconfig_sys::native, libconfig_sys::let_string);
+
+fn corpus(dir: &str) -> Vec<PathBuf> {
+    let mut files: Vec<_> = fs::read_dir(Path::new(CORPUS).join(dir))
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "cfg"))
+        .collect();
+    files.sort();
+    assert!(!files.is_empty(), "no files in {}/{}", CORPUS, dir);
+    files
+}
+
+#[test]
+fn ok_on_valid_corpus() {
+    for file in corpus("valid") {
+        let expected = cpp::read(&file, Path::new(CORPUS));
+        assert!(expected.is_ok(), "{}: {:?}", file.display(), expected);
+        assert_eq!(
+            native::read(&file, Path::new(CORPUS)),
+            expected,
+            "{}",
+            file.display()
+        );
+    }
+}
+
+#[test]
+fn err_on_invalid_corpus() {
+    for file in corpus("invalid") {
+        let expected = cpp::read(&file, Path::new(CORPUS));
+        assert!(expected.is_err(), "{} was read", file.display());
+        assert_eq!(
+            native::read(&file, Path::new(CORPUS)),
+            expected,
+            "{}",
+            file.display()
+        );
+    }
+}
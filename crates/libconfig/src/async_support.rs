@@ -0,0 +1,97 @@
+use crate::watch::Update;
+use crate::{Config, LibconfigError, WatchedConfig};
+use futures_core::Stream;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tokio::sync::mpsc::{self, UnboundedReceiver};
+
+impl Config {
+    /// Reads `path` on tokio's blocking pool so that parsing does not stall
+    /// the runtime. The config is handed back across threads, which relies
+    /// on `Config` being `Send`.
+    pub async fn load_async(path: &str) -> Result<Config, LibconfigError> {
+        let path = path.to_string();
+        tokio::task::spawn_blocking(move || {
+            let mut cfg = Config::new();
+            cfg.read_file(&path).map(|()| cfg)
+        })
+        .await
+        .map_err(|err| LibconfigError::Io(err.to_string()))?
+    }
+}
+
+/// Snapshots of a `WatchedConfig`, one per reload.
+pub struct ConfigStream {
+    receiver: UnboundedReceiver<Update>,
+}
+
+impl Stream for ConfigStream {
+    type Item = Update;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Update>> {
+        self.receiver.poll_recv(cx)
+    }
+}
+
+impl WatchedConfig {
+    /// Yields the outcome of every reload after this call. The stream ends
+    /// when the `WatchedConfig` is dropped.
+    pub fn stream(&self) -> ConfigStream {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        self.add_subscriber(Box::new(move |update| sender.send(update.clone()).is_ok()));
+        ConfigStream { receiver }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::scratch_dir;
+    use crate::{Value, WatchOptions};
+    use std::fs;
+    use std::future::poll_fn;
+    use std::time::Duration;
+
+    fn assert_send<T: Send>(_: &T) {}
+
+    #[test]
+    fn ok_on_spawnable_load() {
+        // Futures that are not `Send` cannot be spawned on a multi-threaded
+        // runtime.
+        assert_send(&Config::load_async("../input/test.cfg"));
+    }
+
+    #[tokio::test]
+    async fn ok_on_load_async() {
+        let cfg = Config::load_async("../input/test.cfg").await.unwrap();
+        assert_eq!(cfg.lookup_i32("val_int"), Some(42));
+        assert!(matches!(
+            Config::load_async("../input/invalid.cfg").await,
+            Err(LibconfigError::Syntax { line: 1, .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn ok_on_change_stream() {
+        let dir = scratch_dir("stream");
+        let path = dir.join("app.cfg");
+        fs::write(&path, "port = 80;\n").unwrap();
+        let options = WatchOptions {
+            poll_interval: Duration::from_millis(10),
+            ..WatchOptions::default()
+        };
+        let watched = WatchedConfig::new(path.to_str().unwrap(), options).unwrap();
+        let mut stream = watched.stream();
+        fs::write(&path, "port = 8080;\n").unwrap();
+        let next = poll_fn(|cx| Pin::new(&mut stream).poll_next(cx));
+        let update = tokio::time::timeout(Duration::from_secs(5), next)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(update.unwrap().get("port"), Some(&Value::Int(8080)));
+        drop(watched);
+        assert!(poll_fn(|cx| Pin::new(&mut stream).poll_next(cx))
+            .await
+            .is_none());
+    }
+}
@@ -0,0 +1,234 @@
+use crate::{Config, LibconfigError};
+use std::collections::hash_map::DefaultHasher;
+use std::fs::{self, File, OpenOptions};
+use std::hash::{Hash, Hasher};
+use std::io::{self, Write};
+use std::path::{self, Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::SystemTime;
+
+static TEMP_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+#[derive(Debug, Clone, Default)]
+pub struct WriteOptions {
+    /// Keep the replaced file as `<path>.bak`.
+    pub backup: bool,
+    /// Hold an exclusive advisory lock on `<path>.lock` while writing. The
+    /// lock file is left in place so that every writer locks the same inode.
+    pub lock: bool,
+    /// Refuse to write when the modification time or the contents of the
+    /// file changed since `read_file` read it. There is nothing to compare
+    /// against, and so no check, when the config was not read from `path`
+    /// with `read_file`.
+    pub if_unchanged: bool,
+}
+
+// What a file looked like when it was read or last written.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct FileStamp {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    hash: u64,
+}
+
+impl FileStamp {
+    pub(crate) fn of(path: &Path) -> Option<FileStamp> {
+        let modified = fs::metadata(path).and_then(|meta| meta.modified()).ok();
+        FileStamp::from_contents(path, modified, &fs::read(path).ok()?)
+    }
+
+    // Stamps contents that were already read. `modified` must have been taken
+    // before reading, so that a write in between shows up as a change.
+    pub(crate) fn from_contents(
+        path: &Path,
+        modified: Option<SystemTime>,
+        contents: &[u8],
+    ) -> Option<FileStamp> {
+        let mut hasher = DefaultHasher::new();
+        contents.hash(&mut hasher);
+        Some(FileStamp {
+            path: path::absolute(path).ok()?,
+            modified,
+            hash: hasher.finish(),
+        })
+    }
+}
+
+impl Config {
+    /// Replaces the file at `path` so that readers see either the old or the
+    /// new config, never a partial one: the config is written to a temporary
+    /// file in the same directory, synced to disk and renamed over `path`.
+    pub fn write_file_atomic(
+        &mut self,
+        path: &str,
+        options: &WriteOptions,
+    ) -> Result<(), LibconfigError> {
+        let path = Path::new(path);
+        let output = self.write_str()?;
+        // Released when dropped at the end of the write.
+        let _lock = match options.lock {
+            true => Some(lock(&with_suffix(path, ".lock"))?),
+            false => None,
+        };
+        if options.if_unchanged {
+            if let Some(source) = &self.source {
+                if path::absolute(path).ok().as_ref() == Some(&source.path)
+                    && FileStamp::of(path).as_ref() != Some(source)
+                {
+                    return Err(LibconfigError::Conflict(path.display().to_string()));
+                }
+            }
+        }
+        replace_file(path, &output, options.backup)?;
+        self.source = FileStamp::of(path);
+        Ok(())
+    }
+}
+
+// Writes `output` to a temporary file next to `path` and renames it over
+// `path`, optionally keeping the old file as `<path>.bak`.
+pub(crate) fn replace_file(path: &Path, output: &str, backup: bool) -> Result<(), LibconfigError> {
+    let tmp = temp_path(path);
+    let result = write_synced(&tmp, output, path).and_then(|()| {
+        if backup && path.exists() {
+            fs::copy(path, with_suffix(path, ".bak")).map_err(|err| io_error(path, err))?;
+        }
+        fs::rename(&tmp, path).map_err(|err| io_error(path, err))?;
+        sync_dir(path)
+    });
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp);
+    }
+    result
+}
+
+fn lock(path: &Path) -> Result<File, LibconfigError> {
+    let file = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(path)
+        .map_err(|err| io_error(path, err))?;
+    file.lock().map_err(|err| io_error(path, err))?;
+    Ok(file)
+}
+
+fn write_synced(tmp: &Path, output: &str, target: &Path) -> Result<(), LibconfigError> {
+    let mut file = OpenOptions::new()
+        .create_new(true)
+        .write(true)
+        .open(tmp)
+        .map_err(|err| io_error(tmp, err))?;
+    if let Ok(meta) = fs::metadata(target) {
+        file.set_permissions(meta.permissions())
+            .map_err(|err| io_error(tmp, err))?;
+    }
+    file.write_all(output.as_bytes())
+        .and_then(|()| file.sync_all())
+        .map_err(|err| io_error(tmp, err))
+}
+
+// Makes the rename durable; directories cannot be opened for syncing on
+// every platform.
+fn sync_dir(path: &Path) -> Result<(), LibconfigError> {
+    if cfg!(unix) {
+        let dir = parent_dir(path);
+        File::open(dir)
+            .and_then(|dir| dir.sync_all())
+            .map_err(|err| io_error(dir, err))?;
+    }
+    Ok(())
+}
+
+fn parent_dir(path: &Path) -> &Path {
+    match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    }
+}
+
+fn temp_path(path: &Path) -> PathBuf {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    parent_dir(path).join(format!(
+        ".{}.{}-{}.tmp",
+        name,
+        process::id(),
+        TEMP_COUNTER.fetch_add(1, Ordering::Relaxed)
+    ))
+}
+
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+fn io_error(path: &Path, err: io::Error) -> LibconfigError {
+    LibconfigError::Io(format!("{}: {}", path.display(), err))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::scratch_dir;
+
+    #[test]
+    fn ok_on_atomic_write() {
+        let dir = scratch_dir("atomic");
+        let path = dir.join("app.cfg");
+        let path = path.to_str().unwrap();
+        fs::write(path, "old = 1;\n").unwrap();
+        let mut cfg = Config::new();
+        assert_eq!(cfg.read_str("new = 2;"), Ok(()));
+        let options = WriteOptions {
+            backup: true,
+            lock: true,
+            if_unchanged: false,
+        };
+        assert_eq!(cfg.write_file_atomic(path, &options), Ok(()));
+        let mut written = Config::new();
+        assert_eq!(written.read_file(path), Ok(()));
+        assert_eq!(written.lookup_i32("new"), Some(2));
+        let backup = fs::read_to_string(dir.join("app.cfg.bak")).unwrap();
+        assert_eq!(backup, "old = 1;\n");
+        let mut files = fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect::<Vec<_>>();
+        files.sort();
+        assert_eq!(files, ["app.cfg", "app.cfg.bak", "app.cfg.lock"]);
+    }
+
+    #[test]
+    fn err_on_concurrent_change() {
+        let dir = scratch_dir("conflict");
+        let path = dir.join("app.cfg");
+        let path = path.to_str().unwrap();
+        fs::write(path, "value = 1;\n").unwrap();
+        let mut cfg = Config::new();
+        assert_eq!(cfg.read_file(path), Ok(()));
+        let options = WriteOptions {
+            if_unchanged: true,
+            ..WriteOptions::default()
+        };
+        assert_eq!(cfg.write_file_atomic(path, &options), Ok(()));
+        // The stamp follows our own writes.
+        assert_eq!(cfg.write_file_atomic(path, &options), Ok(()));
+        fs::write(path, "value = 3;\n").unwrap();
+        assert_eq!(
+            cfg.write_file_atomic(path, &options),
+            Err(LibconfigError::Conflict(path.to_string()))
+        );
+        assert_eq!(fs::read_to_string(path).unwrap(), "value = 3;\n");
+    }
+
+    #[test]
+    fn err_on_missing_directory() {
+        let mut cfg = Config::new();
+        assert!(matches!(
+            cfg.write_file_atomic("missing-dir/app.cfg", &WriteOptions::default()),
+            Err(LibconfigError::Io(_))
+        ));
+    }
+}
@@ -0,0 +1,23 @@
+//! The bindings the safe API is built on: libconfig++ through cxx by
+//! default, the libconfig C API with the `capi` feature, or the
+//! implementation in Rust with `pure-rust`. All expose the same functions and
+//! types.
+
+#[cfg(not(any(feature = "cxx", feature = "capi", feature = "pure-rust")))]
+compile_error!("one of the `cxx`, `capi` or `pure-rust` features must be enabled");
+
+#[cfg(feature = "pure-rust")]
+pub use libconfig_sys::native::*;
+
+#[cfg(all(feature = "capi", not(feature = "pure-rust")))]
+pub use libconfig_sys::capi::*;
+
+#[cfg(any(feature = "capi", feature = "pure-rust"))]
+pub use libconfig_sys::let_string;
+
+#[cfg(not(any(feature = "capi", feature = "pure-rust")))]
+pub use cxx::{let_cxx_string as let_string, Exception, UniquePtr};
+#[cfg(not(any(feature = "capi", feature = "pure-rust")))]
+pub use libconfig_sys::compat;
+#[cfg(not(any(feature = "capi", feature = "pure-rust")))]
+pub use libconfig_sys::ffi::*;
@@ -0,0 +1,95 @@
+#[cfg(feature = "toml")]
+mod toml;
+#[cfg(feature = "yaml")]
+mod yaml;
+
+#[cfg(feature = "toml")]
+pub use self::toml::{from_toml, to_toml};
+#[cfg(feature = "yaml")]
+pub use self::yaml::{from_yaml, to_yaml};
+
+use crate::path::append;
+use crate::{Config, Format, FromConfig, LibconfigError, Setting, Type, Value};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum LossKind {
+    /// A TOML date or time, converted to a string.
+    Datetime,
+    /// A YAML null, which has no libconfig counterpart and is dropped.
+    Null,
+    /// A YAML anchor; its aliases are expanded into copies.
+    Anchor(String),
+    /// A YAML tag, dropped in favour of the tagged value.
+    Tag(String),
+    /// An integer written in hex, exported as a plain number.
+    HexFormat,
+    /// A 64-bit integer small enough to be read back as a 32-bit one.
+    IntWidth,
+    /// A list whose elements would all fit an array, so it is read back as one.
+    ListAsArray,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Loss {
+    pub path: String,
+    pub kind: LossKind,
+}
+
+/// Constructs that did not survive a conversion unchanged.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Report {
+    pub losses: Vec<Loss>,
+}
+
+impl Report {
+    pub fn is_lossless(&self) -> bool {
+        self.losses.is_empty()
+    }
+
+    pub(crate) fn push(&mut self, path: &str, kind: LossKind) {
+        self.losses.push(Loss {
+            path: path.to_string(),
+            kind,
+        });
+    }
+}
+
+// Collects what is lost when a config is exported to a format that keeps
+// neither integer formats nor the distinction between 32 and 64-bit integers
+// or between lists and arrays.
+pub(crate) fn export_report(cfg: &Config) -> Result<Report, LibconfigError> {
+    let mut report = Report::default();
+    walk(&mut cfg.get_root(), "", &mut report)?;
+    Ok(report)
+}
+
+fn walk(setting: &mut Setting, path: &str, report: &mut Report) -> Result<(), LibconfigError> {
+    if setting.get_type() == Type::TypeList {
+        if let Value::List(elems) = Value::from_setting(setting)? {
+            if matches!(Value::array_or_list(elems), Value::Array(_)) {
+                report.push(path, LossKind::ListAsArray);
+            }
+        }
+    }
+    if setting.is_aggregate() {
+        let is_group = setting.is_group();
+        for (idx, mut child) in setting.iter().enumerate() {
+            let component = match is_group {
+                true => child.get_name().unwrap_or_default().to_string(),
+                false => format!("[{}]", idx),
+            };
+            walk(&mut child, &append(path, &component), report)?;
+        }
+        return Ok(());
+    }
+    if setting.is_number() && setting.get_format() == Format::FormatHex {
+        report.push(path, LossKind::HexFormat);
+    }
+    if setting.get_type() == Type::TypeInt64 {
+        let val: i64 = FromConfig::from_setting(setting)?;
+        if i32::try_from(val).is_ok() {
+            report.push(path, LossKind::IntWidth);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,181 @@
+use super::{export_report, LossKind, Report};
+use crate::path::append;
+use crate::{Config, LibconfigError, Value};
+
+pub fn from_toml(input: &str) -> Result<(Config, Report), LibconfigError> {
+    let table: ::toml::Table = input
+        .parse()
+        .map_err(|err: ::toml::de::Error| LibconfigError::Parse(err.message().to_string()))?;
+    let mut report = Report::default();
+    let value = from_table("", &table, &mut report);
+    Ok((Config::from_value(&value)?, report))
+}
+
+pub fn to_toml(cfg: &Config) -> Result<(String, Report), LibconfigError> {
+    let report = export_report(cfg)?;
+    let table = match to_toml_value(&cfg.to_redacted_value()?) {
+        ::toml::Value::Table(table) => table,
+        _ => unreachable!("config root is a group"),
+    };
+    let output = ::toml::to_string(&table).map_err(|err| LibconfigError::Parse(err.to_string()))?;
+    Ok((output, report))
+}
+
+fn from_table(path: &str, table: &::toml::Table, report: &mut Report) -> Value {
+    Value::Group(
+        table
+            .iter()
+            .map(|(name, value)| {
+                let path = append(path, name);
+                (name.clone(), from_toml_value(&path, value, report))
+            })
+            .collect(),
+    )
+}
+
+fn from_toml_value(path: &str, value: &::toml::Value, report: &mut Report) -> Value {
+    match value {
+        ::toml::Value::String(val) => Value::String(val.clone()),
+        ::toml::Value::Integer(val) => match i32::try_from(*val) {
+            Ok(val) => Value::Int(val),
+            Err(_) => Value::Int64(*val),
+        },
+        ::toml::Value::Float(val) => Value::Float(*val),
+        ::toml::Value::Boolean(val) => Value::Bool(*val),
+        ::toml::Value::Datetime(val) => {
+            report.push(path, LossKind::Datetime);
+            Value::String(val.to_string())
+        }
+        ::toml::Value::Array(elems) => Value::array_or_list(
+            elems
+                .iter()
+                .enumerate()
+                .map(|(idx, elem)| {
+                    from_toml_value(&append(path, &format!("[{}]", idx)), elem, report)
+                })
+                .collect(),
+        ),
+        ::toml::Value::Table(table) => from_table(path, table, report),
+    }
+}
+
+fn to_toml_value(value: &Value) -> ::toml::Value {
+    match value {
+        Value::Bool(val) => ::toml::Value::Boolean(*val),
+        Value::Int(val) => ::toml::Value::Integer(i64::from(*val)),
+        Value::Int64(val) => ::toml::Value::Integer(*val),
+        Value::Float(val) => ::toml::Value::Float(*val),
+        Value::String(val) => ::toml::Value::String(val.clone()),
+        Value::Group(children) => ::toml::Value::Table(
+            children
+                .iter()
+                .map(|(name, child)| (name.clone(), to_toml_value(child)))
+                .collect(),
+        ),
+        Value::Array(elems) | Value::List(elems) => {
+            ::toml::Value::Array(elems.iter().map(to_toml_value).collect())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::convert::Loss;
+
+    #[test]
+    fn ok_on_toml_round_trip() {
+        let input =
+            "name = \"svc\"\nports = [80, 443]\n\n[db]\nhost = \"localhost\"\ntimeout = 1.5\n";
+        let (cfg, report) = from_toml(input).unwrap();
+        assert!(report.is_lossless());
+        assert_eq!(
+            cfg.lookup_string("db.host"),
+            Some(String::from("localhost"))
+        );
+        assert_eq!(cfg.lookup_i32("ports.[1]"), Some(443));
+        let (output, report) = to_toml(&cfg).unwrap();
+        assert!(report.is_lossless());
+        assert_eq!(
+            output.parse::<::toml::Table>(),
+            input.parse::<::toml::Table>()
+        );
+    }
+
+    #[test]
+    fn report_on_toml_datetime() {
+        let (cfg, report) = from_toml("[build]\nwhen = 1979-05-27T07:32:00Z\n").unwrap();
+        assert_eq!(
+            report.losses,
+            vec![Loss {
+                path: String::from("build.when"),
+                kind: LossKind::Datetime
+            }]
+        );
+        assert_eq!(
+            cfg.lookup_string("build.when"),
+            Some(String::from("1979-05-27T07:32:00Z"))
+        );
+    }
+
+    #[test]
+    fn report_on_hex_and_int64_export() {
+        let mut cfg = Config::new();
+        assert_eq!(cfg.read_file("../input/test.cfg"), Ok(()));
+        let (_, report) = to_toml(&cfg).unwrap();
+        assert_eq!(
+            report.losses,
+            vec![
+                Loss {
+                    path: String::from("another"),
+                    kind: LossKind::HexFormat
+                },
+                Loss {
+                    path: String::from("val_u64"),
+                    kind: LossKind::HexFormat
+                },
+            ]
+        );
+        assert_eq!(cfg.read_str("val = 0x10L;"), Ok(()));
+        let (_, report) = to_toml(&cfg).unwrap();
+        assert_eq!(
+            report
+                .losses
+                .iter()
+                .map(|loss| &loss.kind)
+                .collect::<Vec<_>>(),
+            vec![&LossKind::HexFormat, &LossKind::IntWidth]
+        );
+    }
+
+    #[test]
+    fn report_on_list_read_back_as_array() {
+        let mut cfg = Config::new();
+        assert_eq!(
+            cfg.read_str("same = (1, 2); mixed = (1, \"two\"); arr = [1, 2];"),
+            Ok(())
+        );
+        let (output, report) = to_toml(&cfg).unwrap();
+        assert_eq!(
+            report.losses,
+            vec![Loss {
+                path: String::from("same"),
+                kind: LossKind::ListAsArray
+            }]
+        );
+        let (mut back, _) = from_toml(&output).unwrap();
+        assert_eq!(
+            back.lookup("same").unwrap().get_type(),
+            crate::Type::TypeArray
+        );
+        assert_eq!(
+            back.lookup("mixed").unwrap().get_type(),
+            crate::Type::TypeList
+        );
+    }
+
+    #[test]
+    fn err_on_invalid_toml() {
+        assert!(matches!(from_toml("a = "), Err(LibconfigError::Parse(_))));
+    }
+}
@@ -0,0 +1,369 @@
+use super::{export_report, LossKind, Report};
+use crate::path::append;
+use crate::{Config, LibconfigError, Value};
+use serde_yaml::{Mapping, Number};
+use std::collections::HashMap;
+use std::ffi::CStr;
+use std::mem::MaybeUninit;
+use unsafe_libyaml::{
+    yaml_event_delete, yaml_event_t, yaml_parser_delete, yaml_parser_initialize, yaml_parser_parse,
+    yaml_parser_set_input_string, yaml_parser_t, YAML_ALIAS_EVENT, YAML_MAPPING_END_EVENT,
+    YAML_MAPPING_START_EVENT, YAML_SCALAR_EVENT, YAML_SEQUENCE_END_EVENT,
+    YAML_SEQUENCE_START_EVENT, YAML_STREAM_END_EVENT,
+};
+
+pub fn from_yaml(input: &str) -> Result<(Config, Report), LibconfigError> {
+    let yaml: serde_yaml::Value =
+        serde_yaml::from_str(input).map_err(|err| LibconfigError::Parse(err.to_string()))?;
+    let mut anchors = anchors(input);
+    let mut report = Report::default();
+    let value = match from_yaml_value("", &yaml, &mut anchors, &mut report)? {
+        Some(value) => value,
+        None => Value::Group(Vec::new()),
+    };
+    Ok((Config::from_value(&value)?, report))
+}
+
+pub fn to_yaml(cfg: &Config) -> Result<(String, Report), LibconfigError> {
+    let report = export_report(cfg)?;
+    let output = serde_yaml::to_string(&to_yaml_value(&cfg.to_redacted_value()?))
+        .map_err(|err| LibconfigError::Parse(err.to_string()))?;
+    Ok((output, report))
+}
+
+// A mapping or sequence being walked by `anchors`, with its path, or `None`
+// within a mapping key.
+enum Frame {
+    Sequence {
+        path: Option<String>,
+        next: usize,
+        is_key: bool,
+    },
+    Mapping {
+        path: Option<String>,
+        // The key whose value comes next, `Some(None)` if it is not a string.
+        key: Option<Option<String>>,
+        is_key: bool,
+    },
+}
+
+enum Slot {
+    Key,
+    Value(Option<String>),
+}
+
+// Where the next node goes within the innermost collection.
+fn next_slot(frames: &mut [Frame]) -> Slot {
+    match frames.last_mut() {
+        None => Slot::Value(Some(String::new())),
+        Some(Frame::Sequence { path, next, .. }) => {
+            let idx = format!("[{}]", next);
+            *next += 1;
+            Slot::Value(path.as_deref().map(|path| append(path, &idx)))
+        }
+        Some(Frame::Mapping { path, key, .. }) => match key.take() {
+            None => Slot::Key,
+            Some(key) => Slot::Value(
+                path.as_deref()
+                    .zip(key)
+                    .map(|(path, key)| append(path, &key)),
+            ),
+        },
+    }
+}
+
+// Records the key whose value comes next, `None` unless it is a scalar.
+fn set_key(frames: &mut [Frame], name: Option<String>) {
+    if let Some(Frame::Mapping { key, .. }) = frames.last_mut() {
+        *key = Some(name);
+    }
+}
+
+unsafe fn c_str(ptr: *const u8) -> Option<String> {
+    match ptr.is_null() {
+        true => None,
+        false => Some(CStr::from_ptr(ptr.cast()).to_string_lossy().into_owned()),
+    }
+}
+
+// serde_yaml expands aliases while parsing, so the anchors are taken from
+// libyaml's events, which serde_yaml is built on: the path of each anchored
+// node by the anchor's name.
+fn anchors(input: &str) -> HashMap<String, String> {
+    let mut anchors = HashMap::new();
+    let mut frames = Vec::new();
+    unsafe {
+        let mut parser = MaybeUninit::<yaml_parser_t>::uninit();
+        if yaml_parser_initialize(parser.as_mut_ptr()).fail {
+            return anchors;
+        }
+        let parser = parser.as_mut_ptr();
+        yaml_parser_set_input_string(parser, input.as_ptr(), input.len() as _);
+        loop {
+            let mut event = MaybeUninit::<yaml_event_t>::uninit();
+            // serde_yaml already accepted the input.
+            if yaml_parser_parse(parser, event.as_mut_ptr()).fail {
+                break;
+            }
+            let event = event.as_mut_ptr();
+            let done = visit(&*event, &mut frames, &mut anchors);
+            yaml_event_delete(event);
+            if done {
+                break;
+            }
+        }
+        yaml_parser_delete(parser);
+    }
+    anchors
+}
+
+// Returns whether the stream ended. `start` is `Some(true)` for a mapping
+// and `Some(false)` for a sequence that begins.
+unsafe fn visit(
+    event: &yaml_event_t,
+    frames: &mut Vec<Frame>,
+    anchors: &mut HashMap<String, String>,
+) -> bool {
+    let (anchor, start) = match event.type_ {
+        YAML_STREAM_END_EVENT => return true,
+        YAML_SCALAR_EVENT => (event.data.scalar.anchor, None),
+        YAML_ALIAS_EVENT => (std::ptr::null_mut(), None),
+        YAML_SEQUENCE_START_EVENT => (event.data.sequence_start.anchor, Some(false)),
+        YAML_MAPPING_START_EVENT => (event.data.mapping_start.anchor, Some(true)),
+        YAML_SEQUENCE_END_EVENT | YAML_MAPPING_END_EVENT => {
+            if let Some(
+                Frame::Sequence { is_key: true, .. } | Frame::Mapping { is_key: true, .. },
+            ) = frames.pop()
+            {
+                set_key(frames, None);
+            }
+            return false;
+        }
+        _ => return false,
+    };
+    let slot = next_slot(frames);
+    if let (Slot::Value(Some(path)), Some(name)) = (&slot, c_str(anchor)) {
+        anchors.insert(path.clone(), name);
+    }
+    let is_key = matches!(slot, Slot::Key);
+    let path = match slot {
+        Slot::Value(path) => path,
+        Slot::Key => None,
+    };
+    match start {
+        Some(true) => frames.push(Frame::Mapping {
+            path,
+            key: None,
+            is_key,
+        }),
+        Some(false) => frames.push(Frame::Sequence {
+            path,
+            next: 0,
+            is_key,
+        }),
+        None if is_key => {
+            let name = match event.type_ {
+                YAML_SCALAR_EVENT => {
+                    let scalar = &event.data.scalar;
+                    let bytes = std::slice::from_raw_parts(scalar.value, scalar.length as usize);
+                    Some(String::from_utf8_lossy(bytes).into_owned())
+                }
+                _ => None,
+            };
+            set_key(frames, name);
+        }
+        None => {}
+    }
+    false
+}
+
+// Returns `None` for nulls, which are dropped from the converted config.
+fn from_yaml_value(
+    path: &str,
+    yaml: &serde_yaml::Value,
+    anchors: &mut HashMap<String, String>,
+    report: &mut Report,
+) -> Result<Option<Value>, LibconfigError> {
+    if let Some(name) = anchors.remove(path) {
+        report.push(path, LossKind::Anchor(name));
+    }
+    Ok(Some(match yaml {
+        serde_yaml::Value::Null => {
+            report.push(path, LossKind::Null);
+            return Ok(None);
+        }
+        serde_yaml::Value::Bool(val) => Value::Bool(*val),
+        serde_yaml::Value::Number(num) => match (num.as_i64(), num.as_f64()) {
+            (Some(val), _) => match i32::try_from(val) {
+                Ok(val) => Value::Int(val),
+                Err(_) => Value::Int64(val),
+            },
+            (None, _) if num.is_u64() => return Err(LibconfigError::OutOfRange(path.to_string())),
+            (None, Some(val)) => Value::Float(val),
+            (None, None) => return Err(LibconfigError::TypeMismatch(path.to_string())),
+        },
+        serde_yaml::Value::String(val) => Value::String(val.clone()),
+        serde_yaml::Value::Sequence(elems) => {
+            let mut values = Vec::new();
+            for (idx, elem) in elems.iter().enumerate() {
+                let path = append(path, &format!("[{}]", idx));
+                values.extend(from_yaml_value(&path, elem, anchors, report)?);
+            }
+            Value::array_or_list(values)
+        }
+        serde_yaml::Value::Mapping(members) => {
+            let mut children = Vec::new();
+            for (key, member) in members {
+                let name = match key.as_str() {
+                    Some(name) => name.to_string(),
+                    None => return Err(LibconfigError::TypeMismatch(path.to_string())),
+                };
+                let path = append(path, &name);
+                if let Some(value) = from_yaml_value(&path, member, anchors, report)? {
+                    children.push((name, value));
+                }
+            }
+            Value::Group(children)
+        }
+        serde_yaml::Value::Tagged(tagged) => {
+            report.push(path, LossKind::Tag(tagged.tag.to_string()));
+            return from_yaml_value(path, &tagged.value, anchors, report);
+        }
+    }))
+}
+
+fn to_yaml_value(value: &Value) -> serde_yaml::Value {
+    match value {
+        Value::Bool(val) => serde_yaml::Value::Bool(*val),
+        Value::Int(val) => serde_yaml::Value::Number(Number::from(*val)),
+        Value::Int64(val) => serde_yaml::Value::Number(Number::from(*val)),
+        Value::Float(val) => serde_yaml::Value::Number(Number::from(*val)),
+        Value::String(val) => serde_yaml::Value::String(val.clone()),
+        Value::Group(children) => serde_yaml::Value::Mapping(
+            children
+                .iter()
+                .map(|(name, child)| {
+                    (
+                        serde_yaml::Value::String(name.clone()),
+                        to_yaml_value(child),
+                    )
+                })
+                .collect::<Mapping>(),
+        ),
+        Value::Array(elems) | Value::List(elems) => {
+            serde_yaml::Value::Sequence(elems.iter().map(to_yaml_value).collect())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::convert::Loss;
+
+    #[test]
+    fn ok_on_yaml_round_trip() {
+        let input = "name: svc\nports:\n- 80\n- 443\ndb:\n  host: localhost\n  retries: [1, two]\n";
+        let (mut cfg, report) = from_yaml(input).unwrap();
+        assert!(report.is_lossless());
+        assert_eq!(cfg.lookup_i32("ports.[1]"), Some(443));
+        assert_eq!(
+            cfg.lookup("db.retries").unwrap().get_type(),
+            crate::Type::TypeList
+        );
+        let (output, report) = to_yaml(&cfg).unwrap();
+        assert!(report.is_lossless());
+        assert_eq!(
+            serde_yaml::from_str::<serde_yaml::Value>(&output).unwrap(),
+            serde_yaml::from_str::<serde_yaml::Value>(input).unwrap()
+        );
+    }
+
+    #[test]
+    fn report_on_yaml_null_anchor_and_tag() {
+        let input = "base: &defaults\n  port: 80\nsvc: *defaults\nunset: ~\nid: !custom 5\n";
+        let (cfg, report) = from_yaml(input).unwrap();
+        assert_eq!(
+            report.losses,
+            vec![
+                Loss {
+                    path: String::from("base"),
+                    kind: LossKind::Anchor(String::from("defaults"))
+                },
+                Loss {
+                    path: String::from("unset"),
+                    kind: LossKind::Null
+                },
+                Loss {
+                    path: String::from("id"),
+                    kind: LossKind::Tag(String::from("!custom"))
+                },
+            ]
+        );
+        assert_eq!(cfg.lookup_i32("svc.port"), Some(80));
+        assert!(!cfg.exists("unset"));
+        assert_eq!(cfg.lookup_i32("id"), Some(5));
+    }
+
+    #[test]
+    fn report_on_tagged_anchors_in_sequences() {
+        let input =
+            "ports:\n- &first !custom 80\n- *first\nalso: !other &second [1]\nref: *second\n";
+        let (cfg, report) = from_yaml(input).unwrap();
+        let loss = |path: &str, kind| Loss {
+            path: String::from(path),
+            kind,
+        };
+        assert_eq!(
+            report.losses,
+            vec![
+                loss("ports.[0]", LossKind::Anchor(String::from("first"))),
+                loss("ports.[0]", LossKind::Tag(String::from("!custom"))),
+                loss("ports.[1]", LossKind::Tag(String::from("!custom"))),
+                loss("also", LossKind::Anchor(String::from("second"))),
+                loss("also", LossKind::Tag(String::from("!other"))),
+                loss("ref", LossKind::Tag(String::from("!other"))),
+            ]
+        );
+        assert_eq!(cfg.lookup_i32("ports.[1]"), Some(80));
+        assert_eq!(cfg.lookup_i32("ref.[0]"), Some(1));
+    }
+
+    #[test]
+    fn ok_on_ampersands_in_scalars() {
+        let input = "script: |\n  make && make install\nmsg: foo &bar\nquoted: \"&x\"\n\
+                     single: 'a &b'\nflow: [c &d]\nreal: &real [1]\n";
+        let (cfg, report) = from_yaml(input).unwrap();
+        assert_eq!(
+            report.losses,
+            vec![Loss {
+                path: String::from("real"),
+                kind: LossKind::Anchor(String::from("real"))
+            }]
+        );
+        assert_eq!(
+            cfg.lookup_string("script"),
+            Some(String::from("make && make install\n"))
+        );
+        assert_eq!(cfg.lookup_string("msg"), Some(String::from("foo &bar")));
+        assert_eq!(cfg.lookup_string("quoted"), Some(String::from("&x")));
+        assert_eq!(cfg.lookup_string("single"), Some(String::from("a &b")));
+        assert_eq!(cfg.lookup_string("flow.[0]"), Some(String::from("c &d")));
+    }
+
+    #[test]
+    fn report_on_anchors_in_keys_and_nested_aliases() {
+        let input = "outer: &o\n  inner: &i 1\n? &k key\n: 2\ncopy: *o\n";
+        let (cfg, report) = from_yaml(input).unwrap();
+        let loss = |path: &str, name: &str| Loss {
+            path: String::from(path),
+            kind: LossKind::Anchor(String::from(name)),
+        };
+        assert_eq!(
+            report.losses,
+            vec![loss("outer", "o"), loss("outer.inner", "i")]
+        );
+        assert_eq!(cfg.lookup_i32("key"), Some(2));
+        assert_eq!(cfg.lookup_i32("copy.inner"), Some(1));
+    }
+}
@@ -0,0 +1,291 @@
+use crate::path::append;
+use crate::{Config, LibconfigError, Value};
+use std::cmp::Ordering;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Change {
+    Added {
+        path: String,
+        new: Value,
+    },
+    Removed {
+        path: String,
+        old: Value,
+    },
+    TypeChanged {
+        path: String,
+        old: Value,
+        new: Value,
+    },
+    ValueChanged {
+        path: String,
+        old: Value,
+        new: Value,
+    },
+}
+
+impl Change {
+    pub fn path(&self) -> &str {
+        match self {
+            Change::Added { path, .. }
+            | Change::Removed { path, .. }
+            | Change::TypeChanged { path, .. }
+            | Change::ValueChanged { path, .. } => path,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct DiffOptions {
+    /// Treat `Int` and `Int64` settings holding the same number as identical.
+    pub ignore_int_width: bool,
+    /// Maximum absolute difference at which two floats are still considered equal.
+    pub float_tolerance: f64,
+}
+
+/// The changes that turn `old` into `new`, groups matched by name and arrays
+/// and lists by index.
+///
+/// This returns a `Result` rather than a bare `Vec<Change>` because both
+/// trees are read through `Config::to_value`, which fails when a setting
+/// cannot be read. An empty list is then never mistaken for "no changes".
+pub fn diff(old: &Config, new: &Config) -> Result<Vec<Change>, LibconfigError> {
+    diff_with(old, new, &DiffOptions::default())
+}
+
+/// Like `diff`, with int widths and float precision compared as `options`
+/// say.
+pub fn diff_with(
+    old: &Config,
+    new: &Config,
+    options: &DiffOptions,
+) -> Result<Vec<Change>, LibconfigError> {
+    let mut changes = Vec::new();
+    diff_values(
+        "",
+        &old.to_value()?,
+        &new.to_value()?,
+        options,
+        &mut changes,
+    );
+    Ok(changes)
+}
+
+fn as_int(value: &Value) -> Option<i64> {
+    match value {
+        Value::Int(v) => Some(i64::from(*v)),
+        Value::Int64(v) => Some(*v),
+        _ => None,
+    }
+}
+
+// Infinities only equal themselves and NaN only equals NaN, whatever the
+// tolerance. A difference that cannot be compared, such as with a NaN
+// tolerance, counts as a change.
+fn floats_differ(a: f64, b: f64, tolerance: f64) -> bool {
+    match a.is_finite() && b.is_finite() {
+        true => !matches!(
+            (a - b).abs().partial_cmp(&tolerance),
+            Some(Ordering::Less | Ordering::Equal)
+        ),
+        false => !(a == b || (a.is_nan() && b.is_nan())),
+    }
+}
+
+fn diff_values(
+    path: &str,
+    old: &Value,
+    new: &Value,
+    options: &DiffOptions,
+    changes: &mut Vec<Change>,
+) {
+    let changed = |changes: &mut Vec<Change>| {
+        changes.push(Change::ValueChanged {
+            path: path.to_string(),
+            old: old.clone(),
+            new: new.clone(),
+        })
+    };
+
+    match (old, new) {
+        (Value::Group(old_children), Value::Group(new_children)) => {
+            for (name, old_child) in old_children {
+                match new.get(name) {
+                    Some(new_child) => {
+                        diff_values(&append(path, name), old_child, new_child, options, changes)
+                    }
+                    None => changes.push(Change::Removed {
+                        path: append(path, name),
+                        old: old_child.clone(),
+                    }),
+                }
+            }
+            for (name, new_child) in new_children {
+                if old.get(name).is_none() {
+                    changes.push(Change::Added {
+                        path: append(path, name),
+                        new: new_child.clone(),
+                    });
+                }
+            }
+        }
+        (Value::Array(old_elems), Value::Array(new_elems))
+        | (Value::List(old_elems), Value::List(new_elems)) => {
+            for idx in 0..old_elems.len().max(new_elems.len()) {
+                let elem_path = append(path, &format!("[{}]", idx));
+                match (old_elems.get(idx), new_elems.get(idx)) {
+                    (Some(old_elem), Some(new_elem)) => {
+                        diff_values(&elem_path, old_elem, new_elem, options, changes)
+                    }
+                    (Some(old_elem), None) => changes.push(Change::Removed {
+                        path: elem_path,
+                        old: old_elem.clone(),
+                    }),
+                    (None, Some(new_elem)) => changes.push(Change::Added {
+                        path: elem_path,
+                        new: new_elem.clone(),
+                    }),
+                    (None, None) => unreachable!(),
+                }
+            }
+        }
+        (Value::Float(a), Value::Float(b)) => {
+            if floats_differ(*a, *b, options.float_tolerance) {
+                changed(changes);
+            }
+        }
+        _ if options.ignore_int_width && as_int(old).is_some() && as_int(new).is_some() => {
+            if as_int(old) != as_int(new) {
+                changed(changes);
+            }
+        }
+        _ if old.get_type() != new.get_type() => changes.push(Change::TypeChanged {
+            path: path.to_string(),
+            old: old.clone(),
+            new: new.clone(),
+        }),
+        _ => {
+            if old != new {
+                changed(changes);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::config;
+
+    #[test]
+    fn empty_for_identical_configs() {
+        let mut a = Config::new();
+        let mut b = Config::new();
+        assert_eq!(a.read_file("../input/test.cfg"), Ok(()));
+        assert_eq!(b.read_file("../input/test.cfg"), Ok(()));
+        assert!(diff(&a, &b).unwrap().is_empty());
+    }
+
+    #[test]
+    fn ok_on_added_and_removed() {
+        let a = config("a = 1; grp = { x = 1; };");
+        let b = config("grp = { x = 1; y = \"new\"; };");
+        assert_eq!(
+            diff(&a, &b).unwrap(),
+            vec![
+                Change::Removed {
+                    path: String::from("a"),
+                    old: Value::Int(1)
+                },
+                Change::Added {
+                    path: String::from("grp.y"),
+                    new: Value::String(String::from("new"))
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn ok_on_changed_elements_by_index() {
+        let a = config("arr = [1, 2, 3]; list = (1, \"x\");");
+        let b = config("arr = [1, 5]; list = (1, 2);");
+        assert_eq!(
+            diff(&a, &b).unwrap(),
+            vec![
+                Change::ValueChanged {
+                    path: String::from("arr.[1]"),
+                    old: Value::Int(2),
+                    new: Value::Int(5)
+                },
+                Change::Removed {
+                    path: String::from("arr.[2]"),
+                    old: Value::Int(3)
+                },
+                Change::TypeChanged {
+                    path: String::from("list.[1]"),
+                    old: Value::String(String::from("x")),
+                    new: Value::Int(2)
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn int_width_respects_options() {
+        let a = config("val = 5;");
+        let b = config("val = 5L;");
+        assert_eq!(diff(&a, &b).unwrap().len(), 1);
+        assert!(matches!(
+            diff(&a, &b).unwrap()[0],
+            Change::TypeChanged { .. }
+        ));
+        let options = DiffOptions {
+            ignore_int_width: true,
+            ..Default::default()
+        };
+        assert!(diff_with(&a, &b, &options).unwrap().is_empty());
+    }
+
+    #[test]
+    fn float_tolerance_respects_options() {
+        let a = config("val = 1.0;");
+        let b = config("val = 1.001;");
+        assert_eq!(diff(&a, &b).unwrap()[0].path(), "val");
+        let options = DiffOptions {
+            float_tolerance: 0.01,
+            ..Default::default()
+        };
+        assert!(diff_with(&a, &b, &options).unwrap().is_empty());
+    }
+
+    #[test]
+    fn non_finite_floats_compare_by_value() {
+        let values = |val: f64| {
+            let cfg = config("val = 0.0;");
+            cfg.get_root().lookup("val").unwrap().set_f64(val).unwrap();
+            cfg
+        };
+        let options = DiffOptions {
+            float_tolerance: f64::INFINITY,
+            ..Default::default()
+        };
+        for (a, b, differ) in [
+            (f64::NAN, 1.0, true),
+            (f64::NAN, f64::NAN, false),
+            (f64::INFINITY, 1e308, true),
+            (f64::INFINITY, f64::NEG_INFINITY, true),
+            (f64::INFINITY, f64::INFINITY, false),
+        ] {
+            assert_eq!(
+                !diff_with(&values(a), &values(b), &options)
+                    .unwrap()
+                    .is_empty(),
+                differ,
+                "{} vs {}",
+                a,
+                b
+            );
+        }
+        assert_eq!(diff(&values(f64::NAN), &values(0.0)).unwrap().len(), 1);
+    }
+}
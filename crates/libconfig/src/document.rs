@@ -0,0 +1,655 @@
+use crate::atomic;
+use crate::path::{self, Component};
+use crate::{Config, LibconfigError, Value};
+use std::fs;
+use std::ops::Range;
+use std::path::Path;
+
+// A setting or an array/list element in the syntax tree. All offsets are byte
+// positions in the editor's text.
+#[derive(Debug, Clone)]
+struct Node {
+    name: Option<String>,
+    // The whole setting, from the start of the name up to and including a
+    // terminating `;` or `,`.
+    span: Range<usize>,
+    // End of the name; the assignment operator sits between here and `value`.
+    name_end: usize,
+    value: Range<usize>,
+    kind: Kind,
+}
+
+#[derive(Debug, Clone)]
+enum Kind {
+    Scalar,
+    Group(Vec<Node>),
+    Array(Vec<Node>),
+    List(Vec<Node>),
+}
+
+impl Node {
+    fn children(&self) -> &[Node] {
+        match &self.kind {
+            Kind::Scalar => &[],
+            Kind::Group(children) | Kind::Array(children) | Kind::List(children) => children,
+        }
+    }
+}
+
+/// Edits a config file in place of its text, so that comments, blank lines
+/// and `@include` directives survive.
+///
+/// Every edit only rewrites the spans it touches and is checked by parsing
+/// the result with libconfig; edits that libconfig rejects leave the document
+/// unchanged.
+#[derive(Debug, Clone)]
+pub struct DocumentEditor {
+    text: String,
+    root: Node,
+    include_dir: Option<String>,
+}
+
+impl DocumentEditor {
+    pub fn parse(text: &str) -> Result<Self, LibconfigError> {
+        Ok(DocumentEditor {
+            root: parse(text)?,
+            text: text.to_string(),
+            include_dir: None,
+        })
+    }
+
+    pub fn open(path: &str) -> Result<Self, LibconfigError> {
+        let text = fs::read_to_string(path)
+            .map_err(|err| LibconfigError::Io(format!("{}: {}", path, err)))?;
+        DocumentEditor::parse(&text).map_err(|err| match err {
+            LibconfigError::Syntax { line, message, .. } => LibconfigError::Syntax {
+                file: path.to_string(),
+                line,
+                message,
+            },
+            err => err,
+        })
+    }
+
+    pub fn set_include_path(&mut self, path: &str) {
+        self.include_dir = Some(path.to_string());
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.text
+    }
+
+    /// Replaces the file at `path` atomically, like `Config::write_file_atomic`.
+    pub fn write_file(&self, path: &str) -> Result<(), LibconfigError> {
+        atomic::replace_file(Path::new(path), &self.text, false)
+    }
+
+    pub fn to_config(&self) -> Result<Config, LibconfigError> {
+        self.check(&self.text)
+    }
+
+    /// Replaces the value of an existing setting or element.
+    ///
+    /// Hex integers stay hex.
+    pub fn set(&mut self, path: &str, value: &Value) -> Result<(), LibconfigError> {
+        let components = path::parse(path)?;
+        if components.is_empty() {
+            return Err(LibconfigError::Invalid);
+        }
+        let node = resolve(&self.root, &components)?;
+        let rendered = render(&self.text[node.value.clone()], value);
+        self.splice(node.value.clone(), &rendered)
+    }
+
+    /// Adds a setting to a group, or inserts an element into an array or list
+    /// when the last path component is an index.
+    pub fn add(&mut self, path: &str, value: &Value) -> Result<(), LibconfigError> {
+        let mut components = path::parse(path)?;
+        let last = components.pop().ok_or(LibconfigError::Invalid)?;
+        let parent = resolve(&self.root, &components)?;
+        let (range, text) = match (&parent.kind, last) {
+            (Kind::Group(children), Component::Name(name)) => {
+                if children
+                    .iter()
+                    .any(|c| c.name.as_deref() == Some(name.as_str()))
+                {
+                    return Err(LibconfigError::Invalid);
+                }
+                group_insertion(&self.text, parent, components.is_empty(), &name, value)
+            }
+            (Kind::Array(elems) | Kind::List(elems), Component::Index(idx)) => {
+                let rendered = value.to_string();
+                let (at, text) = match (elems.get(idx), elems.last()) {
+                    (Some(elem), _) => (elem.span.start, format!("{}, ", rendered)),
+                    (None, Some(last)) if idx == elems.len() => {
+                        (last.span.end, format!(", {}", rendered))
+                    }
+                    (None, None) if idx == 0 => (parent.value.start + 1, rendered),
+                    _ => return Err(LibconfigError::OutOfRange(path.to_string())),
+                };
+                (at..at, text)
+            }
+            _ => return Err(LibconfigError::TypeMismatch(path::join(&components))),
+        };
+        self.splice(range, &text)
+    }
+
+    /// Removes a setting or element. A setting on a line of its own is
+    /// removed together with its line and trailing comment.
+    pub fn remove(&mut self, path: &str) -> Result<(), LibconfigError> {
+        let mut components = path::parse(path)?;
+        let last = components.pop().ok_or(LibconfigError::Invalid)?;
+        let parent = resolve(&self.root, &components)?;
+        let children = parent.children();
+        let idx = match &last {
+            Component::Name(name) => children
+                .iter()
+                .position(|c| c.name.as_deref() == Some(name.as_str())),
+            Component::Index(idx) => Some(*idx).filter(|idx| *idx < children.len()),
+        }
+        .ok_or_else(|| LibconfigError::Missing(path.to_string()))?;
+        let range = match parent.kind {
+            Kind::Group(_) => line_range(&self.text, children[idx].span.clone()),
+            _ => element_range(children, idx),
+        };
+        self.splice(range, "")
+    }
+
+    fn check(&self, text: &str) -> Result<Config, LibconfigError> {
+        let mut cfg = Config::new();
+        if let Some(dir) = &self.include_dir {
+            cfg.set_include_path(dir);
+        }
+        cfg.read_str(text)?;
+        Ok(cfg)
+    }
+
+    fn splice(&mut self, range: Range<usize>, replacement: &str) -> Result<(), LibconfigError> {
+        let mut text = self.text.clone();
+        text.replace_range(range, replacement);
+        let root = parse(&text)?;
+        self.check(&text)?;
+        self.text = text;
+        self.root = root;
+        Ok(())
+    }
+}
+
+fn resolve<'a>(mut node: &'a Node, components: &[Component]) -> Result<&'a Node, LibconfigError> {
+    for (depth, component) in components.iter().enumerate() {
+        let children = node.children();
+        node = match component {
+            Component::Name(name) => children
+                .iter()
+                .find(|c| c.name.as_deref() == Some(name.as_str())),
+            Component::Index(idx) => children.get(*idx),
+        }
+        .ok_or_else(|| LibconfigError::Missing(path::join(&components[..=depth])))?;
+    }
+    Ok(node)
+}
+
+fn render(old: &str, value: &Value) -> String {
+    let hex = old.starts_with("0x") || old.starts_with("0X");
+    match value {
+        Value::Int(val) if hex && *val >= 0 => format!("0x{:X}", val),
+        Value::Int64(val) if hex && *val >= 0 => format!("0x{:X}L", val),
+        value => value.to_string(),
+    }
+}
+
+// Where and what to insert for a new setting in a group, copying the
+// indentation, assignment operator and terminator of the last sibling.
+fn group_insertion(
+    text: &str,
+    group: &Node,
+    root: bool,
+    name: &str,
+    value: &Value,
+) -> (Range<usize>, String) {
+    let Some(last) = group.children().last() else {
+        let line = format!("{} = {};", name, value);
+        if root {
+            let at = text.len();
+            return match text.is_empty() || text.ends_with('\n') {
+                true => (at..at, format!("{}\n", line)),
+                false => (at..at, format!("\n{}\n", line)),
+            };
+        }
+        let inner = group.value.start + 1..group.value.end - 1;
+        return match text[inner.clone()].trim().is_empty() {
+            true => (inner, format!(" {} ", line)),
+            false => (inner.end..inner.end, format!("{} ", line)),
+        };
+    };
+    let assign = match &text[last.name_end..last.value.start] {
+        assign if assign.contains(['#', '/']) => " = ",
+        assign => assign,
+    };
+    let terminator = match text[..last.span.end].chars().last() {
+        Some(';') => ";",
+        Some(',') => ",",
+        _ => "",
+    };
+    let line = format!("{}{}{}{}", name, assign, value, terminator);
+    let line_start = text[..last.span.start].rfind('\n').map_or(0, |pos| pos + 1);
+    let indent = &text[line_start..last.span.start];
+    let rest = &text[last.span.end..];
+    let eol = rest.find('\n');
+    let tail = rest[..eol.unwrap_or(rest.len())].trim();
+    let own_line = indent.trim().is_empty()
+        && (tail.is_empty() || tail.starts_with('#') || tail.starts_with("//"));
+    match (own_line, eol) {
+        (true, Some(eol)) => {
+            let at = last.span.end + eol + 1;
+            (at..at, format!("{}{}\n", indent, line))
+        }
+        (true, None) => (text.len()..text.len(), format!("\n{}{}", indent, line)),
+        (false, _) => (last.span.end..last.span.end, format!(" {}", line)),
+    }
+}
+
+// Extends the span of a setting to its whole line when nothing but
+// whitespace and a comment shares the line with it.
+fn line_range(text: &str, span: Range<usize>) -> Range<usize> {
+    let before = text[..span.start].trim_end_matches([' ', '\t']);
+    let rest = &text[span.end..];
+    let eol = rest.find('\n');
+    let tail = rest[..eol.unwrap_or(rest.len())].trim();
+    let own_line = (before.is_empty() || before.ends_with('\n'))
+        && (tail.is_empty() || tail.starts_with('#') || tail.starts_with("//"));
+    match (own_line, eol) {
+        (true, Some(eol)) => before.len()..span.end + eol + 1,
+        (true, None) => before.len()..text.len(),
+        (false, _) => {
+            let spaces = rest.len() - rest.trim_start_matches([' ', '\t']).len();
+            span.start..span.end + spaces
+        }
+    }
+}
+
+// The span of an element together with the comma separating it from its
+// neighbour.
+fn element_range(elems: &[Node], idx: usize) -> Range<usize> {
+    match (elems.get(idx + 1), idx.checked_sub(1)) {
+        (Some(next), _) => elems[idx].span.start..next.span.start,
+        (None, Some(prev)) => elems[prev].span.end..elems[idx].span.end,
+        (None, None) => elems[idx].span.clone(),
+    }
+}
+
+fn parse(text: &str) -> Result<Node, LibconfigError> {
+    let mut parser = Parser::new(text);
+    let children = parser.settings(None)?;
+    Ok(Node {
+        name: None,
+        span: 0..text.len(),
+        name_end: 0,
+        value: 0..text.len(),
+        kind: Kind::Group(children),
+    })
+}
+
+// The `@include` directives of a document as (line, path) pairs.
+pub(crate) fn includes(text: &str) -> Result<Vec<(u32, String)>, LibconfigError> {
+    let mut parser = Parser::new(text);
+    parser.settings(None)?;
+    Ok(parser
+        .includes
+        .into_iter()
+        .map(|(pos, path)| (line_at(text, pos), path))
+        .collect())
+}
+
+fn line_at(text: &str, pos: usize) -> u32 {
+    text[..pos].matches('\n').count() as u32 + 1
+}
+
+// Recovers the structure of a document. Scalars are only delimited, not
+// validated; libconfig checks them when the document is cross-checked.
+struct Parser<'a> {
+    text: &'a str,
+    pos: usize,
+    // Start and path of every `@include` directive seen so far.
+    includes: Vec<(usize, String)>,
+}
+
+impl<'a> Parser<'a> {
+    fn new(text: &'a str) -> Self {
+        Parser {
+            text,
+            pos: 0,
+            includes: Vec::new(),
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.text.as_bytes().get(self.pos).copied()
+    }
+
+    fn error(&self, message: &str) -> LibconfigError {
+        LibconfigError::Syntax {
+            file: String::new(),
+            line: line_at(self.text, self.pos),
+            message: message.to_string(),
+        }
+    }
+
+    // Skips whitespace, comments and `@include` directives.
+    fn skip_trivia(&mut self) -> Result<(), LibconfigError> {
+        loop {
+            let rest = &self.text[self.pos..];
+            let trimmed = rest.trim_start();
+            self.pos += rest.len() - trimmed.len();
+            if trimmed.starts_with('#') || trimmed.starts_with("//") {
+                self.pos += trimmed.find('\n').unwrap_or(trimmed.len());
+            } else if let Some(comment) = trimmed.strip_prefix("/*") {
+                match comment.find("*/") {
+                    Some(end) => self.pos += end + 4,
+                    None => return Err(self.error("unterminated comment")),
+                }
+            } else if let Some(directive) = trimmed.strip_prefix("@include") {
+                let start = self.pos;
+                self.pos += trimmed.len() - directive.trim_start().len();
+                let path = self.pos + 1;
+                self.string()?;
+                // Trivia after a string is scanned twice when looking for
+                // string concatenation.
+                if self.includes.last().is_none_or(|(pos, _)| *pos < start) {
+                    let path = self.text[path..self.pos - 1].to_string();
+                    self.includes.push((start, path));
+                }
+            } else {
+                return Ok(());
+            }
+        }
+    }
+
+    fn settings(&mut self, close: Option<u8>) -> Result<Vec<Node>, LibconfigError> {
+        let mut nodes = Vec::new();
+        loop {
+            self.skip_trivia()?;
+            match self.peek() {
+                None if close.is_none() => return Ok(nodes),
+                None => return Err(self.error("unexpected end of file")),
+                c if c == close => {
+                    self.pos += 1;
+                    return Ok(nodes);
+                }
+                _ => nodes.push(self.setting()?),
+            }
+        }
+    }
+
+    fn setting(&mut self) -> Result<Node, LibconfigError> {
+        let start = self.pos;
+        let rest = &self.text[start..];
+        let len = rest
+            .find(|c: char| !(c.is_ascii_alphanumeric() || "-_*".contains(c)))
+            .unwrap_or(rest.len());
+        if !rest.starts_with(|c: char| c.is_ascii_alphabetic() || c == '*') {
+            return Err(self.error("expected setting name"));
+        }
+        let name = rest[..len].to_string();
+        self.pos += len;
+        let name_end = self.pos;
+        self.skip_trivia()?;
+        match self.peek() {
+            Some(b'=' | b':') => self.pos += 1,
+            _ => return Err(self.error("expected `=` or `:`")),
+        }
+        self.skip_trivia()?;
+        let (value, kind) = self.value()?;
+        let value_end = self.pos;
+        self.skip_trivia()?;
+        let end = match self.peek() {
+            Some(b';' | b',') => {
+                self.pos += 1;
+                self.pos
+            }
+            _ => value_end,
+        };
+        Ok(Node {
+            name: Some(name),
+            span: start..end,
+            name_end,
+            value,
+            kind,
+        })
+    }
+
+    fn value(&mut self) -> Result<(Range<usize>, Kind), LibconfigError> {
+        let start = self.pos;
+        let kind = match self.peek() {
+            Some(b'{') => {
+                self.pos += 1;
+                Kind::Group(self.settings(Some(b'}'))?)
+            }
+            Some(b'[') => Kind::Array(self.elements(b']')?),
+            Some(b'(') => Kind::List(self.elements(b')')?),
+            Some(b'"') => {
+                // Adjacent string literals are concatenated.
+                self.string()?;
+                loop {
+                    let end = self.pos;
+                    self.skip_trivia()?;
+                    if self.peek() != Some(b'"') {
+                        self.pos = end;
+                        break;
+                    }
+                    self.string()?;
+                }
+                Kind::Scalar
+            }
+            None => return Err(self.error("unexpected end of file")),
+            Some(_) => {
+                let rest = &self.text[self.pos..];
+                let len = rest
+                    .find(|c: char| c.is_whitespace() || ",;=:{}[]()#/\"".contains(c))
+                    .unwrap_or(rest.len());
+                if len == 0 {
+                    return Err(self.error("expected value"));
+                }
+                self.pos += len;
+                Kind::Scalar
+            }
+        };
+        Ok((start..self.pos, kind))
+    }
+
+    fn elements(&mut self, close: u8) -> Result<Vec<Node>, LibconfigError> {
+        self.pos += 1;
+        let mut nodes = Vec::new();
+        let mut separated = false;
+        loop {
+            self.skip_trivia()?;
+            match self.peek() {
+                Some(c) if c == close => {
+                    self.pos += 1;
+                    return Ok(nodes);
+                }
+                Some(b',') if !nodes.is_empty() && !separated => {
+                    self.pos += 1;
+                    separated = true;
+                }
+                _ if nodes.is_empty() || separated => {
+                    let (value, kind) = self.value()?;
+                    nodes.push(Node {
+                        name: None,
+                        span: value.clone(),
+                        name_end: value.start,
+                        value,
+                        kind,
+                    });
+                    separated = false;
+                }
+                _ => return Err(self.error("expected `,`")),
+            }
+        }
+    }
+
+    fn string(&mut self) -> Result<(), LibconfigError> {
+        if self.peek() != Some(b'"') {
+            return Err(self.error("expected string"));
+        }
+        let mut escaped = false;
+        for (offset, c) in self.text[self.pos + 1..].char_indices() {
+            match c {
+                '\\' if !escaped => escaped = true,
+                '"' if !escaped => {
+                    self.pos += offset + 2;
+                    return Ok(());
+                }
+                _ => escaped = false,
+            }
+        }
+        Err(self.error("unterminated string"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DOCUMENT: &str = "\
+# Server settings
+name = \"demo\"; // trailing comment
+
+server = {
+  port = 8080;   # http
+  mask = 0xFF;
+  hosts = [\"a\", \"b\"];
+};
+
+/* block
+   comment */
+list = ( 1, \"two\" );
+";
+
+    #[test]
+    fn ok_on_untouched_document() {
+        let doc = DocumentEditor::parse(DOCUMENT).unwrap();
+        assert_eq!(doc.as_str(), DOCUMENT);
+        assert!(doc.to_config().is_ok());
+    }
+
+    #[test]
+    fn ok_on_set() {
+        let mut doc = DocumentEditor::parse(DOCUMENT).unwrap();
+        assert_eq!(doc.set("server.port", &Value::Int(80)), Ok(()));
+        assert_eq!(doc.set("server.mask", &Value::Int(16)), Ok(()));
+        assert_eq!(
+            doc.set("server.hosts.[1]", &Value::String(String::from("c"))),
+            Ok(())
+        );
+        assert_eq!(
+            doc.as_str(),
+            DOCUMENT
+                .replace("8080", "80")
+                .replace("0xFF", "0x10")
+                .replace("\"b\"", "\"c\"")
+        );
+        let cfg = doc.to_config().unwrap();
+        assert_eq!(cfg.lookup_i32("server.port"), Some(80));
+        assert_eq!(cfg.lookup_i32("server.mask"), Some(16));
+        assert_eq!(
+            cfg.lookup_string("server.hosts.[1]"),
+            Some(String::from("c"))
+        );
+    }
+
+    #[test]
+    fn ok_on_add() {
+        let mut doc = DocumentEditor::parse(DOCUMENT).unwrap();
+        assert_eq!(doc.add("server.timeout", &Value::Float(1.5)), Ok(()));
+        assert_eq!(doc.add("debug", &Value::Bool(true)), Ok(()));
+        assert_eq!(doc.add("list.[1]", &Value::Int64(7)), Ok(()));
+        assert_eq!(
+            doc.as_str(),
+            DOCUMENT
+                .replace(
+                    "hosts = [\"a\", \"b\"];\n",
+                    "hosts = [\"a\", \"b\"];\n  timeout = 1.5;\n"
+                )
+                .replace("1, \"two\"", "1, 7L, \"two\"")
+                + "debug = true;\n"
+        );
+        let cfg = doc.to_config().unwrap();
+        assert_eq!(cfg.lookup_f64("server.timeout"), Some(1.5));
+        assert_eq!(cfg.lookup_bool("debug"), Some(true));
+        assert_eq!(cfg.lookup_i64("list.[1]"), Some(7));
+    }
+
+    #[test]
+    fn ok_on_add_to_empty_group() {
+        let mut doc = DocumentEditor::parse("grp = {};\n").unwrap();
+        assert_eq!(doc.add("grp.a", &Value::Int(1)), Ok(()));
+        assert_eq!(doc.as_str(), "grp = { a = 1; };\n");
+    }
+
+    #[test]
+    fn ok_on_remove() {
+        let mut doc = DocumentEditor::parse(DOCUMENT).unwrap();
+        assert_eq!(doc.remove("server.port"), Ok(()));
+        assert_eq!(doc.remove("list.[0]"), Ok(()));
+        assert_eq!(doc.remove("name"), Ok(()));
+        assert_eq!(
+            doc.as_str(),
+            DOCUMENT
+                .replace("  port = 8080;   # http\n", "")
+                .replace("( 1, ", "( ")
+                .replace("name = \"demo\"; // trailing comment\n", "")
+        );
+        let cfg = doc.to_config().unwrap();
+        assert!(!cfg.exists("server.port"));
+        assert!(!cfg.exists("name"));
+    }
+
+    #[test]
+    fn err_on_edit_rejected_by_libconfig() {
+        let mut doc = DocumentEditor::parse(DOCUMENT).unwrap();
+        assert!(matches!(
+            doc.set("server.hosts.[0]", &Value::Int(1)),
+            Err(LibconfigError::Syntax { .. })
+        ));
+        assert_eq!(doc.as_str(), DOCUMENT);
+    }
+
+    #[test]
+    fn err_on_missing_setting() {
+        let mut doc = DocumentEditor::parse(DOCUMENT).unwrap();
+        assert_eq!(
+            doc.set("server.missing", &Value::Int(1)),
+            Err(LibconfigError::Missing(String::from("server.missing")))
+        );
+        assert_eq!(
+            doc.add("server.port", &Value::Int(1)),
+            Err(LibconfigError::Invalid)
+        );
+    }
+
+    #[test]
+    fn ok_on_include_directives() {
+        let text =
+            "a = \"x\" /* c */;\n@include \"base.cfg\"\ngrp = {\n  @include \"sub/more.cfg\"\n};\n";
+        assert_eq!(
+            includes(text),
+            Ok(vec![
+                (2, String::from("base.cfg")),
+                (4, String::from("sub/more.cfg"))
+            ])
+        );
+    }
+
+    #[test]
+    fn err_on_unbalanced_document() {
+        assert_eq!(
+            DocumentEditor::parse("a = 1;\ngrp = {\n  b = 2;\n").err(),
+            Some(LibconfigError::Syntax {
+                file: String::new(),
+                line: 4,
+                message: String::from("unexpected end of file"),
+            })
+        );
+    }
+}
@@ -0,0 +1,199 @@
+use crate::{document, Config, LibconfigError};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// A file pulled in by an `@include` directive.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IncludedFile {
+    pub path: PathBuf,
+    /// The file holding the directive, `None` for a string read with
+    /// `read_str`.
+    pub included_by: Option<PathBuf>,
+    pub line: u32,
+    /// 1 for files included by the main file or string, 2 for files they
+    /// include, and so on.
+    pub depth: usize,
+}
+
+// Resolves an include the way libconfig does: relative to the include
+// directory when one is set, otherwise relative to the working directory.
+pub(crate) fn resolve(include: &str, include_dir: Option<&str>) -> PathBuf {
+    match include_dir {
+        Some(dir) if Path::new(include).is_relative() => Path::new(dir).join(include),
+        _ => PathBuf::from(include),
+    }
+}
+
+// Every file `text` includes, directly or not, in the order libconfig reads
+// them. Files that cannot be read or parsed are listed but not followed.
+pub(crate) fn scan(
+    text: &str,
+    origin: Option<&Path>,
+    include_dir: Option<&str>,
+) -> Vec<IncludedFile> {
+    let mut files = Vec::new();
+    let mut stack = origin.map(Path::to_path_buf).into_iter().collect();
+    scan_into(text, origin, include_dir, 1, &mut stack, &mut files);
+    files
+}
+
+fn scan_into(
+    text: &str,
+    origin: Option<&Path>,
+    include_dir: Option<&str>,
+    depth: usize,
+    stack: &mut Vec<PathBuf>,
+    files: &mut Vec<IncludedFile>,
+) {
+    for (line, include) in document::includes(text).unwrap_or_default() {
+        let path = resolve(&include, include_dir);
+        files.push(IncludedFile {
+            path: path.clone(),
+            included_by: origin.map(Path::to_path_buf),
+            line,
+            depth,
+        });
+        // libconfig rejects recursive includes itself.
+        if stack.contains(&path) {
+            continue;
+        }
+        if let Ok(contents) = fs::read(&path) {
+            let text = String::from_utf8_lossy(&contents);
+            stack.push(path.clone());
+            scan_into(&text, Some(&path), include_dir, depth + 1, stack, files);
+            stack.pop();
+        }
+    }
+}
+
+impl Config {
+    /// Every file `@include`d by the last `read_file` or `read_str`.
+    pub fn included_files(&self) -> Vec<IncludedFile> {
+        self.included.clone()
+    }
+
+    /// The file last read with `read_file` followed by the files it
+    /// includes, without duplicates.
+    pub fn dependencies(&self) -> Vec<PathBuf> {
+        let mut files: Vec<PathBuf> = self.file.iter().cloned().collect();
+        for include in &self.included {
+            if !files.contains(&include.path) {
+                files.push(include.path.clone());
+            }
+        }
+        files
+    }
+
+    /// Writes a Makefile and ninja compatible depfile to `out`, declaring
+    /// that `target` depends on `dependencies`.
+    pub fn write_depfile(&self, target: &str, out: &str) -> Result<(), LibconfigError> {
+        let mut text = escape(Path::new(target));
+        text.push(':');
+        for file in self.dependencies() {
+            text.push_str(" \\\n  ");
+            text.push_str(&escape(&file));
+        }
+        text.push('\n');
+        fs::write(out, text).map_err(|err| LibconfigError::Io(format!("{}: {}", out, err)))
+    }
+
+    /// Tells cargo to rerun the build script when the config or any file it
+    /// includes changes.
+    pub fn rerun_if_changed(&self) {
+        for file in self.dependencies() {
+            println!("cargo:rerun-if-changed={}", file.display());
+        }
+    }
+}
+
+fn escape(path: &Path) -> String {
+    let mut escaped = String::new();
+    for c in path.display().to_string().chars() {
+        match c {
+            ' ' | '#' | '\\' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            '$' => escaped.push_str("$$"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::scratch_dir;
+
+    #[test]
+    fn ok_on_scan_nested_includes() {
+        let dir = scratch_dir("includes-scan");
+        fs::write(
+            dir.join("a.cfg"),
+            "x = 1;\n@include \"b.cfg\"\n@include \"a.cfg\"\n",
+        )
+        .unwrap();
+        fs::write(dir.join("b.cfg"), "y = 2;\n").unwrap();
+        let include_dir = dir.to_str().unwrap();
+        let files = scan("@include \"a.cfg\"\n", None, Some(include_dir));
+        assert_eq!(
+            files,
+            [
+                IncludedFile {
+                    path: dir.join("a.cfg"),
+                    included_by: None,
+                    line: 1,
+                    depth: 1,
+                },
+                IncludedFile {
+                    path: dir.join("b.cfg"),
+                    included_by: Some(dir.join("a.cfg")),
+                    line: 2,
+                    depth: 2,
+                },
+                IncludedFile {
+                    path: dir.join("a.cfg"),
+                    included_by: Some(dir.join("a.cfg")),
+                    line: 3,
+                    depth: 2,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn ok_on_escape() {
+        assert_eq!(escape(Path::new("a b/#$x")), "a\\ b/\\#$$x");
+    }
+
+    #[test]
+    fn ok_on_read_file_dependencies() {
+        let dir = scratch_dir("includes-depfile");
+        fs::write(
+            dir.join("main.cfg"),
+            "@include \"port.cfg\"\nname = \"a\";\n",
+        )
+        .unwrap();
+        fs::write(dir.join("port.cfg"), "port = 80;\n").unwrap();
+        let mut cfg = Config::new();
+        cfg.set_include_path(dir.to_str().unwrap());
+        let main = dir.join("main.cfg");
+        assert_eq!(cfg.read_file(main.to_str().unwrap()), Ok(()));
+        let included = cfg.included_files();
+        assert_eq!(included.len(), 1);
+        assert_eq!(included[0].path, dir.join("port.cfg"));
+        assert_eq!(included[0].included_by, Some(main.clone()));
+        assert_eq!(cfg.dependencies(), [main.clone(), dir.join("port.cfg")]);
+        let depfile = dir.join("app.d");
+        assert_eq!(cfg.write_depfile("app", depfile.to_str().unwrap()), Ok(()));
+        assert_eq!(
+            fs::read_to_string(depfile).unwrap(),
+            format!(
+                "app: \\\n  {} \\\n  {}\n",
+                main.display(),
+                dir.join("port.cfg").display()
+            )
+        );
+    }
+}
@@ -0,0 +1,201 @@
+use crate::path::append;
+use crate::{Config, LibconfigError, Value};
+use serde_json::{Map, Number};
+
+// Largest integer a JSON number can carry without losing precision in
+// IEEE 754 based parsers.
+const MAX_SAFE_INTEGER: u64 = (1 << 53) - 1;
+
+#[derive(Debug, Clone, Default)]
+pub struct JsonOptions {
+    /// Export 64-bit integers outside JSON's safe-integer range as strings.
+    pub int64_as_string: bool,
+}
+
+impl Value {
+    /// Fails with `OutOfRange` on NaN and infinite floats, which JSON has no
+    /// numbers for.
+    pub fn to_json(&self, options: &JsonOptions) -> Result<serde_json::Value, LibconfigError> {
+        to_json_at("", self, options)
+    }
+
+    pub fn from_json(json: &serde_json::Value) -> Result<Value, LibconfigError> {
+        from_json_at("", json)
+    }
+}
+
+fn to_json_at(
+    path: &str,
+    value: &Value,
+    options: &JsonOptions,
+) -> Result<serde_json::Value, LibconfigError> {
+    Ok(match value {
+        Value::Bool(val) => serde_json::Value::Bool(*val),
+        Value::Int(val) => serde_json::Value::from(*val),
+        Value::Int64(val) if options.int64_as_string && val.unsigned_abs() > MAX_SAFE_INTEGER => {
+            serde_json::Value::String(val.to_string())
+        }
+        Value::Int64(val) => serde_json::Value::from(*val),
+        Value::Float(val) => match Number::from_f64(*val) {
+            Some(num) => serde_json::Value::Number(num),
+            None => return Err(LibconfigError::OutOfRange(path.to_string())),
+        },
+        Value::String(val) => serde_json::Value::String(val.clone()),
+        Value::Group(children) => serde_json::Value::Object(
+            children
+                .iter()
+                .map(|(name, child)| {
+                    Ok((
+                        name.clone(),
+                        to_json_at(&append(path, name), child, options)?,
+                    ))
+                })
+                .collect::<Result<Map<_, _>, LibconfigError>>()?,
+        ),
+        Value::Array(elems) | Value::List(elems) => serde_json::Value::Array(
+            elems
+                .iter()
+                .enumerate()
+                .map(|(idx, elem)| to_json_at(&append(path, &format!("[{}]", idx)), elem, options))
+                .collect::<Result<_, _>>()?,
+        ),
+    })
+}
+
+fn from_json_at(path: &str, json: &serde_json::Value) -> Result<Value, LibconfigError> {
+    Ok(match json {
+        serde_json::Value::Null => return Err(LibconfigError::TypeMismatch(path.to_string())),
+        serde_json::Value::Bool(val) => Value::Bool(*val),
+        serde_json::Value::Number(num) => match (num.as_i64(), num.as_f64()) {
+            (Some(val), _) => match i32::try_from(val) {
+                Ok(val) => Value::Int(val),
+                Err(_) => Value::Int64(val),
+            },
+            (None, _) if num.is_u64() => return Err(LibconfigError::OutOfRange(path.to_string())),
+            (None, Some(val)) => Value::Float(val),
+            (None, None) => return Err(LibconfigError::TypeMismatch(path.to_string())),
+        },
+        serde_json::Value::String(val) => Value::String(val.clone()),
+        serde_json::Value::Object(members) => Value::Group(
+            members
+                .iter()
+                .map(|(name, member)| {
+                    Ok((name.clone(), from_json_at(&append(path, name), member)?))
+                })
+                .collect::<Result<_, LibconfigError>>()?,
+        ),
+        serde_json::Value::Array(elems) => {
+            let elems = elems
+                .iter()
+                .enumerate()
+                .map(|(idx, elem)| from_json_at(&append(path, &format!("[{}]", idx)), elem))
+                .collect::<Result<Vec<_>, _>>()?;
+            Value::array_or_list(elems)
+        }
+    })
+}
+
+impl Config {
+    pub fn to_json(&self) -> Result<serde_json::Value, LibconfigError> {
+        self.to_json_with(&JsonOptions::default())
+    }
+
+    pub fn to_json_with(&self, options: &JsonOptions) -> Result<serde_json::Value, LibconfigError> {
+        self.to_redacted_value()?.to_json(options)
+    }
+
+    pub fn from_json(json: &serde_json::Value) -> Result<Config, LibconfigError> {
+        Config::from_value(&Value::from_json(json)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn ok_on_config_to_json() {
+        let mut cfg = Config::new();
+        assert_eq!(cfg.read_file("../input/test.cfg"), Ok(()));
+        let json = cfg.to_json().unwrap();
+        assert_eq!(json["name"], json!("Some Name"));
+        assert_eq!(json["another"], json!(-1));
+        assert_eq!(json["val_u64"], json!(0xFFFFFFFFFF_i64));
+        assert_eq!(json["arr"], json!([3, 5, 8]));
+        assert_eq!(json["outer"], json!({ "inner": 3 }));
+    }
+
+    #[test]
+    fn ok_on_unsafe_int64_as_string() {
+        let mut cfg = Config::new();
+        assert_eq!(
+            cfg.read_str("big = 9007199254740993L; small = 5L; min = -9223372036854775808L;"),
+            Ok(())
+        );
+        let options = JsonOptions {
+            int64_as_string: true,
+        };
+        assert_eq!(
+            cfg.to_json_with(&options).unwrap(),
+            json!({ "big": "9007199254740993", "small": 5, "min": "-9223372036854775808" })
+        );
+    }
+
+    #[test]
+    fn err_on_non_finite_float_export() {
+        let mut cfg = Config::new();
+        assert_eq!(
+            cfg.read_str("grp = { ok = 1.5; arr = [0.5, 0.5]; };"),
+            Ok(())
+        );
+        for val in [f64::NAN, f64::INFINITY, f64::NEG_INFINITY] {
+            cfg.lookup("grp.arr.[1]").unwrap().set_f64(val).unwrap();
+            assert_eq!(
+                cfg.to_json(),
+                Err(LibconfigError::OutOfRange(String::from("grp.arr.[1]")))
+            );
+        }
+    }
+
+    #[test]
+    fn ok_on_config_from_json() {
+        let mut cfg = Config::from_json(&json!({
+            "name": "x",
+            "ints": [1, 2, 5000000000_i64],
+            "mixed": [1, "two"],
+            "nested": [{ "a": 1.5 }],
+            "grp": { "flag": true }
+        }))
+        .unwrap();
+        assert_eq!(cfg.lookup_string("name"), Some(String::from("x")));
+        assert_eq!(
+            cfg.lookup("ints").unwrap().get_type(),
+            crate::Type::TypeArray
+        );
+        assert_eq!(cfg.lookup_i64("ints.[0]"), Some(1));
+        assert_eq!(
+            cfg.lookup("mixed").unwrap().get_type(),
+            crate::Type::TypeList
+        );
+        assert_eq!(
+            cfg.lookup("nested").unwrap().get_type(),
+            crate::Type::TypeList
+        );
+        assert_eq!(cfg.lookup_f64("nested.[0].a"), Some(1.5));
+        assert_eq!(cfg.lookup_bool("grp.flag"), Some(true));
+    }
+
+    #[test]
+    fn err_on_json_null() {
+        assert_eq!(
+            Config::from_json(&json!({ "grp": { "x": null } })).err(),
+            Some(LibconfigError::TypeMismatch(String::from("grp.x")))
+        );
+    }
+
+    #[test]
+    fn err_on_non_object_root() {
+        assert!(Config::from_json(&json!([1, 2])).is_err());
+    }
+}
@@ -1,39 +1,157 @@
-use cxx::{let_cxx_string, UniquePtr};
-use libconfig_sys::ffi::{
-    addSetting, getNextFromIter, getParentFromSetting, getPathFromSetting, getRootFromConfig,
-    getSettingIter, lookupSettingFromConfig, lookupSettingFromSetting, lookupValueI64FromConfig,
-    lookupValueI64FromSetting, removeSetting, removeSettingByIndex, setBool, setF32, setF64,
-    setI32, setI64, setString, tryBoolFromSetting, tryF32FromSetting, tryF64FromSetting,
-    tryI32FromSetting, tryI64FromSetting, tryStringFromSetting, Config_ctor,
+use crate::snapshot::Snapshot;
+use backend::{
+    addElement, addSetting, getElemFromSetting, getNextFromIter, getParentFromSetting,
+    getPathFromSetting, getRootFromConfig, getSettingIter, lookupSettingFromConfig,
+    lookupSettingFromSetting, lookupValueI64FromConfig, lookupValueI64FromSetting, removeSetting,
+    removeSettingByIndex, setBool, setF32, setF64, setI32, setI64, setString, tryBoolFromSetting,
+    tryF32FromSetting, tryF64FromSetting, tryI32FromSetting, tryI64FromSetting,
+    tryStringFromSetting, writeToString, Config_ctor,
 };
+use backend::{let_string, UniquePtr};
 use std::borrow::BorrowMut;
 use std::ffi::{CStr, CString};
+use std::fs;
 use std::marker::PhantomData;
+use std::path::{Path, PathBuf};
 use std::pin::Pin;
 use thiserror::Error;
 
-pub use libconfig_sys::ffi::Format;
-pub use libconfig_sys::ffi::Type;
+#[cfg(feature = "async")]
+mod async_support;
+mod atomic;
+mod backend;
+#[cfg(any(feature = "toml", feature = "yaml"))]
+pub mod convert;
+mod diff;
+mod document;
+mod includes;
+mod interpolate;
+#[cfg(feature = "json")]
+mod json;
+mod limits;
+mod patch;
+mod path;
+mod query;
+mod secrets;
+mod shared;
+mod snapshot;
+#[cfg(test)]
+mod test_util;
+mod traits;
+mod tree;
+mod value;
+mod version;
+mod watch;
+
+#[cfg(feature = "async")]
+pub use async_support::ConfigStream;
+pub use atomic::WriteOptions;
+pub use backend::{ConfigOption, Format, Type};
+pub use diff::{diff, diff_with, Change, DiffOptions};
+pub use document::DocumentEditor;
+pub use includes::IncludedFile;
+pub use interpolate::InterpolationOptions;
+#[cfg(feature = "json")]
+pub use json::JsonOptions;
+pub use limits::ParseLimits;
+pub use patch::{Patch, PatchOp};
+pub use secrets::{DirectoryProvider, SecretProvider, REDACTED};
+pub use shared::{FrozenConfig, SharedConfig};
+pub use traits::{FromConfig, ToConfig};
+pub use value::Value;
+pub use version::{capabilities, version, Capabilities};
+pub use watch::{Update, Validator, WatchOptions, WatchedConfig};
+
+#[cfg(feature = "derive")]
+pub use libconfig_derive::{FromConfig, ToConfig};
+#[cfg(feature = "macros")]
+pub use libconfig_macros::{include_config, include_config_value};
+
+#[doc(hidden)]
+pub mod __private {
+    pub use crate::traits::{child_path, read_field, read_field_or, write_field};
+}
 
-#[derive(Error, Debug, PartialEq)]
+#[derive(Error, Debug, Clone, PartialEq)]
 pub enum LibconfigError {
     #[error("invalid operation")]
     Invalid,
+    #[error("missing setting `{0}`")]
+    Missing(String),
+    #[error("type mismatch for setting `{0}`")]
+    TypeMismatch(String),
+    #[error("value of setting `{0}` out of range")]
+    OutOfRange(String),
+    #[error("test failed for setting `{0}`")]
+    TestFailed(String),
+    #[error("parse error: {0}")]
+    Parse(String),
+    #[error("parse error at {file}:{line}: {message}")]
+    Syntax {
+        file: String,
+        line: u32,
+        message: String,
+    },
+    #[error("i/o error: {0}")]
+    Io(String),
+    #[error("invalid query at column {column}: {message}")]
+    Query { column: usize, message: String },
+    #[error("file `{0}` changed since it was read")]
+    Conflict(String),
+    #[error("interpolation cycle: {}", .0.join(" -> "))]
+    Cycle(Vec<String>),
+    #[error("refusing to write sensitive setting `{0}`")]
+    Sensitive(String),
+    #[error("include `{0}` is not allowed")]
+    IncludeDenied(String),
+    #[error("include `{0}` is outside the allowed directories")]
+    IncludeOutsideRoots(String),
+    #[error("include `{0}` leaves its directory")]
+    PathTraversal(String),
+    #[error("include `{0}` is nested too deeply")]
+    IncludeTooDeep(String),
+    #[error("`{file}` is {size} bytes, more than the limit of {limit}")]
+    FileTooLarge { file: String, size: u64, limit: u64 },
+    #[error("settings are nested more than {0} levels deep")]
+    NestingTooDeep(usize),
+    #[error("more than {0} settings")]
+    TooManySettings(usize),
+}
+
+impl LibconfigError {
+    // The bridge reports parse errors as "Parse error at <file>:<line> - <message>".
+    fn from_exception(err: &backend::Exception) -> Self {
+        let Some(location) = err.what().strip_prefix("Parse error at ") else {
+            return LibconfigError::Invalid;
+        };
+        for (pos, _) in location.match_indices(" - ") {
+            if let Some((file, line)) = location[..pos].rsplit_once(':') {
+                if let Ok(line) = line.parse() {
+                    return LibconfigError::Syntax {
+                        file: file.to_string(),
+                        line,
+                        message: location[pos + 3..].to_string(),
+                    };
+                }
+            }
+        }
+        LibconfigError::Invalid
+    }
 }
 
 pub struct Setting<'a> {
-    inner: Pin<&'a mut libconfig_sys::ffi::Setting>,
+    inner: Pin<&'a mut backend::Setting>,
 }
 
 pub struct SettingIter<'a> {
-    inner: Option<UniquePtr<libconfig_sys::ffi::SettingIterator>>,
+    inner: Option<UniquePtr<backend::SettingIterator>>,
     items: usize,
     count: usize,
     _lifetime: PhantomData<&'a ()>,
 }
 
 impl<'a> Setting<'a> {
-    pub fn lookup(&'a mut self, path: &str) -> Result<Setting<'a>, LibconfigError> {
+    pub fn lookup(&mut self, path: &str) -> Result<Setting<'_>, LibconfigError> {
         let s = CString::new(path).expect("invalid file");
         unsafe {
             match lookupSettingFromSetting(self.inner.as_mut(), s.as_ptr()) {
@@ -43,7 +161,7 @@ impl<'a> Setting<'a> {
         }
     }
 
-    pub fn exists(&'a self, path: &str) -> bool {
+    pub fn exists(&self, path: &str) -> bool {
         let s = CString::new(path).expect("invalid file");
         unsafe { self.inner.as_ref().exists(s.as_ptr()) }
     }
@@ -105,7 +223,7 @@ impl<'a> Setting<'a> {
 
     pub fn lookup_string(&mut self, path: &str) -> Option<String> {
         let s = CString::new(path).expect("invalid settings");
-        let_cxx_string!(tmp = "");
+        let_string!(tmp = "");
         unsafe {
             match self.inner.as_ref().lookup_string(s.as_ptr(), tmp.as_mut()) {
                 true => Some(tmp.to_string()),
@@ -161,7 +279,7 @@ impl<'a> Setting<'a> {
 
     pub fn set_str(&mut self, val: &str) -> Result<(), LibconfigError> {
         unsafe {
-            let_cxx_string!(s = val);
+            let_string!(s = val);
             match setString(self.inner.as_mut(), &s) {
                 Ok(_) => Ok(()),
                 Err(_) => Err(LibconfigError::Invalid),
@@ -169,9 +287,86 @@ impl<'a> Setting<'a> {
         }
     }
 
-    pub fn remove(&'a mut self, path: &str) -> Result<(), LibconfigError> {
+    /// Sets the setting to `value`, changing its type if needed while keeping
+    /// its name, position in the parent and, for integers, its format.
+    ///
+    /// Replacing an aggregate re-creates its children, and changing the type
+    /// re-creates the setting and the siblings after it. `self` stays valid,
+    /// but other handles to the re-created settings dangle.
+    pub fn replace(&mut self, value: &Value) -> Result<(), LibconfigError> {
+        let setting_type = value.get_type();
+        if setting_type == self.get_type() && !self.is_aggregate() {
+            return snapshot::set_scalar(self, value);
+        }
+        // Rejects values libconfig cannot hold, such as mixed arrays, before
+        // anything is removed.
+        Config::from_value(&Value::Group(vec![(String::from("value"), value.clone())]))
+            .map_err(|_| LibconfigError::TypeMismatch(self.get_path()))?;
+        if setting_type == self.get_type() {
+            return Snapshot::from_value(None, value).restore_children(self);
+        }
+        let (Some(idx), false) = (self.get_index(), self.is_root()) else {
+            return Err(LibconfigError::TypeMismatch(self.get_path()));
+        };
+        let mut parent = self.detached_parent()?;
+        // Array elements all share one type, so only a sole element may change it.
+        if parent.is_array()
+            && (matches!(
+                setting_type,
+                Type::TypeGroup | Type::TypeArray | Type::TypeList
+            ) || parent.get_length()? > 1)
+        {
+            return Err(LibconfigError::TypeMismatch(self.get_path()));
+        }
+        let format = match setting_type {
+            Type::TypeInt | Type::TypeInt64 => self.get_format(),
+            _ => Format::FormatDefault,
+        };
+        let name = self.get_name().map(str::to_string);
+        let new = Snapshot::from_value(name, value).with_format(format);
+        parent.remove_idx(idx)?;
+        snapshot::insert_at(&mut parent, idx, &new)?;
+        let elem = parent.get_elem(idx)?;
+        self.inner = unsafe { Self::extend(elem.inner) };
+        Ok(())
+    }
+
+    /// Like `replace`, but keeps the setting's integer width when the value
+    /// fits, promoting `TypeInt` to `TypeInt64` only when it does not.
+    pub fn set_any(&mut self, value: &Value) -> Result<(), LibconfigError> {
+        match (self.get_type(), value) {
+            (Type::TypeInt64, Value::Int(val)) => self.replace(&Value::Int64(*val as i64)),
+            (Type::TypeInt, Value::Int64(val)) => match i32::try_from(*val) {
+                Ok(val) => self.replace(&Value::Int(val)),
+                Err(_) => self.replace(value),
+            },
+            _ => self.replace(value),
+        }
+    }
+
+    // Like `get_parent`, but the parent is not tied to a borrow of `self`.
+    pub(crate) fn detached_parent(&mut self) -> Result<Setting<'a>, LibconfigError> {
+        unsafe {
+            match getParentFromSetting(self.inner.as_mut()) {
+                Ok(parent) => Ok(Setting {
+                    inner: Self::extend(parent),
+                }),
+                Err(_) => Err(LibconfigError::Invalid),
+            }
+        }
+    }
+
+    // Detaches a setting reference from the temporary borrow it was obtained
+    // through; settings live as long as their config.
+    pub(crate) unsafe fn extend(
+        inner: Pin<&mut backend::Setting>,
+    ) -> Pin<&'a mut backend::Setting> {
+        Pin::new_unchecked(&mut *(inner.get_unchecked_mut() as *mut _))
+    }
+
+    pub fn remove(&mut self, path: &str) -> Result<(), LibconfigError> {
         unsafe {
-            let_cxx_string!(s = path);
+            let_string!(s = path);
             match removeSetting(self.inner.as_mut(), &s) {
                 Ok(_) => Ok(()),
                 Err(_) => Err(LibconfigError::Invalid),
@@ -179,7 +374,7 @@ impl<'a> Setting<'a> {
         }
     }
 
-    pub fn remove_idx(&'a mut self, idx: usize) -> Result<(), LibconfigError> {
+    pub fn remove_idx(&mut self, idx: usize) -> Result<(), LibconfigError> {
         unsafe {
             match removeSettingByIndex(self.inner.as_mut(), idx as u32) {
                 Ok(_) => Ok(()),
@@ -188,13 +383,9 @@ impl<'a> Setting<'a> {
         }
     }
 
-    pub fn add(
-        &'a mut self,
-        path: &str,
-        setting_type: Type,
-    ) -> Result<Setting<'a>, LibconfigError> {
+    pub fn add(&mut self, path: &str, setting_type: Type) -> Result<Setting<'_>, LibconfigError> {
         unsafe {
-            let_cxx_string!(s = path);
+            let_string!(s = path);
             match addSetting(self.inner.as_mut(), &s, setting_type) {
                 Ok(setting) => Ok(Setting { inner: setting }),
                 Err(_) => Err(LibconfigError::Invalid),
@@ -202,7 +393,35 @@ impl<'a> Setting<'a> {
         }
     }
 
-    pub fn get_name(&'a self) -> Option<&'a str> {
+    pub fn push(&mut self, setting_type: Type) -> Result<Setting<'_>, LibconfigError> {
+        unsafe {
+            match addElement(self.inner.as_mut(), setting_type) {
+                Ok(setting) => Ok(Setting { inner: setting }),
+                Err(_) => Err(LibconfigError::Invalid),
+            }
+        }
+    }
+
+    pub fn iter(&mut self) -> SettingIter<'_> {
+        self.reborrow().into_iter()
+    }
+
+    pub fn get_elem(&mut self, idx: usize) -> Result<Setting<'_>, LibconfigError> {
+        unsafe {
+            match getElemFromSetting(self.inner.as_mut(), idx as u32) {
+                Ok(setting) => Ok(Setting { inner: setting }),
+                Err(_) => Err(LibconfigError::Invalid),
+            }
+        }
+    }
+
+    pub(crate) fn reborrow(&mut self) -> Setting<'_> {
+        Setting {
+            inner: self.inner.as_mut(),
+        }
+    }
+
+    pub fn get_name(&self) -> Option<&str> {
         unsafe {
             match self.inner.as_ref().getName() {
                 s if !s.is_null() => Some(CStr::from_ptr(s).to_str().unwrap()),
@@ -212,14 +431,14 @@ impl<'a> Setting<'a> {
     }
 
     pub fn get_path(&self) -> String {
-        let_cxx_string!(tmp = "");
+        let_string!(tmp = "");
         unsafe {
             getPathFromSetting(&self.inner, tmp.as_mut());
             tmp.to_string()
         }
     }
 
-    pub fn get_parent(&'a mut self) -> Result<Setting<'a>, LibconfigError> {
+    pub fn get_parent(&mut self) -> Result<Setting<'_>, LibconfigError> {
         unsafe {
             match getParentFromSetting(self.inner.as_mut()) {
                 Ok(setting) => Ok(Setting { inner: setting }),
@@ -249,7 +468,7 @@ impl<'a> Setting<'a> {
         unsafe { self.inner.getFormat() }
     }
 
-    pub fn set_format(&'a mut self, format: Format) {
+    pub fn set_format(&mut self, format: Format) {
         unsafe {
             self.inner.as_mut().setFormat(format);
         }
@@ -409,28 +628,82 @@ impl<'a> IntoIterator for Setting<'a> {
 }
 
 pub struct Config {
-    inner: UniquePtr<libconfig_sys::ffi::Config>,
+    inner: UniquePtr<backend::Config>,
+    source: Option<atomic::FileStamp>,
+    // The file last read with `read_file` and what it included.
+    file: Option<PathBuf>,
+    included: Vec<IncludedFile>,
+    // Resolved secrets and other settings marked sensitive.
+    sensitive: secrets::SensitiveMarks,
+    limits: ParseLimits,
+}
+
+// libconfig keeps no thread-local or global state, so a config may move
+// between threads. It is not `Sync`: `get_root` hands out mutable settings
+// through shared references, and on libconfig++ even lookups through `&self`
+// attach wrapper objects to the settings they visit. Use `FrozenConfig` or
+// `SharedConfig` to read a config from several threads.
+unsafe impl Send for Config {}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Config {
     pub fn new() -> Self {
         Self {
             inner: Config_ctor(),
+            source: None,
+            file: None,
+            included: Vec::new(),
+            sensitive: secrets::SensitiveMarks::default(),
+            limits: ParseLimits::default(),
         }
     }
 
     pub fn read_file(&mut self, path: &str) -> Result<(), LibconfigError> {
+        let meta = fs::metadata(path).ok();
+        if let Some(meta) = &meta {
+            self.limits.check_size(path, meta.len())?;
+        }
+        // Read once for the limits, the include scan and the stamp, before
+        // libconfig parses the file: an edit in between makes the stamp stale
+        // rather than hiding the edit from `WriteOptions::if_unchanged`.
+        let contents = fs::read(path).ok();
+        // libconfig does not require UTF-8, so a stray byte must not hide the
+        // `@include`s around it from the limits.
+        let text = contents
+            .as_deref()
+            .map(String::from_utf8_lossy)
+            .unwrap_or_default();
+        let include_dir = self.get_include_path();
+        self.limits
+            .check_source(&text, path, include_dir.as_deref())?;
+        let stamp = contents.as_deref().and_then(|contents| {
+            let modified = meta.and_then(|meta| meta.modified().ok());
+            atomic::FileStamp::from_contents(Path::new(path), modified, contents)
+        });
         let s = CString::new(path).expect("invalid file");
         unsafe {
             match self.inner.pin_mut().readFile(s.as_ptr()) {
-                Ok(_) => Ok(()),
-                Err(_) => Err(LibconfigError::Invalid),
+                Ok(_) => {
+                    self.enforce_structure()?;
+                    self.source = stamp;
+                    self.file = Some(PathBuf::from(path));
+                    self.included =
+                        includes::scan(&text, Some(Path::new(path)), include_dir.as_deref());
+                    Ok(())
+                }
+                Err(err) => Err(LibconfigError::from_exception(&err)),
             }
         }
     }
 
     pub fn write_file(&mut self, path: &str) -> Result<(), LibconfigError> {
-        let_cxx_string!(s = path);
+        self.check_no_secrets()?;
+        let_string!(s = path);
         unsafe {
             match self.inner.pin_mut().writeFile(&s) {
                 Ok(_) => Ok(()),
@@ -439,12 +712,30 @@ impl Config {
         }
     }
 
+    pub fn write_str(&self) -> Result<String, LibconfigError> {
+        self.check_no_secrets()?;
+        let_string!(tmp = "");
+        unsafe {
+            match writeToString(&self.inner, tmp.as_mut()) {
+                Ok(_) => Ok(tmp.to_string()),
+                Err(_) => Err(LibconfigError::Invalid),
+            }
+        }
+    }
+
     pub fn read_str(&mut self, path: &str) -> Result<(), LibconfigError> {
-        let_cxx_string!(s = path);
+        self.limits
+            .check_source(path, "<string>", self.get_include_path().as_deref())?;
+        let_string!(s = path);
         unsafe {
             match self.inner.pin_mut().readString(&s) {
-                Ok(_) => Ok(()),
-                Err(_) => Err(LibconfigError::Invalid),
+                Ok(_) => {
+                    self.enforce_structure()?;
+                    self.file = None;
+                    self.included = includes::scan(path, None, self.get_include_path().as_deref());
+                    Ok(())
+                }
+                Err(err) => Err(LibconfigError::from_exception(&err)),
             }
         }
     }
@@ -456,12 +747,68 @@ impl Config {
         }
     }
 
+    pub fn get_include_path(&self) -> Option<String> {
+        unsafe {
+            match self.inner.getIncludeDir() {
+                s if !s.is_null() => Some(CStr::from_ptr(s).to_string_lossy().into_owned()),
+                _ => None,
+            }
+        }
+    }
+
+    pub fn get_options(&self) -> i32 {
+        unsafe { self.inner.getOptions() }
+    }
+
+    pub fn set_options(&mut self, options: i32) {
+        unsafe { self.inner.pin_mut().setOptions(options) }
+    }
+
+    pub fn get_option(&self, option: ConfigOption) -> bool {
+        unsafe { backend::getOptionFromConfig(&self.inner, option) }
+    }
+
+    pub fn set_option(&mut self, option: ConfigOption, flag: bool) {
+        unsafe { backend::setOptionFromConfig(self.inner.pin_mut(), option, flag) }
+    }
+
+    pub fn get_default_format(&self) -> Format {
+        unsafe { self.inner.getDefaultFormat() }
+    }
+
+    pub fn set_default_format(&mut self, format: Format) {
+        unsafe { self.inner.pin_mut().setDefaultFormat(format) }
+    }
+
+    pub fn get_tab_width(&self) -> u16 {
+        unsafe { self.inner.getTabWidth() }
+    }
+
+    pub fn set_tab_width(&mut self, width: u16) {
+        unsafe { self.inner.pin_mut().setTabWidth(width) }
+    }
+
+    /// 0 when linked against libconfig older than 1.6, which has no float
+    /// precision setting; see `capabilities()`.
+    pub fn get_float_precision(&self) -> u16 {
+        backend::compat::float_precision(&self.inner).unwrap_or(0)
+    }
+
+    /// Does nothing when linked against libconfig older than 1.6.
+    pub fn set_float_precision(&mut self, digits: u16) {
+        backend::compat::set_float_precision(self.inner.pin_mut(), digits);
+    }
+
     pub fn get_root<'a>(&'a self) -> Setting<'a> {
         Setting {
             inner: unsafe { getRootFromConfig(self.inner.as_ref().unwrap()) },
         }
     }
 
+    pub fn to_value(&self) -> Result<Value, LibconfigError> {
+        Value::from_setting(&mut self.get_root())
+    }
+
     pub fn lookup<'a>(&'a mut self, path: &str) -> Result<Setting<'a>, LibconfigError> {
         let s = CString::new(path).expect("invalid file");
         unsafe {
@@ -477,7 +824,7 @@ impl Config {
         unsafe { self.inner.as_ref().unwrap().exists(s.as_ptr()) }
     }
 
-    pub fn lookup_bool(&mut self, path: &str) -> Option<bool> {
+    pub fn lookup_bool(&self, path: &str) -> Option<bool> {
         let s = CString::new(path).expect("invalid settings");
         let mut tmp = false;
         unsafe {
@@ -493,7 +840,7 @@ impl Config {
         }
     }
 
-    pub fn lookup_i32(&mut self, path: &str) -> Option<i32> {
+    pub fn lookup_i32(&self, path: &str) -> Option<i32> {
         let s = CString::new(path).expect("invalid settings");
         let mut tmp: i32 = 0;
         unsafe {
@@ -509,7 +856,7 @@ impl Config {
         }
     }
 
-    pub fn lookup_i64(&mut self, path: &str) -> Option<i64> {
+    pub fn lookup_i64(&self, path: &str) -> Option<i64> {
         let s = CString::new(path).expect("invalid settings");
         let mut tmp: i64 = 0;
         unsafe {
@@ -520,7 +867,7 @@ impl Config {
         }
     }
 
-    pub fn lookup_f32(&mut self, path: &str) -> Option<f32> {
+    pub fn lookup_f32(&self, path: &str) -> Option<f32> {
         let s = CString::new(path).expect("invalid settings");
         let mut tmp: f32 = 0.0;
         unsafe {
@@ -536,7 +883,7 @@ impl Config {
         }
     }
 
-    pub fn lookup_f64(&mut self, path: &str) -> Option<f64> {
+    pub fn lookup_f64(&self, path: &str) -> Option<f64> {
         let s = CString::new(path).expect("invalid settings");
         let mut tmp: f64 = 0.0;
         unsafe {
@@ -552,9 +899,9 @@ impl Config {
         }
     }
 
-    pub fn lookup_string(&mut self, path: &str) -> Option<String> {
+    pub fn lookup_string(&self, path: &str) -> Option<String> {
         let s = CString::new(path).expect("invalid settings");
-        let_cxx_string!(tmp = "");
+        let_string!(tmp = "");
         unsafe {
             match self
                 .inner
@@ -585,10 +932,19 @@ mod tests {
     #[test]
     fn error_on_invalid_file() {
         let mut cfg = Config::new();
-        assert_eq!(
+        assert!(matches!(
             cfg.read_file("../input/invalid.cfg"),
-            Err(LibconfigError::Invalid)
-        );
+            Err(LibconfigError::Syntax { file, line: 1, .. }) if file == "../input/invalid.cfg"
+        ));
+    }
+
+    #[test]
+    fn error_on_invalid_string() {
+        let mut cfg = Config::new();
+        assert!(matches!(
+            cfg.read_str("a = 1;\nb = ;"),
+            Err(LibconfigError::Syntax { line: 2, .. })
+        ));
     }
 
     #[test]
@@ -602,12 +958,48 @@ mod tests {
     fn error_on_valid_include_dir() {
         let mut cfg = Config::new();
         cfg.set_include_path("../");
-        assert_eq!(
+        assert!(matches!(
             cfg.read_file("../input/test_with_include.cfg"),
-            Err(LibconfigError::Invalid)
+            Err(LibconfigError::Syntax { .. })
+        ));
+    }
+
+    #[test]
+    fn ok_on_get_include_dir() {
+        let mut cfg = Config::new();
+        assert_eq!(cfg.get_include_path(), None);
+        cfg.set_include_path("../input");
+        assert_eq!(cfg.get_include_path(), Some(String::from("../input")));
+    }
+
+    #[test]
+    fn ok_on_output_options() {
+        let mut cfg = Config::new();
+        cfg.set_tab_width(4);
+        cfg.set_float_precision(3);
+        cfg.set_default_format(Format::FormatHex);
+        cfg.set_option(ConfigOption::OptionSemicolonSeparators, false);
+        assert_eq!(cfg.get_tab_width(), 4);
+        assert_eq!(cfg.get_float_precision(), 3);
+        assert_eq!(cfg.get_default_format(), Format::FormatHex);
+        assert!(!cfg.get_option(ConfigOption::OptionSemicolonSeparators));
+        cfg.set_options(ConfigOption::OptionSemicolonSeparators.repr as i32);
+        assert_eq!(
+            cfg.get_options(),
+            ConfigOption::OptionSemicolonSeparators.repr as i32
         );
     }
 
+    #[test]
+    fn ok_on_write_str() {
+        let mut cfg = Config::new();
+        assert_eq!(cfg.read_str("a = 1; grp = { b = \"x\"; };"), Ok(()));
+        let output = cfg.write_str().unwrap();
+        let mut reread = Config::new();
+        assert_eq!(reread.read_str(&output), Ok(()));
+        assert_eq!(reread.to_value().unwrap(), cfg.to_value().unwrap());
+    }
+
     #[test]
     fn ok_on_valid_file() {
         let mut cfg = Config::new();
@@ -684,7 +1076,7 @@ mod tests {
         if let Ok(setting) = cfg.get_root().lookup("outer") {
             assert!(setting.exists("inner"));
         } else {
-            assert!(false);
+            panic!("setting not found");
         }
     }
 
@@ -695,7 +1087,7 @@ mod tests {
         if let Ok(setting) = cfg.get_root().lookup("outer") {
             assert_eq!(setting.get_name(), Some("outer"));
         } else {
-            assert!(false);
+            panic!("setting not found");
         }
     }
 
@@ -714,7 +1106,7 @@ mod tests {
         if let Ok(setting) = cfg.get_root().lookup("outer").unwrap().lookup("inner") {
             assert_eq!(setting.get_path(), "outer.inner");
         } else {
-            assert!(false);
+            panic!("setting not found");
         }
     }
 
@@ -725,7 +1117,7 @@ mod tests {
         if let Ok(mut setting) = cfg.get_root().lookup("outer").unwrap().lookup("inner") {
             assert_eq!(setting.get_parent().unwrap().get_name(), Some("outer"));
         } else {
-            assert!(false);
+            panic!("setting not found");
         }
     }
 
@@ -744,7 +1136,7 @@ mod tests {
         if let Ok(setting) = cfg.get_root().lookup("outer").unwrap().lookup("inner") {
             assert!(!setting.is_root());
         } else {
-            assert!(false);
+            panic!("setting not found");
         }
     }
 
@@ -755,7 +1147,7 @@ mod tests {
         if let Ok(setting) = cfg.get_root().lookup("outer").unwrap().lookup("inner") {
             assert_eq!(setting.get_type(), Type::TypeInt);
         } else {
-            assert!(false);
+            panic!("setting not found");
         }
     }
 
@@ -766,7 +1158,7 @@ mod tests {
         if let Ok(setting) = cfg.get_root().lookup("outer") {
             assert_eq!(setting.get_type(), Type::TypeGroup);
         } else {
-            assert!(false);
+            panic!("setting not found");
         }
     }
 
@@ -779,7 +1171,7 @@ mod tests {
             let mut iter = setting.into_iter();
             assert!(iter.next().is_none());
         } else {
-            assert!(false);
+            panic!("setting not found");
         }
     }
 
@@ -792,7 +1184,7 @@ mod tests {
             let mut iter = setting.into_iter();
             assert_eq!(iter.next().unwrap().try_into(), Ok(3));
         } else {
-            assert!(false);
+            panic!("setting not found");
         }
     }
 
@@ -808,10 +1200,57 @@ mod tests {
             assert_eq!(iter.next().unwrap().try_into(), Ok(8));
             assert!(iter.next().is_none());
         } else {
-            assert!(false);
+            panic!("setting not found");
         }
     }
 
+    #[test]
+    fn ok_on_replace_with_other_type() {
+        let mut cfg = Config::new();
+        assert_eq!(cfg.read_file("../input/test.cfg"), Ok(()));
+        let mut setting = cfg.lookup("val_int").unwrap();
+        assert_eq!(setting.replace(&Value::String(String::from("x"))), Ok(()));
+        assert_eq!(setting.get_type(), Type::TypeString);
+        assert_eq!(setting.get_name(), Some("val_int"));
+        assert_eq!(setting.get_index(), Some(1));
+        let mut setting = cfg.lookup("another").unwrap();
+        assert_eq!(setting.replace(&Value::Int64(0x10)), Ok(()));
+        assert_eq!(setting.get_format(), Format::FormatHex);
+        assert_eq!(cfg.lookup_string("val_int"), Some(String::from("x")));
+        assert_eq!(cfg.lookup_i64("another"), Some(0x10));
+    }
+
+    #[test]
+    fn ok_on_replace_element() {
+        let mut cfg = Config::new();
+        assert_eq!(cfg.read_str("list = (1, \"two\"); arr = [1, 2];"), Ok(()));
+        let mut setting = cfg.lookup("list.[0]").unwrap();
+        assert_eq!(
+            setting.replace(&Value::Array(vec![Value::Bool(true)])),
+            Ok(())
+        );
+        assert_eq!(setting.get_index(), Some(0));
+        assert_eq!(cfg.lookup_bool("list.[0].[0]"), Some(true));
+        assert_eq!(cfg.lookup_string("list.[1]"), Some(String::from("two")));
+        let mut setting = cfg.lookup("arr.[0]").unwrap();
+        assert_eq!(
+            setting.replace(&Value::Float(1.5)),
+            Err(LibconfigError::TypeMismatch(String::from("arr.[0]")))
+        );
+    }
+
+    #[test]
+    fn ok_on_set_any_promotes_int() {
+        let mut cfg = Config::new();
+        assert_eq!(cfg.read_file("../input/test.cfg"), Ok(()));
+        let mut setting = cfg.lookup("val_int").unwrap();
+        assert_eq!(setting.set_any(&Value::Int64(7)), Ok(()));
+        assert_eq!(setting.get_type(), Type::TypeInt);
+        assert_eq!(setting.set_any(&Value::Int64(1 << 40)), Ok(()));
+        assert_eq!(setting.get_type(), Type::TypeInt64);
+        assert_eq!(cfg.lookup_i64("val_int"), Some(1 << 40));
+    }
+
     #[test]
     fn write_setting() {
         let mut cfg = Config::new();
@@ -0,0 +1,425 @@
+use crate::{includes, Config, LibconfigError, Setting};
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+/// Restrictions for reading configs from untrusted sources.
+///
+/// Includes and file sizes are checked before libconfig sees the input;
+/// nesting and the number of settings are checked once it is parsed, and a
+/// config that exceeds them is left empty.
+#[derive(Debug, Clone)]
+pub struct ParseLimits {
+    pub allow_includes: bool,
+    /// Directories that included files must resolve into, symlinks
+    /// followed. `None` allows any directory.
+    pub include_roots: Option<Vec<PathBuf>>,
+    /// Reject include paths with `..` components.
+    pub reject_traversal: bool,
+    /// How deep includes may nest; a file included by the main input is at
+    /// depth 1.
+    pub max_include_depth: Option<usize>,
+    /// Applies to the main input and to every included file.
+    pub max_file_size: Option<u64>,
+    /// How deep settings may nest; top-level settings are at depth 1.
+    pub max_nesting: Option<usize>,
+    /// Counts every setting and array/list element.
+    pub max_settings: Option<usize>,
+}
+
+impl Default for ParseLimits {
+    fn default() -> Self {
+        ParseLimits {
+            allow_includes: true,
+            include_roots: None,
+            reject_traversal: false,
+            max_include_depth: None,
+            max_file_size: None,
+            max_nesting: None,
+            max_settings: None,
+        }
+    }
+}
+
+impl ParseLimits {
+    /// Limits for input from untrusted users: no includes, at most 1 MiB,
+    /// 32 levels of nesting and 10000 settings.
+    pub fn untrusted() -> Self {
+        ParseLimits {
+            allow_includes: false,
+            include_roots: Some(Vec::new()),
+            reject_traversal: true,
+            max_include_depth: Some(0),
+            max_file_size: Some(1 << 20),
+            max_nesting: Some(32),
+            max_settings: Some(10_000),
+        }
+    }
+
+    // Checks the input and, recursively, every file it includes.
+    pub(crate) fn check_source(
+        &self,
+        text: &str,
+        origin: &str,
+        include_dir: Option<&str>,
+    ) -> Result<(), LibconfigError> {
+        self.check_size(origin, text.len() as u64)?;
+        let roots = self.include_roots.as_ref().map(|roots| {
+            roots
+                .iter()
+                .filter_map(|root| fs::canonicalize(root).ok())
+                .collect::<Vec<_>>()
+        });
+        self.check_includes(text, include_dir, roots.as_deref(), &mut Vec::new())
+    }
+
+    fn check_includes(
+        &self,
+        text: &str,
+        include_dir: Option<&str>,
+        roots: Option<&[PathBuf]>,
+        stack: &mut Vec<PathBuf>,
+    ) -> Result<(), LibconfigError> {
+        for include in directives(text) {
+            if !self.allow_includes {
+                return Err(LibconfigError::IncludeDenied(include));
+            }
+            if self.reject_traversal
+                && Path::new(&include)
+                    .components()
+                    .any(|component| component == Component::ParentDir)
+            {
+                return Err(LibconfigError::PathTraversal(include));
+            }
+            // Missing files are left for libconfig to report.
+            let Ok(path) = fs::canonicalize(includes::resolve(&include, include_dir)) else {
+                continue;
+            };
+            // libconfig includes every file in an included directory.
+            let files = if path.is_dir() {
+                dir_entries(&include, &path)
+            } else {
+                vec![(include, path)]
+            };
+            for (include, path) in files {
+                self.check_file(include, path, include_dir, roots, stack)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn check_file(
+        &self,
+        include: String,
+        path: PathBuf,
+        include_dir: Option<&str>,
+        roots: Option<&[PathBuf]>,
+        stack: &mut Vec<PathBuf>,
+    ) -> Result<(), LibconfigError> {
+        if roots.is_some_and(|roots| !roots.iter().any(|root| path.starts_with(root))) {
+            return Err(LibconfigError::IncludeOutsideRoots(include));
+        }
+        if self
+            .max_include_depth
+            .is_some_and(|limit| stack.len() >= limit)
+        {
+            return Err(LibconfigError::IncludeTooDeep(include));
+        }
+        // libconfig rejects recursive includes itself.
+        if stack.contains(&path) {
+            return Ok(());
+        }
+        let size = fs::metadata(&path).map(|meta| meta.len()).unwrap_or(0);
+        self.check_size(&include, size)?;
+        // Read as bytes: libconfig does not require UTF-8, so neither may the
+        // search for nested includes.
+        let Ok(contents) = fs::read(&path) else {
+            return Ok(());
+        };
+        stack.push(path);
+        self.check_includes(
+            &String::from_utf8_lossy(&contents),
+            include_dir,
+            roots,
+            stack,
+        )?;
+        stack.pop();
+        Ok(())
+    }
+
+    pub(crate) fn check_size(&self, file: &str, size: u64) -> Result<(), LibconfigError> {
+        match self.max_file_size {
+            Some(limit) if size > limit => Err(LibconfigError::FileTooLarge {
+                file: file.to_string(),
+                size,
+                limit,
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    // Walks the parsed tree without recursion, so that deeply nested input
+    // cannot exhaust the stack.
+    pub(crate) fn check_structure(&self, root: Setting) -> Result<(), LibconfigError> {
+        if self.max_nesting.is_none() && self.max_settings.is_none() {
+            return Ok(());
+        }
+        let mut count = 0;
+        let mut pending = vec![(root, 0)];
+        while let Some((setting, depth)) = pending.pop() {
+            if let Some(limit) = self.max_nesting.filter(|limit| depth > *limit) {
+                return Err(LibconfigError::NestingTooDeep(limit));
+            }
+            if depth > 0 {
+                count += 1;
+            }
+            if let Some(limit) = self.max_settings.filter(|limit| count > *limit) {
+                return Err(LibconfigError::TooManySettings(limit));
+            }
+            if setting.is_aggregate() {
+                pending.extend(setting.into_iter().map(|child| (child, depth + 1)));
+            }
+        }
+        Ok(())
+    }
+}
+
+// The paths of the `@include` directives in `text`. libconfig only accepts
+// the directive at the start of a line; directives inside block comments
+// are reported too, which errs on the side of caution.
+fn directives(text: &str) -> Vec<String> {
+    text.lines()
+        .filter_map(|line| {
+            let rest = line
+                .trim_start_matches([' ', '\t'])
+                .strip_prefix("@include")?;
+            let rest = rest.trim_start_matches([' ', '\t']).strip_prefix('"')?;
+            Some(rest[..rest.find('"').unwrap_or(rest.len())].to_string())
+        })
+        .collect()
+}
+
+// The entries of an included directory, sorted by name, each with the name
+// it is reported under. Entries that cannot be resolved are skipped, like
+// missing files.
+fn dir_entries(include: &str, dir: &Path) -> Vec<(String, PathBuf)> {
+    let mut entries = fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| {
+            let entry = entry.ok()?;
+            let path = fs::canonicalize(entry.path()).ok()?;
+            let name = Path::new(include).join(entry.file_name());
+            Some((name.to_string_lossy().into_owned(), path))
+        })
+        .collect::<Vec<_>>();
+    entries.sort();
+    entries
+}
+
+impl Config {
+    pub fn set_limits(&mut self, limits: ParseLimits) {
+        self.limits = limits;
+    }
+
+    pub fn get_limits(&self) -> &ParseLimits {
+        &self.limits
+    }
+
+    // Empties a config that parsed but broke the limits.
+    pub(crate) fn enforce_structure(&mut self) -> Result<(), LibconfigError> {
+        if let Err(err) = self.limits.check_structure(self.get_root()) {
+            let mut root = self.get_root();
+            while let Some(last) = (root.get_length()? as usize).checked_sub(1) {
+                root.remove_idx(last)?;
+            }
+            return Err(err);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::scratch_dir;
+
+    #[test]
+    fn ok_on_directives() {
+        assert_eq!(
+            directives("@include \"a.cfg\"\n  \t@include\t\"b c.cfg\" # x\nx = \"@include\";\n"),
+            ["a.cfg", "b c.cfg"]
+        );
+    }
+
+    #[test]
+    fn err_on_include_policy() {
+        let dir = scratch_dir("limits-include");
+        let allowed = dir.join("allowed");
+        fs::create_dir_all(&allowed).unwrap();
+        fs::write(allowed.join("a.cfg"), "@include \"b.cfg\"\n").unwrap();
+        fs::write(allowed.join("b.cfg"), "x = 1;\n").unwrap();
+        fs::write(dir.join("outside.cfg"), "x = 1;\n").unwrap();
+        let include_dir = allowed.to_str();
+        let text = "@include \"a.cfg\"\n";
+
+        let limits = ParseLimits {
+            allow_includes: false,
+            ..ParseLimits::default()
+        };
+        assert_eq!(
+            limits.check_source(text, "input", include_dir),
+            Err(LibconfigError::IncludeDenied(String::from("a.cfg")))
+        );
+
+        let limits = ParseLimits {
+            include_roots: Some(vec![allowed.clone()]),
+            ..ParseLimits::default()
+        };
+        assert_eq!(limits.check_source(text, "input", include_dir), Ok(()));
+        assert_eq!(
+            limits.check_source("@include \"../outside.cfg\"\n", "input", include_dir),
+            Err(LibconfigError::IncludeOutsideRoots(String::from(
+                "../outside.cfg"
+            )))
+        );
+
+        let limits = ParseLimits {
+            reject_traversal: true,
+            ..ParseLimits::default()
+        };
+        assert_eq!(
+            limits.check_source("@include \"../outside.cfg\"\n", "input", include_dir),
+            Err(LibconfigError::PathTraversal(String::from(
+                "../outside.cfg"
+            )))
+        );
+
+        let limits = ParseLimits {
+            max_include_depth: Some(1),
+            ..ParseLimits::default()
+        };
+        assert_eq!(
+            limits.check_source(text, "input", include_dir),
+            Err(LibconfigError::IncludeTooDeep(String::from("b.cfg")))
+        );
+
+        let limits = ParseLimits {
+            max_file_size: Some(10),
+            ..ParseLimits::default()
+        };
+        assert_eq!(
+            limits.check_source(text, "input", include_dir),
+            Err(LibconfigError::FileTooLarge {
+                file: String::from("input"),
+                size: 17,
+                limit: 10,
+            })
+        );
+    }
+
+    #[test]
+    fn err_on_structure_limits() {
+        let mut cfg = Config::new();
+        cfg.set_limits(ParseLimits {
+            max_nesting: Some(2),
+            ..ParseLimits::default()
+        });
+        assert_eq!(cfg.read_str("a = { b = 1; };"), Ok(()));
+        assert_eq!(
+            cfg.read_str("a = { b = { c = 1; }; };"),
+            Err(LibconfigError::NestingTooDeep(2))
+        );
+        assert!(!cfg.exists("a"));
+        cfg.set_limits(ParseLimits {
+            max_settings: Some(3),
+            ..ParseLimits::default()
+        });
+        assert_eq!(cfg.read_str("a = [1, 2];"), Ok(()));
+        assert_eq!(
+            cfg.read_str("a = [1, 2, 3];"),
+            Err(LibconfigError::TooManySettings(3))
+        );
+    }
+
+    #[test]
+    fn err_on_untrusted_include() {
+        let mut cfg = Config::new();
+        cfg.set_limits(ParseLimits::untrusted());
+        assert_eq!(
+            cfg.read_str("@include \"/etc/shadow\"\nx = 1;\n"),
+            Err(LibconfigError::IncludeDenied(String::from("/etc/shadow")))
+        );
+    }
+
+    #[test]
+    fn err_on_include_after_non_utf8_byte() {
+        let dir = scratch_dir("limits-non-utf8");
+        let main = dir.join("main.cfg");
+        fs::write(&main, b"# \xff\n@include \"/etc/shadow\"\nx = 1;\n").unwrap();
+        let mut cfg = Config::new();
+        cfg.set_limits(ParseLimits::untrusted());
+        assert_eq!(
+            cfg.read_file(main.to_str().unwrap()),
+            Err(LibconfigError::IncludeDenied(String::from("/etc/shadow")))
+        );
+
+        let allowed = dir.join("allowed");
+        fs::create_dir_all(&allowed).unwrap();
+        fs::write(
+            allowed.join("a.cfg"),
+            b"# \xff\n@include \"../outside.cfg\"\n",
+        )
+        .unwrap();
+        fs::write(dir.join("outside.cfg"), "x = 1;\n").unwrap();
+        let limits = ParseLimits {
+            include_roots: Some(vec![allowed.clone()]),
+            ..ParseLimits::default()
+        };
+        assert_eq!(
+            limits.check_source("@include \"a.cfg\"\n", "input", allowed.to_str()),
+            Err(LibconfigError::IncludeOutsideRoots(String::from(
+                "../outside.cfg"
+            )))
+        );
+    }
+
+    #[test]
+    fn err_on_directory_include_entry() {
+        let dir = scratch_dir("limits-include-dir");
+        let allowed = dir.join("allowed");
+        fs::create_dir_all(allowed.join("conf.d")).unwrap();
+        fs::write(allowed.join("conf.d/a.cfg"), "x = 1;\n").unwrap();
+        fs::write(dir.join("outside.cfg"), "y = 2;\n").unwrap();
+        let limits = ParseLimits {
+            include_roots: Some(vec![allowed.clone()]),
+            ..ParseLimits::default()
+        };
+        let text = "@include \"conf.d\"\n";
+        assert_eq!(limits.check_source(text, "input", allowed.to_str()), Ok(()));
+        fs::write(
+            allowed.join("conf.d/b.cfg"),
+            "@include \"../outside.cfg\"\n",
+        )
+        .unwrap();
+        assert_eq!(
+            limits.check_source(text, "input", allowed.to_str()),
+            Err(LibconfigError::IncludeOutsideRoots(String::from(
+                "../outside.cfg"
+            )))
+        );
+        fs::remove_file(allowed.join("conf.d/b.cfg")).unwrap();
+        fs::write(allowed.join("conf.d/c.cfg"), "z = \"much too long\";\n").unwrap();
+        let limits = ParseLimits {
+            max_file_size: Some(20),
+            ..ParseLimits::default()
+        };
+        assert_eq!(
+            limits.check_source(text, "input", allowed.to_str()),
+            Err(LibconfigError::FileTooLarge {
+                file: String::from("conf.d/c.cfg"),
+                size: 21,
+                limit: 20,
+            })
+        );
+    }
+}
@@ -0,0 +1,274 @@
+use crate::path::{self, Component};
+use crate::snapshot::{self, Snapshot};
+use crate::{Config, FromConfig, LibconfigError, Value};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PatchOp {
+    Add { path: String, value: Value },
+    Remove { path: String },
+    Replace { path: String, value: Value },
+    Move { from: String, path: String },
+    Copy { from: String, path: String },
+    Test { path: String, value: Value },
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Patch {
+    pub ops: Vec<PatchOp>,
+}
+
+impl Patch {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, op: PatchOp) -> &mut Self {
+        self.ops.push(op);
+        self
+    }
+}
+
+impl From<Vec<PatchOp>> for Patch {
+    fn from(ops: Vec<PatchOp>) -> Self {
+        Self { ops }
+    }
+}
+
+fn split(path: &str) -> Result<(Vec<Component>, Component), LibconfigError> {
+    let mut components = path::parse(path)?;
+    match components.pop() {
+        Some(last) => Ok((components, last)),
+        None => Err(LibconfigError::Invalid),
+    }
+}
+
+fn name_of(component: &Component) -> Option<String> {
+    match component {
+        Component::Name(name) => Some(name.clone()),
+        Component::Index(_) => None,
+    }
+}
+
+// Inserts the snapshot at `path`. A setting already present under the same
+// name is replaced in place, as is a list element when `replace` is set.
+fn insert(
+    cfg: &Config,
+    path: &str,
+    snapshot: Snapshot,
+    replace: bool,
+) -> Result<(), LibconfigError> {
+    let (parent, last) = split(path)?;
+    let mut parent = path::resolve(cfg.get_root(), &parent)?;
+    let snapshot = snapshot.with_name(name_of(&last));
+    match (&last, parent.is_group()) {
+        (Component::Name(name), true) if parent.exists(name) => {
+            let idx = parent.lookup(name)?.get_index().unwrap_or(0);
+            parent.remove(name)?;
+            snapshot::insert_at(&mut parent, idx, &snapshot)
+        }
+        (Component::Name(_), true) => snapshot.restore(&mut parent),
+        (Component::Index(idx), false) if parent.is_aggregate() => {
+            if replace {
+                path::child(parent.reborrow(), &last)?;
+                parent.remove_idx(*idx)?;
+            }
+            snapshot::insert_at(&mut parent, *idx, &snapshot)
+        }
+        _ => Err(LibconfigError::TypeMismatch(parent.get_path())),
+    }
+}
+
+fn remove(cfg: &Config, path: &str) -> Result<(), LibconfigError> {
+    let (parent, last) = split(path)?;
+    let mut parent = path::resolve(cfg.get_root(), &parent)?;
+    // Resolving the target reports a missing setting with its full path.
+    path::child(parent.reborrow(), &last)?;
+    match last {
+        Component::Name(name) => parent.remove(&name),
+        Component::Index(idx) => parent.remove_idx(idx),
+    }
+}
+
+fn capture(cfg: &Config, path: &str) -> Result<Snapshot, LibconfigError> {
+    let mut setting = path::resolve(cfg.get_root(), &path::parse(path)?)?;
+    Snapshot::capture(&mut setting)
+}
+
+fn apply_op(cfg: &Config, op: &PatchOp) -> Result<(), LibconfigError> {
+    match op {
+        PatchOp::Add { path, value } => insert(cfg, path, Snapshot::from_value(None, value), false),
+        PatchOp::Remove { path } => remove(cfg, path),
+        PatchOp::Replace { path, value } => {
+            path::resolve(cfg.get_root(), &path::parse(path)?)?;
+            insert(cfg, path, Snapshot::from_value(None, value), true)
+        }
+        PatchOp::Move { from, path } => {
+            let snapshot = capture(cfg, from)?;
+            remove(cfg, from)?;
+            insert(cfg, path, snapshot, false)
+        }
+        PatchOp::Copy { from, path } => insert(cfg, path, capture(cfg, from)?, false),
+        PatchOp::Test { path, value } => {
+            let mut setting = path::resolve(cfg.get_root(), &path::parse(path)?)?;
+            match Value::from_setting(&mut setting)? == *value {
+                true => Ok(()),
+                false => Err(LibconfigError::TestFailed(path.clone())),
+            }
+        }
+    }
+}
+
+impl Config {
+    /// Applies every operation in `patch`, or none of them: the whole tree
+    /// is captured first and restored when an operation fails, so applying
+    /// a patch takes time and memory in proportion to the config rather than
+    /// to the patch.
+    pub fn apply_patch(&mut self, patch: &Patch) -> Result<(), LibconfigError> {
+        let backup = Snapshot::capture(&mut self.get_root())?;
+        let marks = self.sensitive.clone();
+        for op in &patch.ops {
+            let result = apply_op(self, op).and_then(|()| match op {
+                PatchOp::Move { from, path } => self.sensitive.copy(from, path, true),
+                PatchOp::Copy { from, path } => self.sensitive.copy(from, path, false),
+                _ => Ok(()),
+            });
+            if let Err(err) = result {
+                backup.restore_children(&mut self.get_root())?;
+                self.sensitive = marks;
+                return Err(err);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::config;
+    use crate::Format;
+
+    #[test]
+    fn ok_on_add_remove_replace() {
+        let mut cfg = config("a = 1; b = \"x\"; grp = { c = 2; };");
+        let patch = Patch::from(vec![
+            PatchOp::Add {
+                path: String::from("grp.d"),
+                value: Value::Bool(true),
+            },
+            PatchOp::Remove {
+                path: String::from("a"),
+            },
+            PatchOp::Replace {
+                path: String::from("b"),
+                value: Value::Int(7),
+            },
+        ]);
+        assert_eq!(cfg.apply_patch(&patch), Ok(()));
+        assert!(!cfg.exists("a"));
+        assert_eq!(cfg.lookup_i32("b"), Some(7));
+        assert_eq!(cfg.lookup_bool("grp.d"), Some(true));
+        assert_eq!(cfg.lookup("b").unwrap().get_index(), Some(0));
+    }
+
+    #[test]
+    fn ok_on_libconfig_path_separators() {
+        let mut cfg = config("grp = { c = 2; arr = [1, 2]; };");
+        let patch = Patch::from(vec![
+            PatchOp::Replace {
+                path: String::from("grp/c"),
+                value: Value::Int(3),
+            },
+            PatchOp::Remove {
+                path: String::from("grp:arr/[0]"),
+            },
+        ]);
+        assert_eq!(cfg.apply_patch(&patch), Ok(()));
+        assert_eq!(cfg.lookup_i32("grp.c"), Some(3));
+        assert_eq!(cfg.lookup_i32("grp.arr.[0]"), Some(2));
+    }
+
+    #[test]
+    fn ok_on_list_insert_by_index() {
+        let mut cfg = config("arr = [1, 3];");
+        let mut patch = Patch::new();
+        patch.push(PatchOp::Add {
+            path: String::from("arr.[1]"),
+            value: Value::Int(2),
+        });
+        assert_eq!(cfg.apply_patch(&patch), Ok(()));
+        assert_eq!(
+            cfg.to_value().unwrap().get("arr"),
+            Some(&Value::Array(vec![
+                Value::Int(1),
+                Value::Int(2),
+                Value::Int(3)
+            ]))
+        );
+    }
+
+    #[test]
+    fn ok_on_move_and_copy_keep_format() {
+        let mut cfg = config("src = 0xFF; grp = { };");
+        let patch = Patch::from(vec![
+            PatchOp::Copy {
+                from: String::from("src"),
+                path: String::from("copy"),
+            },
+            PatchOp::Move {
+                from: String::from("src"),
+                path: String::from("grp.moved"),
+            },
+        ]);
+        assert_eq!(cfg.apply_patch(&patch), Ok(()));
+        assert!(!cfg.exists("src"));
+        assert_eq!(cfg.lookup_i32("grp.moved"), Some(255));
+        assert_eq!(
+            cfg.lookup("grp.moved").unwrap().get_format(),
+            Format::FormatHex
+        );
+        assert_eq!(cfg.lookup_i32("copy"), Some(255));
+    }
+
+    #[test]
+    fn untouched_on_failed_test() {
+        let mut cfg = config("a = 1; b = 0x10;");
+        let before = cfg.to_value().unwrap();
+        let patch = Patch::from(vec![
+            PatchOp::Remove {
+                path: String::from("a"),
+            },
+            PatchOp::Test {
+                path: String::from("b"),
+                value: Value::Int(1),
+            },
+        ]);
+        assert_eq!(
+            cfg.apply_patch(&patch),
+            Err(LibconfigError::TestFailed(String::from("b")))
+        );
+        assert_eq!(cfg.to_value().unwrap(), before);
+        assert_eq!(cfg.lookup("b").unwrap().get_format(), Format::FormatHex);
+    }
+
+    #[test]
+    fn untouched_on_missing_parent() {
+        let mut cfg = config("a = 1;");
+        let before = cfg.to_value().unwrap();
+        let patch = Patch::from(vec![
+            PatchOp::Add {
+                path: String::from("b"),
+                value: Value::Int(2),
+            },
+            PatchOp::Add {
+                path: String::from("missing.c"),
+                value: Value::Int(3),
+            },
+        ]);
+        assert_eq!(
+            cfg.apply_patch(&patch),
+            Err(LibconfigError::Missing(String::from("missing")))
+        );
+        assert_eq!(cfg.to_value().unwrap(), before);
+    }
+}
@@ -0,0 +1,97 @@
+use crate::backend::{getElemFromSetting, lookupSettingFromSetting};
+use crate::{LibconfigError, Setting};
+use std::ffi::CString;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) enum Component {
+    Name(String),
+    Index(usize),
+}
+
+// The separators libconfig accepts between path components.
+pub(crate) const SEPARATORS: [char; 3] = [':', '.', '/'];
+
+// Splits `path` like libconfig's lookup does: components are separated by
+// any of `SEPARATORS`, and a run of them counts as one.
+pub(crate) fn parse(path: &str) -> Result<Vec<Component>, LibconfigError> {
+    path.split(SEPARATORS)
+        .filter(|component| !component.is_empty())
+        .map(|component| {
+            match component
+                .strip_prefix('[')
+                .and_then(|c| c.strip_suffix(']'))
+            {
+                Some(idx) => idx
+                    .parse()
+                    .map(Component::Index)
+                    .map_err(|_| LibconfigError::Invalid),
+                None => Ok(Component::Name(component.to_string())),
+            }
+        })
+        .collect()
+}
+
+pub(crate) fn join(components: &[Component]) -> String {
+    components
+        .iter()
+        .map(|component| match component {
+            Component::Name(name) => name.clone(),
+            Component::Index(idx) => format!("[{}]", idx),
+        })
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+pub(crate) fn append(path: &str, component: &str) -> String {
+    match path.is_empty() {
+        true => component.to_string(),
+        false => format!("{}.{}", path, component),
+    }
+}
+
+pub(crate) fn child<'a>(
+    setting: Setting<'a>,
+    component: &Component,
+) -> Result<Setting<'a>, LibconfigError> {
+    let missing = || {
+        LibconfigError::Missing(crate::traits::child_path(
+            &setting,
+            &component_str(component),
+        ))
+    };
+    match component {
+        Component::Name(name) => {
+            if !setting.is_group() || !setting.exists(name) {
+                return Err(missing());
+            }
+            let s = CString::new(name.as_str()).expect("invalid settings");
+            match unsafe { lookupSettingFromSetting(setting.inner, s.as_ptr()) } {
+                Ok(inner) => Ok(Setting { inner }),
+                Err(_) => Err(LibconfigError::Invalid),
+            }
+        }
+        Component::Index(idx) => {
+            if !setting.is_aggregate() || *idx >= setting.get_length()? as usize {
+                return Err(missing());
+            }
+            match unsafe { getElemFromSetting(setting.inner, *idx as u32) } {
+                Ok(inner) => Ok(Setting { inner }),
+                Err(_) => Err(LibconfigError::Invalid),
+            }
+        }
+    }
+}
+
+pub(crate) fn resolve<'a>(
+    mut setting: Setting<'a>,
+    components: &[Component],
+) -> Result<Setting<'a>, LibconfigError> {
+    for component in components {
+        setting = child(setting, component)?;
+    }
+    Ok(setting)
+}
+
+fn component_str(component: &Component) -> String {
+    join(std::slice::from_ref(component))
+}
@@ -0,0 +1,523 @@
+use crate::path::{self, Component};
+use crate::{Config, FromConfig, LibconfigError, Setting, Value};
+use std::cmp::Ordering;
+use std::collections::HashSet;
+
+#[derive(Debug, PartialEq)]
+enum Segment {
+    Exact(Component),
+    // `*` or `[*]`: every child.
+    Children,
+    // `**`: the setting itself and all of its descendants.
+    Descendants,
+    // `[?(@.path op literal)]`: every child passing the test.
+    Filter(Filter),
+}
+
+#[derive(Debug, PartialEq)]
+struct Filter {
+    path: Vec<Component>,
+    test: Option<(Op, Value)>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Op {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl Config {
+    /// Returns the paths of all settings matching `query`, in document order.
+    ///
+    /// Queries are paths whose components may also be `*` or `[*]` for all
+    /// children, `**` for any number of levels, or a filter such as
+    /// `[?(@.enabled == true)]`, which keeps the children for which the
+    /// relative path `@.enabled` exists and compares equal to the literal.
+    /// Filters support `==`, `!=`, `<`, `<=`, `>` and `>=` against booleans,
+    /// numbers and strings; a filter without a comparison only tests that
+    /// the path exists.
+    ///
+    /// Only the paths are returned; use [`Config::query_with`] to read the
+    /// matching settings themselves.
+    pub fn query(&self, query: &str) -> Result<Vec<String>, LibconfigError> {
+        let mut found = Vec::new();
+        self.query_with(query, |path, _| {
+            found.push(path.to_string());
+            Ok(())
+        })?;
+        Ok(found)
+    }
+
+    /// Reads every setting matching `query` as `T`.
+    pub fn query_values<T: FromConfig>(
+        &self,
+        query: &str,
+    ) -> Result<Vec<(String, T)>, LibconfigError> {
+        let mut found = Vec::new();
+        self.query_with(query, |path, mut setting| {
+            found.push((path.to_string(), T::from_setting(&mut setting)?));
+            Ok(())
+        })?;
+        Ok(found)
+    }
+
+    /// Calls `visit` with the path and setting of every match of `query`,
+    /// in document order, stopping at the first error it returns.
+    ///
+    /// Each setting is only lent for the duration of its call: matches may
+    /// be nested in one another, so no two handles are live at once.
+    pub fn query_with(
+        &self,
+        query: &str,
+        mut visit: impl FnMut(&str, Setting) -> Result<(), LibconfigError>,
+    ) -> Result<(), LibconfigError> {
+        let segments = parse(query)?;
+        // `**` can reach the same setting in more than one way.
+        let mut seen = HashSet::new();
+        walk(
+            self.get_root(),
+            &segments,
+            &mut Vec::new(),
+            &mut |components, setting| match seen.insert(components.to_vec()) {
+                true => visit(&path::join(components), setting),
+                false => Ok(()),
+            },
+        )
+    }
+}
+
+type Visitor<'v> = dyn FnMut(&[Component], Setting) -> Result<(), LibconfigError> + 'v;
+
+fn walk(
+    mut setting: Setting,
+    segments: &[Segment],
+    components: &mut Vec<Component>,
+    visit: &mut Visitor,
+) -> Result<(), LibconfigError> {
+    let Some((segment, rest)) = segments.split_first() else {
+        return visit(components, setting);
+    };
+    match segment {
+        Segment::Exact(component) => {
+            if let Ok(child) = path::child(setting, component) {
+                components.push(component.clone());
+                walk(child, rest, components, visit)?;
+                components.pop();
+            }
+        }
+        Segment::Children => {
+            for (component, child) in children(setting) {
+                components.push(component);
+                walk(child, rest, components, visit)?;
+                components.pop();
+            }
+        }
+        Segment::Descendants => {
+            walk(setting.reborrow(), rest, components, visit)?;
+            for (component, child) in children(setting) {
+                components.push(component);
+                walk(child, segments, components, visit)?;
+                components.pop();
+            }
+        }
+        Segment::Filter(filter) => {
+            for (component, mut child) in children(setting) {
+                if filter.matches(child.reborrow())? {
+                    components.push(component);
+                    walk(child, rest, components, visit)?;
+                    components.pop();
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+impl Filter {
+    fn matches(&self, setting: Setting) -> Result<bool, LibconfigError> {
+        let Ok(mut target) = path::resolve(setting, &self.path) else {
+            return Ok(false);
+        };
+        let Some((op, literal)) = &self.test else {
+            return Ok(true);
+        };
+        if target.is_aggregate() {
+            return Ok(*op == Op::Ne);
+        }
+        let ordering = compare(&Value::from_setting(&mut target)?, literal);
+        Ok(match op {
+            Op::Eq => ordering == Some(Ordering::Equal),
+            Op::Ne => ordering != Some(Ordering::Equal),
+            Op::Lt => ordering == Some(Ordering::Less),
+            Op::Le => matches!(ordering, Some(Ordering::Less | Ordering::Equal)),
+            Op::Gt => ordering == Some(Ordering::Greater),
+            Op::Ge => matches!(ordering, Some(Ordering::Greater | Ordering::Equal)),
+        })
+    }
+}
+
+fn compare(lhs: &Value, rhs: &Value) -> Option<Ordering> {
+    let int = |value: &Value| match value {
+        Value::Int(val) => Some(*val as i64),
+        Value::Int64(val) => Some(*val),
+        _ => None,
+    };
+    let float = |value: &Value| match value {
+        Value::Float(val) => Some(*val),
+        value => int(value).map(|val| val as f64),
+    };
+    match (lhs, rhs) {
+        (Value::Bool(lhs), Value::Bool(rhs)) => Some(lhs.cmp(rhs)),
+        (Value::String(lhs), Value::String(rhs)) => Some(lhs.cmp(rhs)),
+        (lhs, rhs) => match (int(lhs), int(rhs)) {
+            (Some(lhs), Some(rhs)) => Some(lhs.cmp(&rhs)),
+            _ => float(lhs)?.partial_cmp(&float(rhs)?),
+        },
+    }
+}
+
+fn children(setting: Setting) -> impl Iterator<Item = (Component, Setting)> {
+    setting.into_iter().enumerate().map(|(idx, child)| {
+        let component = match child.get_name() {
+            Some(name) => Component::Name(name.to_string()),
+            None => Component::Index(idx),
+        };
+        (component, child)
+    })
+}
+
+fn parse(query: &str) -> Result<Vec<Segment>, LibconfigError> {
+    let mut parser = Parser { query, pos: 0 };
+    let mut segments = Vec::new();
+    if query.is_empty() {
+        return Ok(segments);
+    }
+    loop {
+        segments.push(parser.segment()?);
+        match parser.peek() {
+            None => return Ok(segments),
+            Some(c) if path::SEPARATORS.contains(&c) => parser.pos += 1,
+            Some(_) => return Err(parser.error("expected `.`")),
+        }
+    }
+}
+
+struct Parser<'a> {
+    query: &'a str,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn rest(&self) -> &'a str {
+        &self.query[self.pos..]
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    fn error(&self, message: &str) -> LibconfigError {
+        LibconfigError::Query {
+            column: self.query[..self.pos].chars().count() + 1,
+            message: message.to_string(),
+        }
+    }
+
+    fn skip_spaces(&mut self) {
+        self.pos += self.rest().len() - self.rest().trim_start().len();
+    }
+
+    fn segment(&mut self) -> Result<Segment, LibconfigError> {
+        let rest = self.rest();
+        let ends = |len: usize| {
+            rest[len..]
+                .chars()
+                .next()
+                .is_none_or(|c| path::SEPARATORS.contains(&c))
+        };
+        if rest.starts_with("**") && ends(2) {
+            self.pos += 2;
+            Ok(Segment::Descendants)
+        } else if (rest.starts_with('*') && ends(1)) || (rest.starts_with("[*]") && ends(3)) {
+            self.pos += if rest.starts_with('*') { 1 } else { 3 };
+            Ok(Segment::Children)
+        } else if rest.starts_with("[?(") {
+            self.pos += 3;
+            let filter = self.filter()?;
+            match self.rest().starts_with(")]") {
+                true => self.pos += 2,
+                false => return Err(self.error("expected `)]`")),
+            }
+            Ok(Segment::Filter(filter))
+        } else if let Some(index) = rest.strip_prefix('[') {
+            let len = index.find(']').unwrap_or(index.len());
+            match index[..len].parse() {
+                Ok(idx) if len < index.len() => {
+                    self.pos += len + 2;
+                    Ok(Segment::Exact(Component::Index(idx)))
+                }
+                _ => {
+                    self.pos += 1;
+                    Err(self.error("expected index, `*` or filter"))
+                }
+            }
+        } else {
+            let len = rest.find(path::SEPARATORS).unwrap_or(rest.len());
+            let name = &rest[..len];
+            if let Some(pos) =
+                name.find(|c: char| !(c.is_ascii_alphanumeric() || "-_*".contains(c)))
+            {
+                self.pos += pos;
+                return Err(self.error("invalid character in setting name"));
+            }
+            if name.is_empty() {
+                return Err(self.error("expected setting name"));
+            }
+            self.pos += len;
+            Ok(Segment::Exact(Component::Name(name.to_string())))
+        }
+    }
+
+    fn filter(&mut self) -> Result<Filter, LibconfigError> {
+        self.skip_spaces();
+        if self.peek() != Some('@') {
+            return Err(self.error("expected `@`"));
+        }
+        self.pos += 1;
+        let mut path = Vec::new();
+        if self.peek() == Some('.') {
+            self.pos += 1;
+            let start = self.pos;
+            let len = self
+                .rest()
+                .find(|c: char| c.is_whitespace() || "=!<>)".contains(c))
+                .unwrap_or(self.rest().len());
+            path = path::parse(&self.rest()[..len]).map_err(|_| self.error("invalid path"))?;
+            if path.is_empty() {
+                return Err(self.error("expected path"));
+            }
+            self.pos = start + len;
+        }
+        self.skip_spaces();
+        let op = [
+            ("==", Op::Eq),
+            ("!=", Op::Ne),
+            ("<=", Op::Le),
+            (">=", Op::Ge),
+            ("<", Op::Lt),
+            (">", Op::Gt),
+        ]
+        .into_iter()
+        .find(|(token, _)| self.rest().starts_with(token));
+        let Some((token, op)) = op else {
+            return Ok(Filter { path, test: None });
+        };
+        self.pos += token.len();
+        self.skip_spaces();
+        let literal = self.literal()?;
+        self.skip_spaces();
+        Ok(Filter {
+            path,
+            test: Some((op, literal)),
+        })
+    }
+
+    fn literal(&mut self) -> Result<Value, LibconfigError> {
+        let rest = self.rest();
+        if let Some(string) = rest.strip_prefix('"') {
+            let mut value = String::new();
+            let mut chars = string.char_indices();
+            while let Some((offset, c)) = chars.next() {
+                match c {
+                    '"' => {
+                        self.pos += offset + 2;
+                        return Ok(Value::String(value));
+                    }
+                    '\\' => match chars.next() {
+                        Some((_, c)) => value.push(c),
+                        None => break,
+                    },
+                    c => value.push(c),
+                }
+            }
+            self.pos = self.query.len();
+            return Err(self.error("unterminated string"));
+        }
+        let len = rest
+            .find(|c: char| c.is_whitespace() || c == ')')
+            .unwrap_or(rest.len());
+        let token = &rest[..len];
+        let value = match token {
+            "true" => Value::Bool(true),
+            "false" => Value::Bool(false),
+            token => match (token.parse::<i64>(), token.parse::<f64>()) {
+                (Ok(val), _) => match i32::try_from(val) {
+                    Ok(val) => Value::Int(val),
+                    Err(_) => Value::Int64(val),
+                },
+                (_, Ok(val)) => Value::Float(val),
+                _ => return Err(self.error("expected boolean, number or string")),
+            },
+        };
+        self.pos += len;
+        Ok(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Type;
+
+    const SERVERS: &str = "
+servers = (
+  { name = \"a\"; port = 80; enabled = true; timeout = 5; },
+  { name = \"b\"; port = 8080; enabled = false; },
+  { name = \"c\"; port = 443; enabled = true; }
+);
+client = { timeout = 1.5; retry = { timeout = 3; }; };
+";
+
+    fn config() -> Config {
+        let mut cfg = Config::new();
+        assert_eq!(cfg.read_str(SERVERS), Ok(()));
+        cfg
+    }
+
+    fn paths(cfg: &Config, query: &str) -> Vec<String> {
+        cfg.query(query).unwrap()
+    }
+
+    #[test]
+    fn ok_on_wildcard_query() {
+        let cfg = config();
+        assert_eq!(
+            cfg.query_values::<i32>("servers.[*].port"),
+            Ok(vec![
+                (String::from("servers.[0].port"), 80),
+                (String::from("servers.[1].port"), 8080),
+                (String::from("servers.[2].port"), 443),
+            ])
+        );
+        assert_eq!(paths(&cfg, "client.*"), ["client.timeout", "client.retry"]);
+        assert_eq!(paths(&cfg, "servers/[1]:port"), ["servers.[1].port"]);
+    }
+
+    #[test]
+    fn ok_on_descendant_query() {
+        let cfg = config();
+        assert_eq!(
+            paths(&cfg, "**.timeout"),
+            [
+                "servers.[0].timeout",
+                "client.timeout",
+                "client.retry.timeout"
+            ]
+        );
+    }
+
+    #[test]
+    fn ok_on_nested_descendant_matches() {
+        let cfg = config();
+        let nested = [
+            "client",
+            "client.timeout",
+            "client.retry",
+            "client.retry.timeout",
+        ];
+        assert_eq!(paths(&cfg, "client.**"), nested);
+        assert_eq!(paths(&cfg, "client.**.**"), nested);
+        let values = cfg.query_values::<Value>("client.**").unwrap();
+        assert_eq!(
+            values[2].1,
+            Value::Group(vec![(String::from("timeout"), Value::Int(3))])
+        );
+        assert_eq!(values[3].1, Value::Int(3));
+    }
+
+    #[test]
+    fn ok_on_filter_query() {
+        let cfg = config();
+        assert_eq!(
+            cfg.query_values::<String>("servers.[?(@.enabled == true)].name"),
+            Ok(vec![
+                (String::from("servers.[0].name"), String::from("a")),
+                (String::from("servers.[2].name"), String::from("c")),
+            ])
+        );
+        assert_eq!(
+            paths(&cfg, "servers.[?(@.port >= 443)]"),
+            ["servers.[1]", "servers.[2]"]
+        );
+        assert_eq!(paths(&cfg, "servers.[?(@.timeout)]"), ["servers.[0]"]);
+        assert_eq!(paths(&cfg, "servers.[?(@.name != \"b\")].port").len(), 2);
+    }
+
+    #[test]
+    fn ok_on_query_with_settings() {
+        let cfg = config();
+        let mut found = Vec::new();
+        let result = cfg.query_with("servers.[?(@.enabled == true)]", |path, mut setting| {
+            let port = setting.lookup_i32("port").ok_or(LibconfigError::Invalid)?;
+            found.push((path.to_string(), setting.get_type(), port));
+            Ok(())
+        });
+        assert_eq!(result, Ok(()));
+        assert_eq!(
+            found,
+            [
+                (String::from("servers.[0]"), Type::TypeGroup, 80),
+                (String::from("servers.[2]"), Type::TypeGroup, 443),
+            ]
+        );
+
+        let mut calls = 0;
+        let result = cfg.query_with("**.timeout", |_, _| {
+            calls += 1;
+            Err(LibconfigError::Invalid)
+        });
+        assert_eq!((result, calls), (Err(LibconfigError::Invalid), 1));
+    }
+
+    #[test]
+    fn ok_on_no_match() {
+        let cfg = config();
+        assert!(cfg.query("servers.[5].port").unwrap().is_empty());
+        assert!(cfg.query("missing.**").unwrap().is_empty());
+    }
+
+    #[test]
+    fn err_on_invalid_query() {
+        let column = |query: &str| match parse(query) {
+            Err(LibconfigError::Query { column, .. }) => Some(column),
+            _ => None,
+        };
+        assert_eq!(column("servers..port"), Some(9));
+        assert_eq!(column("servers.[x].port"), Some(10));
+        assert_eq!(column("servers.[?(enabled)]"), Some(12));
+        assert_eq!(column("servers.[?(@.port ~ 1)]"), Some(19));
+        assert_eq!(column("a.b c"), Some(4));
+        assert_eq!(column("servers.[?(@.name == \"x)]"), Some(26));
+    }
+
+    #[test]
+    fn ok_on_parse_query() {
+        assert_eq!(
+            parse("a.[1].*.**.[?(@.x.[0] < -2.5)]"),
+            Ok(vec![
+                Segment::Exact(Component::Name(String::from("a"))),
+                Segment::Exact(Component::Index(1)),
+                Segment::Children,
+                Segment::Descendants,
+                Segment::Filter(Filter {
+                    path: vec![Component::Name(String::from("x")), Component::Index(0)],
+                    test: Some((Op::Lt, Value::Float(-2.5))),
+                }),
+            ])
+        );
+    }
+}
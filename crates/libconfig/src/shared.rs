@@ -0,0 +1,183 @@
+use crate::{Config, LibconfigError, Setting, Value};
+use std::sync::{Arc, Mutex, RwLock, RwLockReadGuard};
+
+/// A config that can no longer be modified, so that any number of threads
+/// may look settings up at once. `to_value` calls take turns.
+pub struct FrozenConfig {
+    inner: Config,
+    // `to_value` walks the tree through mutable setting handles, which must
+    // not exist on two threads at once.
+    walking: Mutex<()>,
+}
+
+// libconfig++ wraps each setting in a C++ object the first time it is looked
+// up and stores it in the setting, so two threads looking up a setting for
+// the first time would race. Every setting is wrapped before a config is
+// shared (see `wrap_all`), after which lookups and `write_str` only read.
+unsafe impl Sync for FrozenConfig {}
+
+impl FrozenConfig {
+    pub fn new(cfg: Config) -> Self {
+        wrap_all(&mut cfg.get_root());
+        FrozenConfig {
+            inner: cfg,
+            walking: Mutex::new(()),
+        }
+    }
+
+    pub fn load(path: &str) -> Result<Self, LibconfigError> {
+        let mut cfg = Config::new();
+        cfg.read_file(path)?;
+        Ok(FrozenConfig::new(cfg))
+    }
+
+    pub fn into_inner(self) -> Config {
+        self.inner
+    }
+
+    pub fn exists(&self, path: &str) -> bool {
+        self.inner.exists(path)
+    }
+
+    pub fn lookup_bool(&self, path: &str) -> Option<bool> {
+        self.inner.lookup_bool(path)
+    }
+
+    pub fn lookup_i32(&self, path: &str) -> Option<i32> {
+        self.inner.lookup_i32(path)
+    }
+
+    pub fn lookup_i64(&self, path: &str) -> Option<i64> {
+        self.inner.lookup_i64(path)
+    }
+
+    pub fn lookup_f32(&self, path: &str) -> Option<f32> {
+        self.inner.lookup_f32(path)
+    }
+
+    pub fn lookup_f64(&self, path: &str) -> Option<f64> {
+        self.inner.lookup_f64(path)
+    }
+
+    pub fn lookup_string(&self, path: &str) -> Option<String> {
+        self.inner.lookup_string(path)
+    }
+
+    pub fn to_value(&self) -> Result<Value, LibconfigError> {
+        let _walking = self.walking.lock().unwrap();
+        self.inner.to_value()
+    }
+
+    pub fn write_str(&self) -> Result<String, LibconfigError> {
+        self.inner.write_str()
+    }
+}
+
+impl From<Config> for FrozenConfig {
+    fn from(cfg: Config) -> Self {
+        FrozenConfig::new(cfg)
+    }
+}
+
+/// A config shared between threads. Lookups run concurrently under a read
+/// lock; `update` waits for them and edits the config exclusively.
+#[derive(Clone)]
+pub struct SharedConfig {
+    inner: Arc<RwLock<FrozenConfig>>,
+}
+
+impl SharedConfig {
+    pub fn new(cfg: Config) -> Self {
+        SharedConfig {
+            inner: Arc::new(RwLock::new(FrozenConfig::new(cfg))),
+        }
+    }
+
+    pub fn read(&self) -> RwLockReadGuard<'_, FrozenConfig> {
+        self.inner.read().unwrap()
+    }
+
+    /// Edits the config while no one reads it. Settings added by `edit` are
+    /// wrapped before readers are let back in, which takes time in
+    /// proportion to the config.
+    pub fn update<R>(&self, edit: impl FnOnce(&mut Config) -> R) -> R {
+        let mut frozen = self.inner.write().unwrap();
+        let result = edit(&mut frozen.inner);
+        wrap_all(&mut frozen.inner.get_root());
+        result
+    }
+}
+
+// Visits every setting once, which makes libconfig++ create the wrapper
+// objects it would otherwise create lazily during lookups.
+fn wrap_all(setting: &mut Setting) {
+    if setting.is_aggregate() {
+        for mut child in setting.iter() {
+            wrap_all(&mut child);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    fn assert_send<T: Send>() {}
+    fn assert_sync<T: Sync>() {}
+
+    #[test]
+    fn ok_on_thread_safety_markers() {
+        assert_send::<Config>();
+        assert_send::<FrozenConfig>();
+        assert_sync::<FrozenConfig>();
+        assert_send::<SharedConfig>();
+        assert_sync::<SharedConfig>();
+    }
+
+    #[test]
+    fn ok_on_parallel_frozen_lookups() {
+        let cfg = FrozenConfig::load("../input/test.cfg").unwrap();
+        thread::scope(|scope| {
+            for _ in 0..8 {
+                scope.spawn(|| {
+                    for _ in 0..1000 {
+                        assert_eq!(cfg.lookup_string("name"), Some(String::from("Some Name")));
+                        assert_eq!(cfg.lookup_i32("val_int"), Some(42));
+                        assert_eq!(cfg.lookup_i64("val_u64"), Some(0xFFFFFFFFFF));
+                        assert_eq!(cfg.lookup_f64("some_f64"), Some(1e10));
+                        assert_eq!(cfg.lookup_i32("outer.inner"), Some(3));
+                        assert_eq!(cfg.lookup_i32("arr.[2]"), Some(8));
+                        assert!(!cfg.exists("missing"));
+                    }
+                    assert!(cfg.to_value().is_ok());
+                });
+            }
+        });
+    }
+
+    #[test]
+    fn ok_on_parallel_shared_lookups_and_updates() {
+        let mut cfg = Config::new();
+        assert_eq!(cfg.read_str("counter = 0; name = \"x\";"), Ok(()));
+        let shared = SharedConfig::new(cfg);
+        thread::scope(|scope| {
+            for _ in 0..8 {
+                let shared = shared.clone();
+                scope.spawn(move || {
+                    for _ in 0..1000 {
+                        let cfg = shared.read();
+                        assert!(cfg.lookup_i32("counter").is_some());
+                        assert_eq!(cfg.lookup_string("name"), Some(String::from("x")));
+                    }
+                });
+            }
+            scope.spawn(|| {
+                for val in 1..=100 {
+                    shared.update(|cfg| cfg.lookup("counter").unwrap().set_i32(val).unwrap());
+                }
+            });
+        });
+        assert_eq!(shared.read().lookup_i32("counter"), Some(100));
+    }
+}
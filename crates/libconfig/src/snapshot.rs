@@ -0,0 +1,157 @@
+use crate::Format;
+use crate::{FromConfig, LibconfigError, Setting, Type, Value};
+
+// Detached copy of a setting that, unlike `Value`, keeps names and formats so
+// that it can be written back without losing anything.
+#[derive(Debug, Clone)]
+pub(crate) struct Snapshot {
+    name: Option<String>,
+    format: Format,
+    data: Data,
+}
+
+#[derive(Debug, Clone)]
+enum Data {
+    Scalar(Value),
+    Aggregate(Type, Vec<Snapshot>),
+}
+
+impl Snapshot {
+    pub(crate) fn capture(setting: &mut Setting) -> Result<Self, LibconfigError> {
+        let name = setting.get_name().map(str::to_string);
+        let format = setting.get_format();
+        let data = match setting.is_aggregate() {
+            true => Data::Aggregate(
+                setting.get_type(),
+                setting
+                    .iter()
+                    .map(|mut child| Snapshot::capture(&mut child))
+                    .collect::<Result<_, _>>()?,
+            ),
+            false => Data::Scalar(Value::from_setting(setting)?),
+        };
+        Ok(Snapshot { name, format, data })
+    }
+
+    pub(crate) fn from_value(name: Option<String>, value: &Value) -> Self {
+        let data = match value {
+            Value::Group(children) => Data::Aggregate(
+                Type::TypeGroup,
+                children
+                    .iter()
+                    .map(|(name, child)| Snapshot::from_value(Some(name.clone()), child))
+                    .collect(),
+            ),
+            Value::Array(elems) | Value::List(elems) => Data::Aggregate(
+                value.get_type(),
+                elems
+                    .iter()
+                    .map(|elem| Snapshot::from_value(None, elem))
+                    .collect(),
+            ),
+            scalar => Data::Scalar(scalar.clone()),
+        };
+        Snapshot {
+            name,
+            format: Format::FormatDefault,
+            data,
+        }
+    }
+
+    pub(crate) fn with_name(self, name: Option<String>) -> Self {
+        Snapshot { name, ..self }
+    }
+
+    pub(crate) fn with_format(self, format: Format) -> Self {
+        Snapshot { format, ..self }
+    }
+
+    pub(crate) fn get_type(&self) -> Type {
+        match &self.data {
+            Data::Scalar(value) => value.get_type(),
+            Data::Aggregate(setting_type, _) => *setting_type,
+        }
+    }
+
+    // Appends the snapshot as the last child of `parent`.
+    pub(crate) fn restore(&self, parent: &mut Setting) -> Result<(), LibconfigError> {
+        let mut setting = match (&self.name, parent.is_group()) {
+            (Some(name), true) => parent.add(name, self.get_type())?,
+            (None, true) => return Err(LibconfigError::Invalid),
+            (_, false) => parent.push(self.get_type())?,
+        };
+        self.write_into(&mut setting)
+    }
+
+    // Writes the snapshot's contents into a setting which already has the
+    // snapshot's type and no children.
+    pub(crate) fn write_into(&self, setting: &mut Setting) -> Result<(), LibconfigError> {
+        match &self.data {
+            Data::Scalar(value) => set_scalar(setting, value)?,
+            Data::Aggregate(_, children) => {
+                for child in children {
+                    child.restore(setting)?;
+                }
+            }
+        }
+        if self.format != Format::FormatDefault {
+            setting.set_format(self.format);
+        }
+        Ok(())
+    }
+
+    // Replaces all children of `setting` with the children captured in the snapshot.
+    pub(crate) fn restore_children(&self, setting: &mut Setting) -> Result<(), LibconfigError> {
+        clear(setting)?;
+        self.write_into(setting)
+    }
+}
+
+pub(crate) fn set_scalar(setting: &mut Setting, value: &Value) -> Result<(), LibconfigError> {
+    match value {
+        Value::Bool(val) => setting.set_bool(*val),
+        Value::Int(val) => setting.set_i32(*val),
+        Value::Int64(val) => setting.set_i64(*val),
+        Value::Float(val) => setting.set_f64(*val),
+        Value::String(val) => setting.set_str(val),
+        _ => Err(LibconfigError::TypeMismatch(setting.get_path())),
+    }
+}
+
+pub(crate) fn clear(setting: &mut Setting) -> Result<(), LibconfigError> {
+    while setting.get_length()? > 0 {
+        setting.remove_idx(0)?;
+    }
+    Ok(())
+}
+
+// Inserts the snapshot as child `idx` of `parent`, shifting the children at
+// and after `idx` back by one. libconfig can only append, so those children
+// are captured, removed and restored: handles to them, or to anything below
+// them, are left dangling.
+pub(crate) fn insert_at(
+    parent: &mut Setting,
+    idx: usize,
+    snapshot: &Snapshot,
+) -> Result<(), LibconfigError> {
+    let length = parent.get_length()? as usize;
+    if idx > length {
+        return Err(LibconfigError::OutOfRange(crate::traits::child_path(
+            parent,
+            &format!("[{}]", idx),
+        )));
+    }
+    let trailing = parent
+        .iter()
+        .skip(idx)
+        .map(|mut child| Snapshot::capture(&mut child))
+        .collect::<Result<Vec<_>, _>>()?;
+    for _ in idx..length {
+        parent.remove_idx(idx)?;
+    }
+    snapshot.restore(parent)?;
+    for child in &trailing {
+        child.restore(parent)?;
+    }
+    Ok(())
+}
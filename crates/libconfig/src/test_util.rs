@@ -0,0 +1,53 @@
+//! Helpers shared by the unit tests.
+
+use crate::Config;
+use std::fs;
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+static SCRATCH_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// A config read from `input`, which must be valid.
+pub fn config(input: &str) -> Config {
+    let mut cfg = Config::new();
+    assert_eq!(cfg.read_str(input), Ok(()));
+    cfg
+}
+
+/// An empty directory of its own for a test, removed again when dropped,
+/// including when the test panics.
+pub struct ScratchDir(PathBuf);
+
+impl Deref for ScratchDir {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl AsRef<Path> for ScratchDir {
+    fn as_ref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for ScratchDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+pub fn scratch_dir(name: &str) -> ScratchDir {
+    let dir = std::env::temp_dir().join(format!(
+        "libconfig-{}-{}-{}",
+        name,
+        process::id(),
+        SCRATCH_COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    ScratchDir(dir)
+}
@@ -0,0 +1,258 @@
+use crate::backend::{
+    tryBoolFromSetting, tryF32FromSetting, tryF64FromSetting, tryI32FromSetting, tryI64FromSetting,
+    tryStringFromSetting,
+};
+use crate::{Format, LibconfigError, Setting, Type, Value};
+
+/// Reads a value from a setting, usually via `#[derive(FromConfig)]`.
+pub trait FromConfig: Sized {
+    fn from_setting(setting: &mut Setting) -> Result<Self, LibconfigError>;
+
+    fn from_missing(path: &str) -> Result<Self, LibconfigError> {
+        Err(LibconfigError::Missing(path.to_string()))
+    }
+}
+
+/// Writes a value into a setting, usually via `#[derive(ToConfig)]`.
+pub trait ToConfig {
+    fn setting_type() -> Type;
+
+    fn to_setting(&self, setting: &mut Setting) -> Result<(), LibconfigError>;
+
+    fn is_present(&self) -> bool {
+        true
+    }
+}
+
+pub fn child_path(setting: &Setting, name: &str) -> String {
+    crate::path::append(&setting.get_path(), name)
+}
+
+pub fn read_field<T: FromConfig>(setting: &mut Setting, name: &str) -> Result<T, LibconfigError> {
+    if !setting.exists(name) {
+        return T::from_missing(&child_path(setting, name));
+    }
+    let mut child = setting.lookup(name)?;
+    T::from_setting(&mut child)
+}
+
+pub fn read_field_or<T: FromConfig>(
+    setting: &mut Setting,
+    name: &str,
+    default: impl FnOnce() -> T,
+) -> Result<T, LibconfigError> {
+    if !setting.exists(name) {
+        return Ok(default());
+    }
+    let mut child = setting.lookup(name)?;
+    T::from_setting(&mut child)
+}
+
+pub fn write_field<T: ToConfig>(
+    setting: &mut Setting,
+    name: &str,
+    value: &T,
+    format: Format,
+) -> Result<(), LibconfigError> {
+    let setting_type = T::setting_type();
+    if setting.exists(name)
+        && (!value.is_present() || !same_kind(setting.lookup(name)?.get_type(), setting_type))
+    {
+        setting.remove(name)?;
+    }
+    if !value.is_present() {
+        return Ok(());
+    }
+    let mut child = match setting.exists(name) {
+        true => setting.lookup(name)?,
+        false => setting.add(name, setting_type)?,
+    };
+    value.to_setting(&mut child)?;
+    if format != Format::FormatDefault {
+        child.set_format(format);
+    }
+    Ok(())
+}
+
+// An existing integer setting is written in place whatever its width, so
+// that `port = 80;` is not rewritten as `port = 80L;`.
+fn same_kind(existing: Type, wanted: Type) -> bool {
+    let integer = |t: Type| matches!(t, Type::TypeInt | Type::TypeInt64);
+    existing == wanted || (integer(existing) && integer(wanted))
+}
+
+// libconfig++ only reads an int setting as a 64-bit integer with
+// `OptionAutoConvert`, but every int fits.
+fn read_integer(setting: &Setting) -> Result<i64, LibconfigError> {
+    let read = match setting.get_type() {
+        Type::TypeInt => unsafe { tryI32FromSetting(&setting.inner) }.map(i64::from),
+        _ => unsafe { tryI64FromSetting(&setting.inner) },
+    };
+    read.map_err(|_| LibconfigError::TypeMismatch(setting.get_path()))
+}
+
+macro_rules! impl_scalar {
+    ($ty:ty, $setting_type:ident, $read:ident, $write:ident) => {
+        impl FromConfig for $ty {
+            fn from_setting(setting: &mut Setting) -> Result<Self, LibconfigError> {
+                unsafe { $read(&setting.inner) }
+                    .map_err(|_| LibconfigError::TypeMismatch(setting.get_path()))
+            }
+        }
+
+        impl ToConfig for $ty {
+            fn setting_type() -> Type {
+                Type::$setting_type
+            }
+
+            fn to_setting(&self, setting: &mut Setting) -> Result<(), LibconfigError> {
+                setting
+                    .$write(*self)
+                    .map_err(|_| LibconfigError::TypeMismatch(setting.get_path()))
+            }
+        }
+    };
+}
+
+impl_scalar!(bool, TypeBoolean, tryBoolFromSetting, set_bool);
+impl_scalar!(f32, TypeFloat, tryF32FromSetting, set_f32);
+impl_scalar!(f64, TypeFloat, tryF64FromSetting, set_f64);
+
+// Integers are read from and written to settings of either width; `set_any`
+// widens an int setting when the value does not fit.
+macro_rules! impl_integer {
+    ($ty:ty, $setting_type:ident, $value:ident) => {
+        impl FromConfig for $ty {
+            fn from_setting(setting: &mut Setting) -> Result<Self, LibconfigError> {
+                <$ty>::try_from(read_integer(setting)?)
+                    .map_err(|_| LibconfigError::OutOfRange(setting.get_path()))
+            }
+        }
+
+        impl ToConfig for $ty {
+            fn setting_type() -> Type {
+                Type::$setting_type
+            }
+
+            fn to_setting(&self, setting: &mut Setting) -> Result<(), LibconfigError> {
+                setting
+                    .set_any(&Value::$value(*self))
+                    .map_err(|_| LibconfigError::TypeMismatch(setting.get_path()))
+            }
+        }
+    };
+}
+
+impl_integer!(i32, TypeInt, Int);
+impl_integer!(i64, TypeInt64, Int64);
+
+impl FromConfig for String {
+    fn from_setting(setting: &mut Setting) -> Result<Self, LibconfigError> {
+        match unsafe { tryStringFromSetting(&setting.inner) } {
+            Ok(val) => Ok(val.to_string()),
+            Err(_) => Err(LibconfigError::TypeMismatch(setting.get_path())),
+        }
+    }
+}
+
+impl ToConfig for String {
+    fn setting_type() -> Type {
+        Type::TypeString
+    }
+
+    fn to_setting(&self, setting: &mut Setting) -> Result<(), LibconfigError> {
+        setting
+            .set_str(self)
+            .map_err(|_| LibconfigError::TypeMismatch(setting.get_path()))
+    }
+}
+
+macro_rules! impl_narrow_int {
+    ($ty:ty, $wide:ty, $setting_type:ident) => {
+        impl FromConfig for $ty {
+            fn from_setting(setting: &mut Setting) -> Result<Self, LibconfigError> {
+                let wide = <$wide>::from_setting(setting)?;
+                <$ty>::try_from(wide).map_err(|_| LibconfigError::OutOfRange(setting.get_path()))
+            }
+        }
+
+        impl ToConfig for $ty {
+            fn setting_type() -> Type {
+                Type::$setting_type
+            }
+
+            fn to_setting(&self, setting: &mut Setting) -> Result<(), LibconfigError> {
+                <$wide>::try_from(*self)
+                    .map_err(|_| LibconfigError::OutOfRange(setting.get_path()))?
+                    .to_setting(setting)
+            }
+        }
+    };
+}
+
+impl_narrow_int!(i8, i32, TypeInt);
+impl_narrow_int!(i16, i32, TypeInt);
+impl_narrow_int!(u8, i32, TypeInt);
+impl_narrow_int!(u16, i32, TypeInt);
+impl_narrow_int!(u32, i64, TypeInt64);
+impl_narrow_int!(u64, i64, TypeInt64);
+impl_narrow_int!(usize, i64, TypeInt64);
+
+impl<T: FromConfig> FromConfig for Option<T> {
+    fn from_setting(setting: &mut Setting) -> Result<Self, LibconfigError> {
+        T::from_setting(setting).map(Some)
+    }
+
+    fn from_missing(_path: &str) -> Result<Self, LibconfigError> {
+        Ok(None)
+    }
+}
+
+impl<T: ToConfig> ToConfig for Option<T> {
+    fn setting_type() -> Type {
+        T::setting_type()
+    }
+
+    fn to_setting(&self, setting: &mut Setting) -> Result<(), LibconfigError> {
+        match self {
+            Some(value) => value.to_setting(setting),
+            None => Ok(()),
+        }
+    }
+
+    fn is_present(&self) -> bool {
+        self.is_some()
+    }
+}
+
+impl<T: FromConfig> FromConfig for Vec<T> {
+    fn from_setting(setting: &mut Setting) -> Result<Self, LibconfigError> {
+        if !setting.is_array() && !setting.is_list() {
+            return Err(LibconfigError::TypeMismatch(setting.get_path()));
+        }
+        setting
+            .iter()
+            .map(|mut elem| T::from_setting(&mut elem))
+            .collect()
+    }
+}
+
+impl<T: ToConfig> ToConfig for Vec<T> {
+    fn setting_type() -> Type {
+        match T::setting_type() {
+            Type::TypeGroup | Type::TypeArray | Type::TypeList => Type::TypeList,
+            _ => Type::TypeArray,
+        }
+    }
+
+    fn to_setting(&self, setting: &mut Setting) -> Result<(), LibconfigError> {
+        while setting.get_length()? > 0 {
+            setting.remove_idx(0)?;
+        }
+        for value in self {
+            let mut elem = setting.push(T::setting_type())?;
+            value.to_setting(&mut elem)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,288 @@
+use crate::path;
+use crate::snapshot::{self, Snapshot};
+use crate::traits::child_path;
+use crate::{LibconfigError, Setting};
+use std::cmp::Ordering;
+
+impl<'a> Setting<'a> {
+    /// Renames a setting in a group, keeping its value, format and index.
+    ///
+    /// libconfig cannot rename in place, so the setting and the siblings
+    /// after it are re-created: `self` is updated, but any other handle to
+    /// them, or to anything below them, is left dangling.
+    pub fn rename(&mut self, new_name: &str) -> Result<(), LibconfigError> {
+        if self.get_name() == Some(new_name) {
+            return Ok(());
+        }
+        let idx = self.get_index().ok_or(LibconfigError::Invalid)?;
+        let mut parent = self.detached_parent()?;
+        if !parent.is_group() || !is_valid_name(new_name) || parent.exists(new_name) {
+            return Err(LibconfigError::Invalid);
+        }
+        let snapshot = Snapshot::capture(self)?.with_name(Some(new_name.to_string()));
+        parent.remove_idx(idx)?;
+        snapshot::insert_at(&mut parent, idx, &snapshot)?;
+        self.inner = unsafe { Self::extend(parent.get_elem(idx)?.inner) };
+        Ok(())
+    }
+
+    /// Moves the setting to position `index` of the group, array or list at
+    /// `new_parent_path`, which is relative to the root.
+    ///
+    /// Settings moved into a group keep their name; elements of arrays and
+    /// lists have none, so they cannot be moved into a group.
+    ///
+    /// Only `self` follows the move. Other handles to the moved setting, to
+    /// its descendants, or to the siblings after it in either parent are
+    /// invalidated, since those settings are removed and re-created.
+    pub fn move_to(&mut self, new_parent_path: &str, index: usize) -> Result<(), LibconfigError> {
+        let idx = self.get_index().ok_or(LibconfigError::Invalid)?;
+        let source = path::parse(&self.get_path())?;
+        let target = path::parse(new_parent_path)?;
+        if target.starts_with(&source) {
+            return Err(LibconfigError::Invalid);
+        }
+        let same_parent = target[..] == source[..source.len() - 1];
+        let mut dest = path::resolve(self.root()?, &target)?;
+        if !dest.is_aggregate() {
+            return Err(LibconfigError::TypeMismatch(new_parent_path.to_string()));
+        }
+        let length = dest.get_length()? as usize - same_parent as usize;
+        if index > length {
+            return Err(LibconfigError::OutOfRange(child_path(
+                &dest,
+                &format!("[{}]", index),
+            )));
+        }
+        let name = match (dest.is_group(), self.get_name()) {
+            (true, Some(name)) if same_parent || !dest.exists(name) => Some(name.to_string()),
+            (true, _) => return Err(LibconfigError::Invalid),
+            (false, _) => None,
+        };
+        if dest.is_array()
+            && !same_parent
+            && (self.is_aggregate()
+                || (length > 0 && dest.get_elem(0)?.get_type() != self.get_type()))
+        {
+            return Err(LibconfigError::TypeMismatch(self.get_path()));
+        }
+        let snapshot = Snapshot::capture(self)?.with_name(name);
+        match same_parent {
+            true => dest.remove_idx(idx)?,
+            false => self.detached_parent()?.remove_idx(idx)?,
+        }
+        snapshot::insert_at(&mut dest, index, &snapshot)?;
+        self.inner = unsafe { Self::extend(dest.get_elem(index)?.inner) };
+        Ok(())
+    }
+
+    /// Swaps children `i` and `j` of a group, array or list.
+    ///
+    /// All children are re-created in their new order, so handles to any of
+    /// them or their descendants must not be used afterwards.
+    pub fn swap(&mut self, i: usize, j: usize) -> Result<(), LibconfigError> {
+        let length = self.get_length()? as usize;
+        if let Some(idx) = [i, j].into_iter().find(|idx| *idx >= length) {
+            return Err(LibconfigError::OutOfRange(child_path(
+                self,
+                &format!("[{}]", idx),
+            )));
+        }
+        let mut order = (0..length).collect::<Vec<_>>();
+        order.swap(i, j);
+        self.reorder(&order)
+    }
+
+    /// Stable-sorts the children of a group, array or list.
+    ///
+    /// As with `swap`, the children are rebuilt, which invalidates every
+    /// handle to them or to settings below them.
+    pub fn sort_children_by<F>(&mut self, mut compare: F) -> Result<(), LibconfigError>
+    where
+        F: FnMut(&mut Setting, &mut Setting) -> Ordering,
+    {
+        let mut children = self.iter().collect::<Vec<_>>();
+        let mut order = (0..children.len()).collect::<Vec<_>>();
+        order.sort_by(|&a, &b| match a.cmp(&b) {
+            Ordering::Equal => Ordering::Equal,
+            Ordering::Less => {
+                let (head, tail) = children.split_at_mut(b);
+                compare(&mut head[a], &mut tail[0])
+            }
+            Ordering::Greater => {
+                let (head, tail) = children.split_at_mut(a);
+                compare(&mut tail[0], &mut head[b])
+            }
+        });
+        drop(children);
+        self.reorder(&order)
+    }
+
+    // Rebuilds the children so that child `order[n]` ends up at index `n`.
+    fn reorder(&mut self, order: &[usize]) -> Result<(), LibconfigError> {
+        if order.iter().enumerate().all(|(pos, idx)| pos == *idx) {
+            return Ok(());
+        }
+        let children = self
+            .iter()
+            .map(|mut child| Snapshot::capture(&mut child))
+            .collect::<Result<Vec<_>, _>>()?;
+        snapshot::clear(self)?;
+        for idx in order {
+            children[*idx].restore(self)?;
+        }
+        Ok(())
+    }
+
+    fn root(&mut self) -> Result<Setting<'a>, LibconfigError> {
+        let mut root = Setting {
+            inner: unsafe { Self::extend(self.inner.as_mut()) },
+        };
+        while !root.is_root() {
+            root = root.detached_parent()?;
+        }
+        Ok(root)
+    }
+}
+
+// Setting names as accepted by libconfig's parser.
+fn is_valid_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '*')
+        && chars.all(|c| c.is_ascii_alphanumeric() || "-_*".contains(c))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Config, Format, FromConfig, LibconfigError, Setting, Type};
+
+    const TREE: &str = "
+a = 0x10;
+b = \"two\";
+grp = { x = 1; y = 2; };
+servers = (
+  { name = \"web\"; port = 8080; },
+  { name = \"db\"; port = 5432; },
+  { name = \"dns\"; port = 53; }
+);
+arr = [3, 1, 2];
+";
+
+    fn config() -> Config {
+        let mut cfg = Config::new();
+        assert_eq!(cfg.read_str(TREE), Ok(()));
+        cfg
+    }
+
+    fn names(mut setting: Setting) -> Vec<String> {
+        setting
+            .iter()
+            .map(|child| child.get_name().unwrap().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn ok_on_rename() {
+        let mut cfg = config();
+        let mut setting = cfg.lookup("a").unwrap();
+        assert_eq!(setting.rename("renamed"), Ok(()));
+        assert_eq!(setting.get_name(), Some("renamed"));
+        assert_eq!(setting.get_index(), Some(0));
+        assert_eq!(setting.get_format(), Format::FormatHex);
+        assert_eq!(cfg.lookup_i32("renamed"), Some(0x10));
+        assert!(!cfg.exists("a"));
+    }
+
+    #[test]
+    fn err_on_invalid_rename() {
+        let mut cfg = config();
+        let mut setting = cfg.lookup("a").unwrap();
+        assert_eq!(setting.rename("b"), Err(LibconfigError::Invalid));
+        assert_eq!(setting.rename("9lives"), Err(LibconfigError::Invalid));
+        let mut setting = cfg.lookup("arr.[0]").unwrap();
+        assert_eq!(setting.rename("elem"), Err(LibconfigError::Invalid));
+        assert_eq!(cfg.lookup_i32("a"), Some(0x10));
+    }
+
+    #[test]
+    fn ok_on_move_to_other_parent() {
+        let mut cfg = config();
+        let mut setting = cfg.lookup("b").unwrap();
+        assert_eq!(setting.move_to("grp", 1), Ok(()));
+        assert_eq!(setting.get_path(), "grp.b");
+        assert_eq!(setting.get_index(), Some(1));
+        assert_eq!(names(cfg.lookup("grp").unwrap()), ["x", "b", "y"]);
+        assert_eq!(cfg.lookup_string("grp.b"), Some(String::from("two")));
+        assert!(!cfg.exists("b"));
+        let mut setting = cfg.lookup("servers.[2]").unwrap();
+        assert_eq!(setting.move_to("", 0), Err(LibconfigError::Invalid));
+        assert_eq!(
+            setting.move_to("servers.[2]", 0),
+            Err(LibconfigError::Invalid)
+        );
+        assert_eq!(
+            setting.move_to("arr", 0),
+            Err(LibconfigError::TypeMismatch(String::from("servers.[2]")))
+        );
+    }
+
+    #[test]
+    fn ok_on_move_within_parent() {
+        let mut cfg = config();
+        let mut setting = cfg.lookup("a").unwrap();
+        assert_eq!(setting.move_to("", 4), Ok(()));
+        assert_eq!(setting.get_index(), Some(4));
+        assert_eq!(names(cfg.get_root()), ["b", "grp", "servers", "arr", "a"]);
+        let mut setting = cfg.lookup("a").unwrap();
+        assert!(matches!(
+            setting.move_to("", 5),
+            Err(LibconfigError::OutOfRange(_))
+        ));
+    }
+
+    #[test]
+    fn ok_on_swap() {
+        let mut cfg = config();
+        assert_eq!(cfg.lookup("servers").unwrap().swap(0, 2), Ok(()));
+        assert_eq!(
+            cfg.lookup_string("servers.[0].name"),
+            Some(String::from("dns"))
+        );
+        assert_eq!(
+            cfg.lookup_string("servers.[2].name"),
+            Some(String::from("web"))
+        );
+        assert!(matches!(
+            cfg.lookup("servers").unwrap().swap(0, 3),
+            Err(LibconfigError::OutOfRange(_))
+        ));
+    }
+
+    #[test]
+    fn ok_on_sort_children_by() {
+        let mut cfg = config();
+        let mut servers = cfg.lookup("servers").unwrap();
+        assert_eq!(
+            servers.sort_children_by(|a, b| a.lookup_i32("port").cmp(&b.lookup_i32("port"))),
+            Ok(())
+        );
+        assert_eq!(cfg.lookup_i32("servers.[0].port"), Some(53));
+        assert_eq!(cfg.lookup_i32("servers.[2].port"), Some(8080));
+        let mut root = cfg.get_root();
+        assert_eq!(
+            root.sort_children_by(|a, b| b.get_name().cmp(&a.get_name())),
+            Ok(())
+        );
+        assert_eq!(names(cfg.get_root()), ["servers", "grp", "b", "arr", "a"]);
+        assert_eq!(cfg.lookup("a").unwrap().get_format(), Format::FormatHex);
+        let mut arr = cfg.lookup("arr").unwrap();
+        assert_eq!(
+            arr.sort_children_by(|a, b| i32::from_setting(a).ok().cmp(&i32::from_setting(b).ok())),
+            Ok(())
+        );
+        assert_eq!(arr.get_type(), Type::TypeArray);
+        assert_eq!(cfg.lookup_i32("arr.[0]"), Some(1));
+    }
+}
@@ -0,0 +1,284 @@
+use crate::path;
+use crate::snapshot::Snapshot;
+use crate::{Config, FromConfig, LibconfigError, Setting, Type};
+use std::fmt;
+
+/// Owned snapshot of a setting and all of its children.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Bool(bool),
+    Int(i32),
+    Int64(i64),
+    Float(f64),
+    String(String),
+    Group(Vec<(String, Value)>),
+    Array(Vec<Value>),
+    List(Vec<Value>),
+}
+
+impl Value {
+    pub fn get_type(&self) -> Type {
+        match self {
+            Value::Bool(_) => Type::TypeBoolean,
+            Value::Int(_) => Type::TypeInt,
+            Value::Int64(_) => Type::TypeInt64,
+            Value::Float(_) => Type::TypeFloat,
+            Value::String(_) => Type::TypeString,
+            Value::Group(_) => Type::TypeGroup,
+            Value::Array(_) => Type::TypeArray,
+            Value::List(_) => Type::TypeList,
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Value> {
+        match self {
+            Value::Group(children) => children
+                .iter()
+                .find(|(child, _)| child == name)
+                .map(|(_, value)| value),
+            _ => None,
+        }
+    }
+
+    pub fn get_elem(&self, idx: usize) -> Option<&Value> {
+        match self {
+            Value::Array(elems) | Value::List(elems) => elems.get(idx),
+            _ => None,
+        }
+    }
+
+    // Every string in the tree with its path, in document order.
+    pub(crate) fn strings(&self) -> Vec<(String, &str)> {
+        let mut out = Vec::new();
+        collect_strings(self, String::new(), &mut out);
+        out
+    }
+
+    // libconfig arrays hold scalars of a single type, everything else becomes a
+    // list. Mixed 32 and 64-bit integers are widened so they still fit an array.
+    #[cfg(any(feature = "json", feature = "toml", feature = "yaml"))]
+    pub(crate) fn array_or_list(elems: Vec<Value>) -> Value {
+        let is_int = |elem: &Value| matches!(elem, Value::Int(_) | Value::Int64(_));
+        if !elems.is_empty() && elems.iter().all(is_int) {
+            if elems.iter().all(|elem| matches!(elem, Value::Int(_))) {
+                return Value::Array(elems);
+            }
+            return Value::Array(
+                elems
+                    .into_iter()
+                    .map(|elem| match elem {
+                        Value::Int(val) => Value::Int64(i64::from(val)),
+                        other => other,
+                    })
+                    .collect(),
+            );
+        }
+        let homogeneous = match elems.first() {
+            Some(first) => {
+                !matches!(first, Value::Group(_) | Value::Array(_) | Value::List(_))
+                    && elems.iter().all(|elem| elem.get_type() == first.get_type())
+            }
+            None => true,
+        };
+        match homogeneous {
+            true => Value::Array(elems),
+            false => Value::List(elems),
+        }
+    }
+}
+
+fn collect_strings<'a>(value: &'a Value, at: String, out: &mut Vec<(String, &'a str)>) {
+    match value {
+        Value::String(val) => out.push((at, val)),
+        Value::Group(children) => {
+            for (name, child) in children {
+                collect_strings(child, path::append(&at, name), out);
+            }
+        }
+        Value::Array(elems) | Value::List(elems) => {
+            for (idx, elem) in elems.iter().enumerate() {
+                collect_strings(elem, path::append(&at, &format!("[{}]", idx)), out);
+            }
+        }
+        _ => {}
+    }
+}
+
+impl FromConfig for Value {
+    fn from_setting(setting: &mut Setting) -> Result<Self, LibconfigError> {
+        Ok(match setting.get_type() {
+            Type::TypeBoolean => Value::Bool(bool::from_setting(setting)?),
+            Type::TypeInt => Value::Int(i32::from_setting(setting)?),
+            Type::TypeInt64 => Value::Int64(i64::from_setting(setting)?),
+            Type::TypeFloat => Value::Float(f64::from_setting(setting)?),
+            Type::TypeString => Value::String(String::from_setting(setting)?),
+            Type::TypeGroup => Value::Group(
+                setting
+                    .iter()
+                    .map(|mut child| {
+                        let name = child.get_name().unwrap_or_default().to_string();
+                        Ok((name, Value::from_setting(&mut child)?))
+                    })
+                    .collect::<Result<_, LibconfigError>>()?,
+            ),
+            Type::TypeArray => Value::Array(Vec::from_setting(setting)?),
+            Type::TypeList => Value::List(Vec::from_setting(setting)?),
+            _ => return Err(LibconfigError::TypeMismatch(setting.get_path())),
+        })
+    }
+}
+
+// Formats values in libconfig syntax, so the output can be read back.
+// libconfig has no literal for infinity but reads one that overflows as such;
+// NaN cannot be written at all and comes out as `nan`, which libconfig
+// rejects.
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Bool(val) => write!(f, "{}", val),
+            Value::Int(val) => write!(f, "{}", val),
+            Value::Int64(val) => write!(f, "{}L", val),
+            Value::Float(val) if val.is_infinite() => match val.is_sign_negative() {
+                true => f.write_str("-1e999"),
+                false => f.write_str("1e999"),
+            },
+            Value::Float(val) => write!(f, "{:?}", val),
+            Value::String(val) => {
+                f.write_str("\"")?;
+                for c in val.chars() {
+                    match c {
+                        '"' => f.write_str("\\\"")?,
+                        '\\' => f.write_str("\\\\")?,
+                        '\n' => f.write_str("\\n")?,
+                        '\r' => f.write_str("\\r")?,
+                        '\t' => f.write_str("\\t")?,
+                        '\x0c' => f.write_str("\\f")?,
+                        c => write!(f, "{}", c)?,
+                    }
+                }
+                f.write_str("\"")
+            }
+            Value::Group(children) => {
+                f.write_str("{")?;
+                for (name, child) in children {
+                    write!(f, " {} = {};", name, child)?;
+                }
+                f.write_str(" }")
+            }
+            Value::Array(elems) => write_elems(f, "[", elems, "]"),
+            Value::List(elems) => write_elems(f, "(", elems, ")"),
+        }
+    }
+}
+
+fn write_elems(
+    f: &mut fmt::Formatter<'_>,
+    open: &str,
+    elems: &[Value],
+    close: &str,
+) -> fmt::Result {
+    f.write_str(open)?;
+    for (idx, elem) in elems.iter().enumerate() {
+        match idx {
+            0 => write!(f, " {}", elem)?,
+            _ => write!(f, ", {}", elem)?,
+        }
+    }
+    f.write_str(" ")?;
+    f.write_str(close)
+}
+
+impl Config {
+    pub fn from_value(value: &Value) -> Result<Config, LibconfigError> {
+        if value.get_type() != Type::TypeGroup {
+            return Err(LibconfigError::TypeMismatch(String::new()));
+        }
+        let cfg = Config::new();
+        Snapshot::from_value(None, value).restore_children(&mut cfg.get_root())?;
+        Ok(cfg)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Config;
+
+    #[test]
+    fn ok_on_value_from_config() {
+        let mut cfg = Config::new();
+        assert_eq!(cfg.read_file("../input/test.cfg"), Ok(()));
+        let value = cfg.to_value().unwrap();
+        assert_eq!(value.get("val_int"), Some(&Value::Int(42)));
+        assert_eq!(value.get("val_u64"), Some(&Value::Int64(0xFFFFFFFFFF)));
+        assert_eq!(
+            value.get("outer").and_then(|outer| outer.get("inner")),
+            Some(&Value::Int(3))
+        );
+        assert_eq!(
+            value.get("arr").and_then(|arr| arr.get_elem(2)),
+            Some(&Value::Int(8))
+        );
+    }
+
+    #[test]
+    fn ok_on_value_display() {
+        let mut cfg = Config::new();
+        assert_eq!(
+            cfg.read_str("g = { s = \"a\\\"b\"; l = (1, 2L, [1.5]); };"),
+            Ok(())
+        );
+        let value = cfg.to_value().unwrap();
+        assert_eq!(
+            value.to_string(),
+            "{ g = { s = \"a\\\"b\"; l = ( 1, 2L, [ 1.5 ] ); }; }"
+        );
+        let mut reread = Config::new();
+        assert_eq!(reread.read_str(&format!("v = {};", value)), Ok(()));
+        assert_eq!(reread.to_value().unwrap().get("v"), Some(&value));
+    }
+
+    #[test]
+    fn ok_on_infinite_float_display() {
+        let value = Value::Array(vec![
+            Value::Float(f64::INFINITY),
+            Value::Float(-f64::INFINITY),
+        ]);
+        assert_eq!(value.to_string(), "[ 1e999, -1e999 ]");
+        let mut reread = Config::new();
+        assert_eq!(reread.read_str(&format!("v = {};", value)), Ok(()));
+        assert_eq!(reread.to_value().unwrap().get("v"), Some(&value));
+        assert!(reread
+            .read_str(&format!("v = {};", Value::Float(f64::NAN)))
+            .is_err());
+    }
+
+    #[test]
+    fn ok_on_config_from_value() {
+        let mut cfg = Config::new();
+        assert_eq!(cfg.read_file("../input/test.cfg"), Ok(()));
+        let value = cfg.to_value().unwrap();
+        assert_eq!(
+            Config::from_value(&value).unwrap().to_value().unwrap(),
+            value
+        );
+        assert!(Config::from_value(&Value::Int(1)).is_err());
+    }
+
+    #[test]
+    fn ok_on_list_value() {
+        let mut cfg = Config::new();
+        assert_eq!(
+            cfg.read_str("list = (1, \"two\", { three = 3.0; });"),
+            Ok(())
+        );
+        assert_eq!(
+            cfg.to_value().unwrap().get("list"),
+            Some(&Value::List(vec![
+                Value::Int(1),
+                Value::String(String::from("two")),
+                Value::Group(vec![(String::from("three"), Value::Float(3.0))]),
+            ]))
+        );
+    }
+}
@@ -0,0 +1,79 @@
+use crate::backend::{getVersionMajor, getVersionMinor, getVersionRevision};
+
+/// The (major, minor, revision) version of the linked libconfig, or with
+/// `pure-rust` of the release the Rust implementation follows.
+pub fn version() -> (u32, u32, u32) {
+    (getVersionMajor(), getVersionMinor(), getVersionRevision())
+}
+
+/// Features of libconfig that depend on its version and the backend.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Capabilities {
+    /// `get_float_precision`/`set_float_precision` take effect.
+    pub float_precision: bool,
+    /// `ConfigOption::OptionAllowScientificNotation` is honored.
+    pub scientific_notation: bool,
+    /// `ConfigOption::OptionFsync` is honored.
+    pub fsync: bool,
+    /// `ConfigOption::OptionAllowOverrides` is honored.
+    pub allow_overrides: bool,
+    /// An include hook, libconfig's `config_set_include_func` or libconfig++'s
+    /// `Config::evaluateIncludePath`, can resolve `@include` paths.
+    pub include_hook: bool,
+}
+
+impl Capabilities {
+    /// What libconfig `version` supports.
+    pub fn of(version: (u32, u32, u32)) -> Self {
+        let (major, minor, _) = version;
+        let at_least = |want: u32| major > 1 || (major == 1 && minor >= want);
+        Capabilities {
+            float_precision: at_least(6),
+            scientific_notation: at_least(6),
+            fsync: at_least(7),
+            allow_overrides: at_least(7),
+            include_hook: at_least(7),
+        }
+    }
+}
+
+/// What the backend in use supports: that of the linked libconfig, or with
+/// `pure-rust` what the Rust implementation has of the release it follows.
+pub fn capabilities() -> Capabilities {
+    let caps = Capabilities::of(version());
+    #[cfg(feature = "pure-rust")]
+    let caps = Capabilities {
+        include_hook: false,
+        ..caps
+    };
+    caps
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ok_on_capabilities_of() {
+        let caps = Capabilities::of((1, 5, 0));
+        assert!(!caps.float_precision);
+        assert!(!caps.scientific_notation);
+        assert!(!caps.fsync);
+        let caps = Capabilities::of((1, 6, 0));
+        assert!(caps.scientific_notation);
+        assert!(!caps.allow_overrides);
+        assert!(!caps.include_hook);
+        let caps = Capabilities::of((1, 7, 3));
+        assert!(caps.fsync);
+        assert!(caps.allow_overrides);
+        assert!(caps.include_hook);
+    }
+
+    #[cfg(feature = "pure-rust")]
+    #[test]
+    fn ok_on_pure_rust_capabilities() {
+        let caps = capabilities();
+        assert!(caps.fsync && caps.allow_overrides);
+        assert!(!caps.include_hook);
+    }
+}
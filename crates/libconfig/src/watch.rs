@@ -0,0 +1,405 @@
+use crate::{includes, Config, LibconfigError, Value};
+use std::collections::VecDeque;
+use std::fs;
+use std::mem;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, SystemTime};
+
+/// Checks a freshly parsed config before it replaces the current one.
+pub type Validator = Arc<dyn Fn(&Value) -> Result<(), LibconfigError> + Send + Sync>;
+
+/// Outcome of a reload as seen by subscribers.
+pub type Update = Result<Arc<Value>, LibconfigError>;
+
+#[derive(Clone)]
+pub struct WatchOptions {
+    pub include_dir: Option<String>,
+    pub validator: Option<Validator>,
+    /// How often the main file and its includes are checked for changes.
+    pub poll_interval: Duration,
+}
+
+impl Default for WatchOptions {
+    fn default() -> Self {
+        WatchOptions {
+            include_dir: None,
+            validator: None,
+            poll_interval: Duration::from_secs(1),
+        }
+    }
+}
+
+/// A config file that is reloaded whenever it or one of the files it
+/// `@include`s changes.
+///
+/// Readers get the latest valid config as an `Arc<Value>` snapshot. A reload
+/// that fails to parse or validate keeps the last good snapshot and reports
+/// the error to subscribers and through `last_error`.
+pub struct WatchedConfig {
+    shared: Arc<Shared>,
+    worker: Option<JoinHandle<()>>,
+    #[cfg(all(unix, feature = "signal"))]
+    hangup: Option<signal_hook::SigId>,
+}
+
+struct Shared {
+    path: String,
+    options: WatchOptions,
+    state: Mutex<State>,
+    // Held while loading, so that a slower reload cannot replace the
+    // snapshot and stamps of a newer one.
+    reloading: Mutex<()>,
+    subscribers: Mutex<Vec<Subscriber>>,
+    // Updates not yet delivered, in the order their reloads finished.
+    pending: Mutex<VecDeque<Update>>,
+    // Set while a thread delivers `pending`, so that subscribers see the
+    // updates in order.
+    notifying: AtomicBool,
+    stop: AtomicBool,
+    reload_requested: Arc<AtomicBool>,
+}
+
+struct State {
+    current: Arc<Value>,
+    stamps: Vec<(PathBuf, Option<Stamp>)>,
+    last_error: Option<LibconfigError>,
+}
+
+// Receives every update; returns false once it is no longer interested.
+pub(crate) type Subscriber = Box<dyn FnMut(&Update) -> bool + Send>;
+
+// Modification time and size, which together catch edits that land within
+// the file system's timestamp granularity.
+type Stamp = (Option<SystemTime>, u64);
+
+impl WatchedConfig {
+    /// Loads `path` and starts watching it. Fails if the initial load does.
+    pub fn new(path: &str, options: WatchOptions) -> Result<Self, LibconfigError> {
+        let files = tracked_files(path, options.include_dir.as_deref());
+        let stamps = stamp_all(&files);
+        let current = load(path, &options)?;
+        let shared = Arc::new(Shared {
+            path: path.to_string(),
+            options,
+            state: Mutex::new(State {
+                current,
+                stamps,
+                last_error: None,
+            }),
+            reloading: Mutex::new(()),
+            subscribers: Mutex::new(Vec::new()),
+            pending: Mutex::new(VecDeque::new()),
+            notifying: AtomicBool::new(false),
+            stop: AtomicBool::new(false),
+            reload_requested: Arc::new(AtomicBool::new(false)),
+        });
+        let worker = {
+            let shared = shared.clone();
+            thread::spawn(move || shared.poll())
+        };
+        Ok(WatchedConfig {
+            shared,
+            worker: Some(worker),
+            #[cfg(all(unix, feature = "signal"))]
+            hangup: None,
+        })
+    }
+
+    /// The latest config that parsed and validated.
+    pub fn current(&self) -> Arc<Value> {
+        self.shared.state.lock().unwrap().current.clone()
+    }
+
+    /// The error of the last reload, if it failed.
+    pub fn last_error(&self) -> Option<LibconfigError> {
+        self.shared.state.lock().unwrap().last_error.clone()
+    }
+
+    /// Reloads now, whether or not any file changed.
+    pub fn reload(&self) -> Result<Arc<Value>, LibconfigError> {
+        self.shared.reload()
+    }
+
+    /// Receives the outcome of every reload after this call.
+    pub fn subscribe(&self) -> Receiver<Update> {
+        let (sender, receiver) = mpsc::channel();
+        self.add_subscriber(Box::new(move |update| sender.send(update.clone()).is_ok()));
+        receiver
+    }
+
+    pub(crate) fn add_subscriber(&self, subscriber: Subscriber) {
+        self.shared.add_subscriber(subscriber);
+    }
+
+    /// Reloads on `SIGHUP`, at the next poll.
+    #[cfg(all(unix, feature = "signal"))]
+    pub fn reload_on_sighup(&mut self) -> Result<(), LibconfigError> {
+        if self.hangup.is_none() {
+            let id = signal_hook::flag::register(
+                signal_hook::consts::SIGHUP,
+                self.shared.reload_requested.clone(),
+            )
+            .map_err(|err| LibconfigError::Io(err.to_string()))?;
+            self.hangup = Some(id);
+        }
+        Ok(())
+    }
+}
+
+impl Drop for WatchedConfig {
+    fn drop(&mut self) {
+        #[cfg(all(unix, feature = "signal"))]
+        if let Some(id) = self.hangup.take() {
+            signal_hook::low_level::unregister(id);
+        }
+        self.shared.stop.store(true, Ordering::Relaxed);
+        if let Some(worker) = self.worker.take() {
+            worker.thread().unpark();
+            let _ = worker.join();
+        }
+    }
+}
+
+impl Shared {
+    fn poll(&self) {
+        loop {
+            thread::park_timeout(self.options.poll_interval);
+            if self.stop.load(Ordering::Relaxed) {
+                return;
+            }
+            if self.reload_requested.swap(false, Ordering::Relaxed) || self.changed() {
+                let _ = self.reload();
+            }
+        }
+    }
+
+    fn changed(&self) -> bool {
+        let state = self.state.lock().unwrap();
+        state
+            .stamps
+            .iter()
+            .any(|(path, stamp)| stamp_of(path) != *stamp)
+    }
+
+    fn reload(&self) -> Result<Arc<Value>, LibconfigError> {
+        let update = self.load();
+        self.notify();
+        update
+    }
+
+    fn load(&self) -> Update {
+        let _reloading = self.reloading.lock().unwrap();
+        // Stamped before parsing, so that edits made while parsing trigger
+        // another reload.
+        let files = tracked_files(&self.path, self.options.include_dir.as_deref());
+        let stamps = stamp_all(&files);
+        let update = load(&self.path, &self.options);
+        {
+            let mut state = self.state.lock().unwrap();
+            match &update {
+                Ok(value) => {
+                    state.current = value.clone();
+                    state.stamps = stamps;
+                    state.last_error = None;
+                }
+                Err(err) => {
+                    // Keep watching the files that made up the last good
+                    // config as well, in case the broken edit is reverted.
+                    let mut stamps = stamps;
+                    for (path, _) in &state.stamps {
+                        if !stamps.iter().any(|(known, _)| known == path) {
+                            stamps.push((path.clone(), stamp_of(path)));
+                        }
+                    }
+                    state.stamps = stamps;
+                    state.last_error = Some(err.clone());
+                }
+            }
+        }
+        self.pending.lock().unwrap().push_back(update.clone());
+        update
+    }
+
+    // Delivers the pending updates unless another thread already is; a
+    // reload started by a subscriber is queued behind the current update.
+    fn notify(&self) {
+        while !self.pending.lock().unwrap().is_empty() {
+            if self.notifying.swap(true, Ordering::Acquire) {
+                return;
+            }
+            loop {
+                let Some(update) = self.pending.lock().unwrap().pop_front() else {
+                    break;
+                };
+                // Called without any lock held, so that subscribers may
+                // reload or subscribe themselves.
+                let mut subscribers = mem::take(&mut *self.subscribers.lock().unwrap());
+                subscribers.retain_mut(|subscriber| subscriber(&update));
+                let mut current = self.subscribers.lock().unwrap();
+                subscribers.append(&mut current);
+                *current = subscribers;
+            }
+            self.notifying.store(false, Ordering::Release);
+        }
+    }
+
+    fn add_subscriber(&self, subscriber: Subscriber) {
+        self.subscribers.lock().unwrap().push(subscriber);
+    }
+}
+
+fn load(path: &str, options: &WatchOptions) -> Result<Arc<Value>, LibconfigError> {
+    let mut cfg = Config::new();
+    if let Some(dir) = &options.include_dir {
+        cfg.set_include_path(dir);
+    }
+    cfg.read_file(path)?;
+    let value = cfg.to_value()?;
+    if let Some(validator) = &options.validator {
+        validator(&value)?;
+    }
+    Ok(Arc::new(value))
+}
+
+// The main file followed by every file it includes, directly or not.
+fn tracked_files(path: &str, include_dir: Option<&str>) -> Vec<PathBuf> {
+    let mut files = vec![PathBuf::from(path)];
+    let text = fs::read_to_string(path).unwrap_or_default();
+    for include in includes::scan(&text, Some(Path::new(path)), include_dir) {
+        if !files.contains(&include.path) {
+            files.push(include.path);
+        }
+    }
+    files
+}
+
+fn stamp_of(path: &Path) -> Option<Stamp> {
+    fs::metadata(path)
+        .ok()
+        .map(|meta| (meta.modified().ok(), meta.len()))
+}
+
+fn stamp_all(files: &[PathBuf]) -> Vec<(PathBuf, Option<Stamp>)> {
+    files
+        .iter()
+        .map(|path| (path.clone(), stamp_of(path)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::scratch_dir;
+
+    fn options(dir: &Path) -> WatchOptions {
+        WatchOptions {
+            include_dir: Some(dir.to_str().unwrap().to_string()),
+            poll_interval: Duration::from_millis(10),
+            ..WatchOptions::default()
+        }
+    }
+
+    #[test]
+    fn ok_on_include_change() {
+        let dir = scratch_dir("watch-include");
+        fs::write(
+            dir.join("main.cfg"),
+            "@include \"port.cfg\"\nname = \"a\";\n",
+        )
+        .unwrap();
+        fs::write(dir.join("port.cfg"), "port = 80;\n").unwrap();
+        let main = dir.join("main.cfg");
+        let watched = WatchedConfig::new(main.to_str().unwrap(), options(&dir)).unwrap();
+        assert_eq!(watched.current().get("port"), Some(&Value::Int(80)));
+        let updates = watched.subscribe();
+        fs::write(dir.join("port.cfg"), "port = 8080;\n").unwrap();
+        let update = updates.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(update.unwrap().get("port"), Some(&Value::Int(8080)));
+        assert_eq!(watched.current().get("port"), Some(&Value::Int(8080)));
+        drop(watched);
+    }
+
+    #[test]
+    fn ok_on_invalid_edit_keeps_last_good() {
+        let dir = scratch_dir("watch-invalid");
+        let main = dir.join("main.cfg");
+        fs::write(&main, "port = 80;\n").unwrap();
+        let watched = WatchedConfig::new(main.to_str().unwrap(), options(&dir)).unwrap();
+        let updates = watched.subscribe();
+        fs::write(&main, "port = ;\n# broken\n").unwrap();
+        let update = updates.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert!(matches!(
+            update,
+            Err(LibconfigError::Syntax { line: 1, .. })
+        ));
+        assert_eq!(watched.current().get("port"), Some(&Value::Int(80)));
+        assert!(watched.last_error().is_some());
+        fs::write(&main, "port = 443;\n").unwrap();
+        let update = updates.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(update.unwrap().get("port"), Some(&Value::Int(443)));
+        assert_eq!(watched.last_error(), None);
+        drop(watched);
+    }
+
+    #[test]
+    fn err_on_rejected_by_validator() {
+        let dir = scratch_dir("watch-validator");
+        let main = dir.join("main.cfg");
+        fs::write(&main, "port = 80;\n").unwrap();
+        let validator: Validator = Arc::new(|value: &Value| match value.get("port") {
+            Some(Value::Int(port)) if *port > 0 => Ok(()),
+            _ => Err(LibconfigError::TestFailed(String::from("port"))),
+        });
+        let options = WatchOptions {
+            validator: Some(validator),
+            ..options(&dir)
+        };
+        let watched = WatchedConfig::new(main.to_str().unwrap(), options.clone()).unwrap();
+        fs::write(&main, "port = -1;\n").unwrap();
+        assert_eq!(
+            watched.reload(),
+            Err(LibconfigError::TestFailed(String::from("port")))
+        );
+        assert_eq!(watched.current().get("port"), Some(&Value::Int(80)));
+        drop(watched);
+        assert!(WatchedConfig::new(main.to_str().unwrap(), options).is_err());
+    }
+
+    #[test]
+    fn ok_on_subscriber_reloading_and_subscribing() {
+        let dir = scratch_dir("watch-reentrant");
+        let main = dir.join("main.cfg");
+        fs::write(&main, "port = 80;\n").unwrap();
+        let watched = WatchedConfig::new(main.to_str().unwrap(), options(&dir)).unwrap();
+        let shared = Arc::downgrade(&watched.shared);
+        let (sender, updates) = mpsc::channel();
+        let (late_sender, late_updates) = mpsc::channel();
+        let mut late_sender = Some(late_sender);
+        watched.add_subscriber(Box::new(move |update| {
+            if let (Some(shared), Some(late_sender)) = (shared.upgrade(), late_sender.take()) {
+                assert!(shared.reload().is_ok());
+                shared.add_subscriber(Box::new(move |update| {
+                    late_sender.send(update.clone()).is_ok()
+                }));
+            }
+            sender.send(update.clone()).is_ok()
+        }));
+
+        let (done, finished) = mpsc::channel();
+        let reloader = thread::spawn({
+            let shared = watched.shared.clone();
+            move || done.send(shared.reload()).unwrap()
+        });
+        let timeout = Duration::from_secs(5);
+        assert!(finished.recv_timeout(timeout).unwrap().is_ok());
+        reloader.join().unwrap();
+        assert!(updates.recv_timeout(timeout).unwrap().is_ok());
+        assert!(updates.recv_timeout(timeout).unwrap().is_ok());
+        assert!(late_updates.recv_timeout(timeout).unwrap().is_ok());
+        drop(watched);
+    }
+}